@@ -9,8 +9,14 @@ fn main() -> Result<()> {
         fs::create_dir_all(generated_dir)?;
     }
 
-    // Compile the original schema for the main program
-    prost_build::compile_protos(&["proto/person.proto"], &["proto"])?;
+    // Compile the original schema for the main program, keeping its descriptor
+    // set so --show-schema can print the compiled definition. Imported well-known
+    // types resolve to prost-types (FieldMask), and the wrappers (StringValue,
+    // Int32Value, ...) to `Option` of the Rust primitive, still encoded as messages
+    let out_dir = std::env::var("OUT_DIR").unwrap();
+    prost_build::Config::new()
+        .file_descriptor_set_path(Path::new(&out_dir).join("person_descriptor.bin"))
+        .compile_protos(&["proto/person.proto"], &["proto"])?;
     
     // Compile the evolved schema with an extern path to map it to a different module
     let mut evolved_config = prost_build::Config::new();
@@ -20,9 +26,62 @@ fn main() -> Result<()> {
     evolved_config.extern_path(".test", "crate::test_data::evolved");
     evolved_config.compile_protos(&["proto/person_evolved.proto"], &["proto"])?;
     
+    // The reduced schema (a removed, reserved field) goes next to it
+    let mut reduced_config = prost_build::Config::new();
+    reduced_config.out_dir("src/generated");
+    reduced_config.compile_protos(&["proto/person_reduced.proto"], &["proto"])?;
+    
+    // Cap'n Proto code for --with-capnp, generated by the capnp schema compiler
+    #[cfg(feature = "capnp")]
+    {
+        capnpc::CompilerCommand::new()
+            .src_prefix("proto")
+            .file("proto/person.capnp")
+            .run()
+            .map_err(|e| std::io::Error::other(e.to_string()))?;
+        println!("cargo:rerun-if-changed=proto/person.capnp");
+    }
+    
     // Tell cargo to rerun this build script if proto files change
     println!("cargo:rerun-if-changed=proto/person.proto");
     println!("cargo:rerun-if-changed=proto/person_evolved.proto");
+    println!("cargo:rerun-if-changed=proto/person_reduced.proto");
+    
+    // Record the resolved dependency and compiler versions for --version-info
+    println!("cargo:rerun-if-changed=Cargo.lock");
+    let lock = fs::read_to_string("Cargo.lock").unwrap_or_default();
+    for (name, var) in [("prost", "BUILD_PROST_VERSION"), ("serde_json", "BUILD_SERDE_JSON_VERSION"), ("flate2", "BUILD_FLATE2_VERSION")] {
+        let version = locked_version(&lock, name).unwrap_or_else(|| "unknown".to_string());
+        println!("cargo:rustc-env={}={}", var, version);
+    }
+    let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let rustc_version = std::process::Command::new(rustc)
+        .arg("--version")
+        .output()
+        .ok()
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|version| version.trim().to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=BUILD_RUSTC_VERSION={}", rustc_version);
     
     Ok(())
+}
+
+// The version of `name` this crate depends on, from Cargo.lock. A dependency
+// entry carries its version (`"prost 0.11.9"`) only when several versions of
+// the crate are in the lock file; otherwise the single package entry has it.
+fn locked_version(lock: &str, name: &str) -> Option<String> {
+    let packages: Vec<&str> = lock.split("[[package]]").collect();
+    let ours = packages.iter().find(|package| package.contains("name = \"protobuf-json-benchmark\""))?;
+    let dependency = ours.lines()
+        .map(|line| line.trim().trim_end_matches(',').trim_matches('"'))
+        .find(|dependency| *dependency == name || dependency.starts_with(&format!("{} ", name)))?;
+    if let Some((_, version)) = dependency.split_once(' ') {
+        return Some(version.to_string());
+    }
+    packages.iter()
+        .find(|package| package.contains(&format!("name = \"{}\"\n", name)))?
+        .lines()
+        .find_map(|line| line.strip_prefix("version = "))
+        .map(|version| version.trim_matches('"').to_string())
 }
\ No newline at end of file
@@ -0,0 +1,27 @@
+#!/usr/bin/env bash
+# Run the full suite once per global allocator and print each allocator's change
+# against the system allocator. Extra arguments are passed to every run, e.g.
+#   scripts/compare_allocators.sh --size 1000 --iterations 5000
+set -euo pipefail
+
+cd "$(dirname "$0")/.."
+out="${OUT_DIR:-target/allocators}"
+mkdir -p "$out"
+
+run() {
+    local name="$1" features="$2"
+    shift 2
+    echo "Running with $name..." >&2
+    # Progress goes to stderr; stdout is the line of flattened results
+    cargo run --release --quiet $features -- --flatten --no-color "$@" > "$out/$name.json"
+}
+
+run system "" "$@"
+run mimalloc "--features mimalloc" "$@"
+run jemalloc "--features jemalloc" "$@"
+
+for name in mimalloc jemalloc; do
+    echo
+    echo "system -> $name"
+    cargo run --release --quiet -- compare "$out/system.json" "$out/$name.json"
+done
@@ -0,0 +1,96 @@
+#[cfg(feature = "alloc-tracker")]
+use std::alloc::{GlobalAlloc, Layout};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[cfg(all(feature = "mimalloc", feature = "jemalloc"))]
+compile_error!("features `mimalloc` and `jemalloc` are mutually exclusive");
+
+// The allocator that actually serves requests, chosen by cargo feature
+#[cfg(feature = "mimalloc")]
+pub use mimalloc::MiMalloc as Backend;
+#[cfg(feature = "mimalloc")]
+pub const ALLOCATOR: &str = "mimalloc";
+
+#[cfg(all(feature = "jemalloc", not(feature = "mimalloc")))]
+pub use tikv_jemallocator::Jemalloc as Backend;
+#[cfg(all(feature = "jemalloc", not(feature = "mimalloc")))]
+pub const ALLOCATOR: &str = "jemalloc";
+
+#[cfg(not(any(feature = "mimalloc", feature = "jemalloc")))]
+pub use std::alloc::System as Backend;
+#[cfg(not(any(feature = "mimalloc", feature = "jemalloc")))]
+pub const ALLOCATOR: &str = "system";
+
+// Whether `CountingAlloc` is built in; without the `alloc-tracker` feature the
+// backend is installed directly and every counter reads 0
+pub const TRACKING: bool = cfg!(feature = "alloc-tracker");
+
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+static TOTAL: AtomicUsize = AtomicUsize::new(0);
+
+// Global allocator that forwards to the selected backend while tracking live heap
+// bytes, their high-water mark and the running total ever allocated
+#[cfg(feature = "alloc-tracker")]
+pub struct CountingAlloc;
+
+#[cfg(feature = "alloc-tracker")]
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = Backend.alloc(layout);
+        if !ptr.is_null() {
+            record_alloc(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = Backend.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            record_alloc(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        Backend.dealloc(ptr, layout);
+        CURRENT.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = Backend.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            CURRENT.fetch_sub(layout.size(), Ordering::Relaxed);
+            record_alloc(new_size);
+        }
+        new_ptr
+    }
+}
+
+#[cfg(feature = "alloc-tracker")]
+fn record_alloc(size: usize) {
+    TOTAL.fetch_add(size, Ordering::Relaxed);
+    let current = CURRENT.fetch_add(size, Ordering::Relaxed) + size;
+    PEAK.fetch_max(current, Ordering::Relaxed);
+}
+
+// Bytes currently allocated on the heap
+pub fn current_bytes() -> usize {
+    CURRENT.load(Ordering::Relaxed)
+}
+
+// Highest value of `current_bytes` since the last `reset_peak`
+pub fn peak_bytes() -> usize {
+    PEAK.load(Ordering::Relaxed)
+}
+
+// Bytes allocated since startup, counting memory that has since been freed; a
+// `realloc` counts its whole new size
+pub fn total_bytes() -> usize {
+    TOTAL.load(Ordering::Relaxed)
+}
+
+// Start a new high-water measurement from the current heap size
+pub fn reset_peak() {
+    PEAK.store(CURRENT.load(Ordering::Relaxed), Ordering::Relaxed);
+}
@@ -0,0 +1,95 @@
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+
+// A metric that got worse than the baseline by more than the allowed margin
+pub struct Regression {
+    pub metric: String,
+    pub baseline: f64,
+    pub current: f64,
+    // How much worse, as a percentage of the baseline value
+    pub change_percent: f64,
+}
+
+// How one metric changed between two runs
+pub struct Delta {
+    pub metric: String,
+    pub old: f64,
+    pub new: f64,
+    // Signed change relative to the old value, in percent
+    pub change_percent: f64,
+    // Whether the change is an improvement; `None` for metrics without a direction
+    pub improved: Option<bool>,
+}
+
+// Baselines are stored as the flattened results (see `flatten_results`)
+pub fn load_baseline(path: &str) -> Result<BTreeMap<String, Value>, String> {
+    let contents = fs::read_to_string(path).map_err(|e| format!("Failed to read baseline {}: {}", path, e))?;
+    serde_json::from_str(&contents).map_err(|e| format!("Failed to parse baseline {}: {}", path, e))
+}
+
+pub fn save_baseline(path: &str, flat: &BTreeMap<String, Value>) -> Result<(), String> {
+    let contents = serde_json::to_string_pretty(flat).unwrap();
+    fs::write(path, contents).map_err(|e| format!("Failed to write baseline {}: {}", path, e))
+}
+
+// Whether a larger value of this metric is an improvement; `None` for metrics that
+// aren't gated (relative differences, p-values, the volume of bytes processed)
+fn higher_is_better(metric: &str) -> Option<bool> {
+    if metric.contains(".difference.") || metric.starts_with("bytes_processed.") {
+        None
+    } else if metric.ends_with(".ops_per_sec") || metric.ends_with(".mb_per_sec") || metric.ends_with(".normalized")
+        || metric.ends_with(".ratio") {
+        Some(true)
+    } else if metric.ends_with(".ms") || metric.ends_with(".bytes") {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+// Every numeric metric present in both runs, in metric-name order
+pub fn diff(old: &BTreeMap<String, Value>, new: &BTreeMap<String, Value>) -> Vec<Delta> {
+    new.iter()
+        .filter_map(|(metric, new_value)| {
+            let old = old.get(metric)?.as_f64()?;
+            let new = new_value.as_f64()?;
+            let change_percent = if old == 0.0 { 0.0 } else { (new - old) / old * 100.0 };
+            let improved = higher_is_better(metric)
+                .filter(|_| new != old)
+                .map(|higher_is_better| (new > old) == higher_is_better);
+            Some(Delta { metric: metric.clone(), old, new, change_percent, improved })
+        })
+        .collect()
+}
+
+// Metrics present in both runs that are more than `max_regression_percent` worse
+pub fn find_regressions(
+    baseline: &BTreeMap<String, Value>,
+    current: &BTreeMap<String, Value>,
+    max_regression_percent: f64,
+) -> Vec<Regression> {
+    let mut regressions = Vec::new();
+
+    for (metric, current_value) in current {
+        let Some(higher_is_better) = higher_is_better(metric) else { continue };
+        let (Some(baseline), Some(current)) = (baseline.get(metric).and_then(Value::as_f64), current_value.as_f64())
+        else {
+            continue;
+        };
+        if baseline == 0.0 {
+            continue;
+        }
+
+        let change_percent = if higher_is_better {
+            (baseline - current) / baseline * 100.0
+        } else {
+            (current - baseline) / baseline * 100.0
+        };
+        if change_percent > max_regression_percent {
+            regressions.push(Regression { metric: metric.clone(), baseline, current, change_percent });
+        }
+    }
+
+    regressions
+}
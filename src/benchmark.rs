@@ -1,17 +1,53 @@
-use crate::test_data::{generate_test_data, generate_evolved_test_data, JsonPerson, evolved};
+use crate::alloc_tracker;
+use crate::calibration;
+use crate::codec::{CanonicalJson, Codec};
+use crate::compression::{train_zstd_dictionary, zstd_dictionary_codec, Compressor};
+use crate::config::{run_id, BenchConfig};
+use crate::formats::{Decoded, Format};
+use crate::stats;
+use crate::test_data::{generate_test_data_with, generate_test_data_pool, TestMessage, build_json_person, build_proto_person, generate_evolved_test_data, DataOptions,
+                       generate_optional_test_data, wrap_profiles, generate_map_test_data, generate_int_map_test_data, generate_id_test_data, generate_large_int_test_data, generate_wide_number_json,
+                       generate_partial_update, generate_small_number_test_data, generate_int_value_test_data, generate_money_test_data, generate_string_id_test_data, parse_money, generate_double_array_test_data, generate_shared_address_test_data, JsonIdList, TextCharset, JsonAddress, JsonMoney, JsonMoneyString, JsonDoubleArray, JsonFloatId, JsonLargeInts, JsonPerson, JsonPersonInt64Id, JsonPersonStringId, JsonPersonOpen, JsonPersonShared, JsonPersonShortKeys, JsonPhoneNumber, JsonProfile, JsonProfileOmitted, evolved, reduced};
+use crate::test_data;
+use bytes::buf::UninitSlice;
+use bytes::BufMut;
 use colored::*;
+use flate2::read::GzDecoder;
 use flate2::write::GzEncoder;
 use flate2::Compression;
-use prettytable::{Table, row};
+use prettytable::{Cell, Row, Table, row};
+use prost::encoding;
 use prost::Message;
-use serde_json;
+use schemars::JsonSchema;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::{BTreeMap, HashMap};
 use std::time::{Duration, Instant};
-use std::io::Write;
+use std::fmt;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Read, Seek, Write};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
 
 // Include the generated Protocol Buffers code
 include!(concat!(env!("OUT_DIR"), "/test.rs"));
 
+#[derive(Serialize, Deserialize, JsonSchema, Clone)]
 pub struct BenchmarkResults {
+    // Stable hash of the run configuration, see `config::run_id`
+    pub run_id: String,
+    // Order the tests ran in, and the shuffle seed when it wasn't the default order
+    pub test_order: Vec<String>,
+    pub order_seed: Option<u64>,
+    // Worker threads of the tokio runtime
+    pub worker_threads: usize,
+    // Global allocator the binary was built with (see the `mimalloc`/`jemalloc` features)
+    pub allocator: String,
     pub serialization: BenchmarkMetric,
     pub deserialization: BenchmarkMetric,
     pub payload_size: PayloadSizeMetric,
@@ -22,39 +58,947 @@ pub struct BenchmarkResults {
     pub parser_init: BenchmarkMetric,
     pub throughput: ThroughputMetric,
     pub schema_evolution: SchemaEvolutionMetric,
+    pub bytes_processed: AggregateThroughput,
+    // Every table metric across --runs repetitions, whose means the metrics above
+    // hold; empty for a single run
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub runs: Vec<MetricRuns>,
 }
 
+// Samples of one value across --runs repetitions of the suite
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, Default)]
+pub struct RunAggregate {
+    pub samples: Vec<f64>,
+}
+
+impl RunAggregate {
+    pub fn mean(&self) -> f64 {
+        stats::mean(&self.samples)
+    }
+
+    // Sample standard deviation; 0 for a single run
+    pub fn std_dev(&self) -> f64 {
+        if self.samples.len() < 2 { 0.0 } else { stats::variance(&self.samples).sqrt() }
+    }
+
+    // Standard deviation as a percentage of the mean
+    pub fn coefficient_of_variation(&self) -> f64 {
+        self.std_dev() / self.mean().abs() * 100.0
+    }
+}
+
+// One table metric's JSON and protobuf values across runs
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug)]
+pub struct MetricRuns {
+    pub test: String,
+    pub json: RunAggregate,
+    pub protobuf: RunAggregate,
+}
+
+// Serialized bytes produced or consumed across the run, and the time spent on them
+#[derive(Clone, Debug, Default)]
+struct ByteVolume {
+    json_bytes: f64,
+    protobuf_bytes: f64,
+    json_ms: f64,
+    protobuf_ms: f64,
+}
+
+// Headline totals over every test that reports its byte volume
+#[derive(Serialize, Deserialize, JsonSchema, Clone)]
+pub struct AggregateThroughput {
+    pub json_bytes: u64,
+    pub protobuf_bytes: u64,
+    pub json_mb_per_sec: f64,
+    pub protobuf_mb_per_sec: f64,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Clone)]
 pub struct BenchmarkMetric {
     pub json: f64,
     pub protobuf: f64,
-    pub difference_percent: f64,
+    // json / protobuf: 1.0 is a tie, and whether above 1 is good depends on the metric
+    pub ratio: f64,
+    // How much better the winner's value is than the runner-up's, in percent (0 = tie)
+    pub percent_faster: f64,
+    // The best of JSON, protobuf and the additional codecs
     pub winner: String,
+    pub status: TestStatus,
+    // Welch's t-test p-value for tests that keep per-iteration samples; the winner is
+    // "Inconclusive" when it isn't below SIGNIFICANCE_LEVEL
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub p_value: Option<f64>,
+    // Heap high-water mark above the starting heap size, for tests that track it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub peak_memory: Option<PeakMemory>,
+    // The two parts of a simulated network time, for the network transfer test
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub network: Option<NetworkComponents>,
+    // Results for additional codecs (e.g. postcard), keyed by codec name
+    pub others: Vec<(String, f64)>,
+}
+
+// How much better the winning value is than the losing one, in percent, in either
+// direction: 2 ms vs 3 ms and 3 vs 2 ops/s are both 50%
+fn percent_faster(a: f64, b: f64) -> f64 {
+    (a.max(b) / a.min(b) - 1.0) * 100.0
+}
+
+// JSON, protobuf and the additional codecs, best first. Formats without a value
+// (NaN) are left out; ties go to protobuf, then JSON, then the codecs in order.
+fn rank_formats(json: f64, protobuf: f64, others: &[(String, f64)], lower_is_better: bool) -> Vec<(String, f64)> {
+    let mut ranked = vec![("Protobuf".to_string(), protobuf), ("JSON".to_string(), json)];
+    ranked.extend(others.iter().cloned());
+    ranked.retain(|(_, value)| !value.is_nan());
+    // Stable, so tied formats keep the order above
+    ranked.sort_by(|a, b| if lower_is_better { a.1.total_cmp(&b.1) } else { b.1.total_cmp(&a.1) });
+    ranked
+}
+
+// The first format of a ranking and how much better it is than the second, in
+// percent; NaN when only one format was measured and "-" when none was
+fn winner_and_margin(ranked: &[(String, f64)]) -> (String, f64) {
+    match ranked {
+        [] => ("-".to_string(), f64::NAN),
+        [(winner, _)] => (winner.clone(), f64::NAN),
+        [(winner, best), (_, runner_up), ..] => (winner.clone(), percent_faster(*best, *runner_up)),
+    }
+}
+
+impl BenchmarkMetric {
+    // Compare two measurements where the smaller value wins (times, sizes)
+    fn lower_is_better(json: f64, protobuf: f64) -> Self {
+        let mut metric = BenchmarkMetric {
+            json,
+            protobuf,
+            ratio: json / protobuf,
+            percent_faster: f64::NAN,
+            winner: String::new(),
+            status: TestStatus::Completed,
+            p_value: None,
+            peak_memory: None,
+            network: None,
+            others: Vec::new(),
+        };
+        metric.rank(true);
+        metric
+    }
+
+    // Add the additional codecs' values and pick the winner among all formats
+    fn with_others(mut self, others: Vec<(String, f64)>, lower_is_better: bool) -> Self {
+        self.others = others;
+        self.rank(lower_is_better);
+        self
+    }
+
+    // Set the winner and margin from the current values, see `rank_formats`. When
+    // JSON and protobuf are the top two and the t-test found no significant
+    // difference between them, the winner is "Inconclusive".
+    fn rank(&mut self, lower_is_better: bool) {
+        let ranked = rank_formats(self.json, self.protobuf, &self.others, lower_is_better);
+        (self.winner, self.percent_faster) = winner_and_margin(&ranked);
+        let top_two_are_json_and_protobuf = ranked.len() >= 2
+            && ranked[..2].iter().all(|(name, _)| name == "JSON" || name == "Protobuf");
+        if top_two_are_json_and_protobuf && self.p_value.is_some_and(|p_value| p_value >= SIGNIFICANCE_LEVEL) {
+            self.winner = "Inconclusive".to_string();
+        }
+    }
+
+    // Replace the values with new (e.g. averaged) ones, updating the ratio, margin
+    // and winner. The p-value belonged to the old values, so it and an Inconclusive
+    // verdict are dropped; a "-" winner (nothing measured) is kept.
+    fn set_values(&mut self, json: f64, protobuf: f64, lower_is_better: bool) {
+        self.json = json;
+        self.protobuf = protobuf;
+        self.ratio = json / protobuf;
+        self.p_value = None;
+        if self.winner != "-" {
+            self.rank(lower_is_better);
+        }
+    }
+}
+
+// None for a format left out of --formats
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Copy, Debug)]
+pub struct PeakMemory {
+    pub json_bytes: Option<usize>,
+    pub protobuf_bytes: Option<usize>,
+}
+
+// Distribution of per-iteration timings, in milliseconds
+#[derive(Clone, Copy, Debug)]
+pub struct Percentiles {
+    pub min: f64,
+    pub p50: f64,
+    pub p95: f64,
+    pub p99: f64,
+    pub max: f64,
+    pub std_dev: f64,
+}
+
+// Nearest-rank percentiles of `samples` (at least 2)
+fn percentiles(samples: &[f64]) -> Percentiles {
+    let mut sorted = samples.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let rank = |p: f64| sorted[((p / 100.0 * sorted.len() as f64).ceil() as usize).clamp(1, sorted.len()) - 1];
+    Percentiles {
+        min: sorted[0],
+        p50: rank(50.0),
+        p95: rank(95.0),
+        p99: rank(99.0),
+        max: sorted[sorted.len() - 1],
+        std_dev: stats::variance(samples).sqrt(),
+    }
+}
+
+// Median cost of the `Instant::now` / `elapsed` pair around every sample, in milliseconds
+fn timer_overhead_ms() -> f64 {
+    let mut reads: Vec<f64> = (0..1000)
+        .map(|_| {
+            let start = Instant::now();
+            start.elapsed().as_secs_f64() * 1000.0
+        })
+        .collect();
+    reads.sort_by(|a, b| a.total_cmp(b));
+    reads[reads.len() / 2]
+}
+
+// Simulated network time split into its components; total = transmission + base latency
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Copy, Debug)]
+pub struct NetworkComponents {
+    // Payload size over the simulated bandwidth
+    pub json_transmission_ms: f64,
+    pub protobuf_transmission_ms: f64,
+    // Fixed per-request latency, the same for both formats
+    pub base_latency_ms: f64,
+}
+
+// Whether a test ran to completion or was cut off by --per-test-timeout-secs
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum TestStatus {
+    Completed,
+    Timeout,
+    // Not run because a format it compares wasn't selected (--formats)
+    Skipped,
+}
+
+// Placeholder results for a test that timed out
+pub trait TimedOut {
+    fn timed_out() -> Self;
+}
+
+impl TimedOut for BenchmarkMetric {
+    fn timed_out() -> Self {
+        BenchmarkMetric {
+            json: f64::NAN,
+            protobuf: f64::NAN,
+            ratio: f64::NAN,
+            percent_faster: f64::NAN,
+            winner: "TIMEOUT".to_string(),
+            status: TestStatus::Timeout,
+            p_value: None,
+            peak_memory: None,
+            network: None,
+            others: Vec::new(),
+        }
+    }
+}
+
+impl TimedOut for PayloadSizeMetric {
+    fn timed_out() -> Self {
+        PayloadSizeMetric {
+            uncompressed: BenchmarkMetric::timed_out(),
+            compressed: BenchmarkMetric::timed_out(),
+            zstd_compressed: BenchmarkMetric::timed_out(),
+            json_compress_ms: f64::NAN,
+            proto_compress_ms: f64::NAN,
+            json_ratio: f64::NAN,
+            proto_ratio: f64::NAN,
+            gzip_levels: Vec::new(),
+            integer_encoding: IntegerEncodingMetric::default(),
+        }
+    }
+}
+
+impl TimedOut for ThroughputMetric {
+    fn timed_out() -> Self {
+        ThroughputMetric {
+            json: f64::NAN,
+            protobuf: f64::NAN,
+            ratio: f64::NAN,
+            percent_faster: f64::NAN,
+            winner: "TIMEOUT".to_string(),
+            status: TestStatus::Timeout,
+            normalized: None,
+            parallel: None,
+            others: Vec::new(),
+        }
+    }
 }
 
+impl TimedOut for SchemaEvolutionMetric {
+    fn timed_out() -> Self {
+        SchemaEvolutionMetric {
+            json: f64::NAN,
+            protobuf_backwards: f64::NAN,
+            protobuf_forwards: f64::NAN,
+            protobuf_average: f64::NAN,
+            winner: "TIMEOUT".to_string(),
+            status: TestStatus::Timeout,
+            decode_cost: EvolvedDecodeCost {
+                basic_ms: f64::NAN,
+                evolved_ms: f64::NAN,
+                delta_ms: f64::NAN,
+                added_values: 0,
+                added_bytes: 0,
+                per_value_ns: f64::NAN,
+            },
+            removed_field: RemovedFieldDecode {
+                decoded: false,
+                ms: f64::NAN,
+                dropped_bytes: 0,
+            },
+        }
+    }
+}
+
+impl SchemaEvolutionMetric {
+    // Placeholder results when JSON or protobuf wasn't selected
+    fn skipped() -> Self {
+        SchemaEvolutionMetric {
+            winner: "-".to_string(),
+            status: TestStatus::Skipped,
+            ..Self::timed_out()
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Clone)]
 pub struct PayloadSizeMetric {
     pub uncompressed: BenchmarkMetric,
     pub compressed: BenchmarkMetric,
+    // zstd at --zstd-level; shown, but not counted towards the overall winner
+    pub zstd_compressed: BenchmarkMetric,
+    // Time to gzip the already-serialized bytes at the default level, per op
+    pub json_compress_ms: f64,
+    pub proto_compress_ms: f64,
+    // Uncompressed / gzip-compressed size
+    pub json_ratio: f64,
+    pub proto_ratio: f64,
+    pub gzip_levels: Vec<CompressionLevelMetric>,
+    pub integer_encoding: IntegerEncodingMetric,
+}
+
+// Encoded size of `--size` records of large integers, with the protobuf fields
+// declared as varints (int64/uint64) and as fixed64/sfixed64
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Default)]
+pub struct IntegerEncodingMetric {
+    pub records: usize,
+    pub json_bytes: usize,
+    pub varint_bytes: usize,
+    pub fixed_bytes: usize,
+}
+
+// Compressed size and time for one gzip level
+#[derive(Serialize, Deserialize, JsonSchema, Clone)]
+pub struct CompressionLevelMetric {
+    pub level: u32,
+    pub json_bytes: usize,
+    pub protobuf_bytes: usize,
+    pub json_ms: f64,
+    pub protobuf_ms: f64,
 }
 
+#[derive(Serialize, Deserialize, JsonSchema, Clone)]
 pub struct ThroughputMetric {
     pub json: f64,
     pub protobuf: f64,
-    pub difference_percent: f64,
+    // json / protobuf: 1.0 is a tie, and whether above 1 is good depends on the metric
+    pub ratio: f64,
+    // How much better the winner's value is than the runner-up's, in percent (0 = tie)
+    pub percent_faster: f64,
+    // The best of JSON, protobuf and the additional codecs
     pub winner: String,
+    pub status: TestStatus,
+    // Present with --normalize, see `calibration::normalize`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub normalized: Option<NormalizedThroughput>,
+    // Present with --producers above 1
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parallel: Option<ParallelThroughput>,
+    // Ops per second of additional codecs, keyed by codec name
+    pub others: Vec<(String, f64)>,
 }
 
+impl ThroughputMetric {
+    // Set the winner and margin from the current values; higher is better
+    fn rank(&mut self) {
+        (self.winner, self.percent_faster) = winner_and_margin(&rank_formats(self.json, self.protobuf, &self.others, false));
+    }
+}
+
+// Aggregate throughput of several threads serializing and deserializing at once
+#[derive(Serialize, Deserialize, JsonSchema, Clone)]
+pub struct ParallelThroughput {
+    pub producers: usize,
+    // Sum of all threads' ops per second
+    pub json: f64,
+    pub protobuf: f64,
+    // Jain's fairness index over the per-thread op counts: 1.0 when every thread
+    // got the same share, down to 1/producers when one thread did all the work
+    pub json_fairness: f64,
+    pub protobuf_fairness: f64,
+}
+
+// Throughput relative to a calibration workload, comparable across machines
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Copy)]
+pub struct NormalizedThroughput {
+    // Time of one calibration op on this machine
+    pub calibration_ns: f64,
+    pub json: f64,
+    pub protobuf: f64,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Clone)]
 pub struct SchemaEvolutionMetric {
     pub json: f64,
     pub protobuf_backwards: f64,
     pub protobuf_forwards: f64,
     pub protobuf_average: f64,
     pub winner: String,
+    pub status: TestStatus,
+    pub decode_cost: EvolvedDecodeCost,
+    pub removed_field: RemovedFieldDecode,
+}
+
+// Old data read by a later schema that removed `email` and reserved its number
+#[derive(Serialize, Deserialize, JsonSchema, Clone)]
+pub struct RemovedFieldDecode {
+    // Whether the decode succeeded with every remaining field intact
+    pub decoded: bool,
+    pub ms: f64,
+    // Bytes of the removed field that a decode and re-encode leaves out
+    pub dropped_bytes: usize,
+}
+
+// Plain prost decode of the same record under the basic and the evolved schema,
+// isolating what the added fields (`is_primary`, `additional_info`,
+// `additional_field`, `priority`) cost at decode time
+#[derive(Serialize, Deserialize, JsonSchema, Clone)]
+pub struct EvolvedDecodeCost {
+    pub basic_ms: f64,
+    pub evolved_ms: f64,
+    pub delta_ms: f64,
+    // New-field values present on the wire; proto3 skips defaults such as `is_primary: false`
+    pub added_values: usize,
+    pub added_bytes: usize,
+    // `delta_ms` spread over the added values, in nanoseconds
+    pub per_value_ns: f64,
+}
+
+pub struct OptionalFieldsMetric {
+    pub null_fraction: f64,
+    // `others` holds the wrapper-type (`StringValue` etc.) variant of the protobuf side
+    pub payload_size: BenchmarkMetric,
+    pub serialization: BenchmarkMetric,
+    pub deserialization: BenchmarkMetric,
+}
+
+// Raw and gzip sizes of one batch of profiles at a given null fraction
+pub struct SparsityRow {
+    pub null_fraction: f64,
+    // Absent fields written as explicit `null`
+    pub json_nulls: usize,
+    pub json_nulls_gzip: usize,
+    // Absent fields left out of the object
+    pub json_omitted: usize,
+    pub json_omitted_gzip: usize,
+    pub protobuf: usize,
+    pub protobuf_gzip: usize,
+}
+
+// How the share of absent optional fields affects compressed size
+pub struct SparsityMetric {
+    pub records: usize,
+    pub rows: Vec<SparsityRow>,
+}
+
+// Per-op serialization time into one kind of destination
+pub struct SinkRow {
+    pub sink: &'static str,
+    pub json: f64,
+    // None where protobuf has no such sink (it can't produce a String)
+    pub protobuf: Option<f64>,
+}
+
+// Gateway-style conversion: decode one format, encode the other
+pub struct TranscodingMetric {
+    // Milliseconds per conversion
+    pub json_to_protobuf: f64,
+    pub protobuf_to_json: f64,
+}
+
+// A batch of records loaded with --input, timed as a whole
+pub struct InputBatchMetric {
+    pub records: usize,
+    // Milliseconds per pass over the batch, and total encoded bytes
+    pub serialization: BenchmarkMetric,
+    pub deserialization: BenchmarkMetric,
+    pub payload_size: BenchmarkMetric,
+}
+
+// A file of length-delimited protobuf records against the same records as NDJSON
+pub struct RecordsFileMetric {
+    pub records: usize,
+    // Milliseconds per pass over the whole file, and file bytes
+    pub encode: BenchmarkMetric,
+    pub decode: BenchmarkMetric,
+    pub file_size: BenchmarkMetric,
+}
+
+// Cost of byte-for-byte deterministic output, which signing and hashing need
+pub struct DeterminismMetric {
+    // Milliseconds per op for the default encoders
+    pub default: BenchmarkMetric,
+    // Canonical JSON (sorted keys) and protobuf with map entries sorted by key
+    pub deterministic: BenchmarkMetric,
+    // Whether the default encoders wrote equal records with differently ordered
+    // maps as identical bytes
+    pub json_default_stable: bool,
+    pub protobuf_default_stable: bool,
+}
+
+// Per-op time of one test at two iteration counts (--compare-iterations)
+pub struct IterationStability {
+    pub test: &'static str,
+    // Milliseconds per op at the low and the high iteration count
+    pub json: (f64, f64),
+    pub protobuf: (f64, f64),
+    // Larger over smaller per-op time of the format that moved most; 1.0 is stable
+    pub ratio: f64,
+}
+
+// A test run reduced to its per-op (JSON, protobuf) milliseconds
+type PerOpTest = fn(&PerformanceTester) -> (f64, f64);
+
+// Encoded size of one record for every format and compressor
+pub struct CompressionMatrix {
+    pub compressors: Vec<&'static str>,
+    // (format, size in bytes per compressor, in the order of `compressors`)
+    pub rows: Vec<(String, Vec<usize>)>,
+}
+
+// A single `repeated double`: JSON array vs protobuf packed encoding
+pub struct DoubleArrayMetric {
+    pub values: usize,
+    pub payload_size: BenchmarkMetric,
+    pub serialization: BenchmarkMetric,
+    pub deserialization: BenchmarkMetric,
+}
+
+// Smallest --size from which protobuf beats JSON on one metric
+pub struct Crossover {
+    pub metric: &'static str,
+    // None if JSON still wins (or ties) at the largest size swept
+    pub size: Option<usize>,
+}
+
+// How much of JSON's extra size is field names
+pub struct ShortKeysMetric {
+    // Uncompressed bytes
+    pub json: usize,
+    pub json_short_keys: usize,
+    pub protobuf: usize,
+    // Share of the JSON-to-protobuf size gap closed by shortening keys, in percent
+    pub gap_closed_percent: f64,
+    pub compressed_gap_closed_percent: f64,
+}
+
+// Records sharing addresses through `Arc` versus fully materialized copies
+pub struct SharedDataMetric {
+    pub records: usize,
+    pub distinct_addresses: usize,
+    // Heap bytes held by each in-memory collection; None without the heap tracker
+    pub materialized_heap: Option<usize>,
+    pub shared_heap: Option<usize>,
+    // Total encoded bytes, the same for both representations
+    pub json_size: usize,
+    pub protobuf_size: usize,
+    // Milliseconds to serialize the whole collection
+    pub json_materialized: f64,
+    pub json_shared: f64,
+    pub protobuf_materialized: f64,
+    // Includes copying the shared addresses into owned prost messages
+    pub protobuf_shared: f64,
+}
+
+// Teardown cost of a collection of decoded records
+pub struct DropCostMetric {
+    pub objects: usize,
+    // Milliseconds to drop the whole collection (median round)
+    pub time: BenchmarkMetric,
+    // Heap bytes released by the drop; None without the heap tracker
+    pub json_freed_bytes: Option<usize>,
+    pub protobuf_freed_bytes: Option<usize>,
+}
+
+// A standalone string map: JSON object vs protobuf map field
+pub struct MapSerializationMetric {
+    pub entries: usize,
+    pub payload_size: BenchmarkMetric,
+    pub serialization: BenchmarkMetric,
+    pub deserialization: BenchmarkMetric,
+}
+
+// Binary IDs as protobuf `string` (hex), protobuf `bytes` and base64 JSON
+pub struct BinaryFieldsMetric {
+    pub ids: usize,
+    pub json_size: usize,
+    pub protobuf_string_size: usize,
+    pub protobuf_bytes_size: usize,
+    // Decode time per op in milliseconds
+    pub json_decode: f64,
+    pub protobuf_string_decode: f64,
+    pub protobuf_bytes_decode: f64,
+}
+
+// A partial update as a JSON Merge Patch vs a protobuf message with a FieldMask
+pub struct PartialUpdateMetric {
+    // Size of the full record, for scale
+    pub full_json_size: usize,
+    pub full_protobuf_size: usize,
+    pub payload_size: BenchmarkMetric,
+    pub serialization: BenchmarkMetric,
+    // Decode the patch and apply it to a copy of the stored record
+    pub apply: BenchmarkMetric,
+}
+
+// How a decoder handled randomly corrupted input
+#[derive(Default)]
+pub struct DecodeOutcomes {
+    // Returned an error
+    pub rejected: usize,
+    // Decoded to some value, possibly different from the original
+    pub accepted: usize,
+    pub panicked: usize,
+}
+
+impl DecodeOutcomes {
+    fn record<T, E>(&mut self, outcome: std::thread::Result<Result<T, E>>) {
+        match outcome {
+            Ok(Err(_)) => self.rejected += 1,
+            Ok(Ok(_)) => self.accepted += 1,
+            Err(_) => self.panicked += 1,
+        }
+    }
+}
+
+// Decoder behaviour on mutated copies of a valid record
+pub struct FuzzMetric {
+    pub mutations: usize,
+    pub json: DecodeOutcomes,
+    pub protobuf: DecodeOutcomes,
+}
+
+// Round-trip (decode with the original schema, re-encode) of evolved data
+pub struct UnknownFieldsMetric {
+    pub json_retain: f64,
+    pub json_drop: f64,
+    pub protobuf_drop: f64,
+    // prost behind `RetainingPerson`, which keeps unknown fields as raw bytes
+    pub protobuf_retain: f64,
+    // Share of the evolved payload that survives the round trip, in percent
+    pub json_retain_fidelity: f64,
+    pub json_drop_fidelity: f64,
+    pub protobuf_fidelity: f64,
+    pub protobuf_retain_fidelity: f64,
+    // Whether the round-tripped data still decodes to the original evolved record
+    pub json_retain_lossless: bool,
+    pub json_drop_lossless: bool,
+    pub protobuf_lossless: bool,
+    pub protobuf_retain_lossless: bool,
+}
+
+// Serialize-then-compress versus serializing straight into the gzip stream
+pub struct StreamCompressionMetric {
+    pub two_step: BenchmarkMetric,
+    pub one_pass: BenchmarkMetric,
+}
+
+// Decoding a stream one message at a time, with the heap high-water mark above
+// the pre-test baseline; a bounded peak shows memory doesn't grow with the input.
+// The peak is None without the `alloc-tracker` feature.
+pub struct StreamingDecodeMetric {
+    pub messages: usize,
+    pub json_input_bytes: u64,
+    pub protobuf_input_bytes: u64,
+    pub time: BenchmarkMetric,
+    pub peak_memory: Option<BenchmarkMetric>,
+}
+
+// Stream decode feeding a bounded channel drained by a slow consumer
+pub struct BackpressureMetric {
+    pub messages: usize,
+    pub buffer: usize,
+    // Sustained messages per second through the whole pipeline
+    pub json_msgs_per_sec: f64,
+    pub protobuf_msgs_per_sec: f64,
+    // Time the decoding producer spent blocked on a full channel
+    pub json_send_wait_ms: f64,
+    pub protobuf_send_wait_ms: f64,
+}
+
+// CRC32 of the serialized payload, as a pipeline that checksums what it sends
+pub struct ChecksumMetric {
+    pub json_bytes: usize,
+    pub protobuf_bytes: usize,
+    // Milliseconds for the checksum alone
+    pub json_checksum_ms: f64,
+    pub protobuf_checksum_ms: f64,
+    // Serialization plus checksum, per op
+    pub serialize_and_checksum: BenchmarkMetric,
+}
+
+// Records whose text mixes in non-ASCII characters (--charset)
+pub struct CharsetMetric {
+    pub charset: TextCharset,
+    pub json_bytes: usize,
+    // The same JSON with every non-ASCII character written as a `\uXXXX` escape
+    pub escaped_json_bytes: usize,
+    pub protobuf_bytes: usize,
+    pub serialization: BenchmarkMetric,
+    pub deserialization: BenchmarkMetric,
+    // Whether every decoded string equals the original
+    pub json_round_trip: bool,
+    pub escaped_json_round_trip: bool,
+    pub protobuf_round_trip: bool,
+}
+
+// One representation of the same values (monetary amounts, 64-bit IDs)
+pub struct RepresentationRow {
+    pub representation: &'static str,
+    pub bytes: usize,
+    // Milliseconds per list of `--size` values
+    pub serialize_ms: f64,
+    pub deserialize_ms: f64,
+    // Share of values that decode to exactly the original
+    pub exact_percent: f64,
+}
+
+// Per-message compression of small records, with and without a zstd dictionary
+// trained on other records of the same format
+pub struct DictionaryCompressionMetric {
+    // Held-out records compressed one at a time
+    pub records: usize,
+    pub rows: Vec<DictionaryRow>,
+}
+
+pub struct DictionaryRow {
+    pub format: &'static str,
+    pub dictionary_bytes: usize,
+    // Average bytes per message
+    pub raw: f64,
+    pub gzip: f64,
+    pub zstd: f64,
+    pub zstd_dictionary: f64,
+    // Milliseconds to compress one message with the dictionary
+    pub zstd_dictionary_ms: f64,
+}
+
+// Encoded size and time of a one-integer message at one magnitude
+pub struct VarintPoint {
+    pub value: i64,
+    // The number alone: varint length versus decimal digits (with any sign)
+    pub varint_bytes: usize,
+    pub decimal_bytes: usize,
+    // Whole message: a 1-byte tag plus the varint, versus `{"value":N}`
+    pub protobuf_bytes: usize,
+    pub json_bytes: usize,
+    // Nanoseconds per encode
+    pub protobuf_ns: f64,
+    pub json_ns: f64,
+}
+
+// Parsing numbers into `serde_json::Value`, whose precision depends on whether
+// serde_json's `arbitrary_precision` feature is enabled in this build
+pub struct NumberPrecisionMetric {
+    pub arbitrary_precision: bool,
+    // Milliseconds per document of `--size` records
+    pub json_value_parse: f64,
+    pub json_value_serialize: f64,
+    pub json_typed_parse: f64,
+    pub protobuf_decode: f64,
+    // Share of each field's values that survive a `Value` round trip digit for digit
+    pub exact_percent: Vec<(&'static str, f64)>,
+}
+
+// Cost of building the in-memory records, separate from any serialization
+pub struct ConstructionMetric {
+    // Milliseconds to build one record
+    pub json: f64,
+    pub protobuf: f64,
+    // protobuf / json
+    pub ratio: f64,
+}
+
+// Decoding into a fresh message versus merging into a reused, cleared one
+pub struct DecodeReuseMetric {
+    // Milliseconds per decode
+    pub json: f64,
+    pub protobuf_fresh: f64,
+    pub protobuf_reused: f64,
+    // How much faster reuse is than a fresh decode, in percent
+    pub improvement_percent: f64,
+}
+
+// Results for one message type (see `TestMessage`), e.g. one loaded from a
+// descriptor at runtime
+pub struct MessageMetric {
+    pub message: String,
+    pub serialization: BenchmarkMetric,
+    pub deserialization: BenchmarkMetric,
+    pub payload_size: BenchmarkMetric,
+}
+
+// A correctness check that failed under --verify
+#[derive(Debug, Clone)]
+pub struct VerificationError {
+    pub test: String,
+    pub message: String,
+}
+
+impl fmt::Display for VerificationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.test, self.message)
+    }
+}
+
+impl std::error::Error for VerificationError {}
+
+// Relative change in per-op time below which adaptive iteration counts are considered stable
+const AUTO_ITER_THRESHOLD: f64 = 0.02;
+// Upper bound on the time spent converging a single measurement
+const AUTO_ITER_MAX_SECS: f64 = 5.0;
+// Tests in the full run, in their default order
+const SUITE: [&str; 10] = [
+    "serialization", "deserialization", "payload", "cpu", "memory",
+    "network", "latency", "init", "throughput", "schema",
+];
+// Collections built and dropped per format by the drop-cost test
+const DROP_ROUNDS: usize = 5;
+// Channel capacity and per-message consumer work in the backpressure test
+const BACKPRESSURE_BUFFER: usize = 8;
+const CONSUMER_WORK: Duration = Duration::from_micros(10);
+// Addresses shared by every record in the shared-data test
+const SHARED_ADDRESSES: usize = 3;
+// Records the dictionary test trains on and then compresses, and the dictionary size limit
+const DICTIONARY_TRAINING_RECORDS: usize = 1000;
+const DICTIONARY_TEST_RECORDS: usize = 200;
+const DICTIONARY_MAX_BYTES: usize = 16 * 1024;
+// Largest --size swept by the crossover analysis (powers of two from 1)
+const CROSSOVER_MAX_SIZE: usize = 1024;
+// Metrics compared by the crossover analysis, all lower-is-better
+const CROSSOVER_METRICS: [&str; 4] = ["serialization", "deserialization", "payload", "compressed"];
+// Timed passes over an --input batch
+const INPUT_PASSES: usize = 5;
+// Significance level (alpha) for declaring a timing winner
+const SIGNIFICANCE_LEVEL: f64 = 0.05;
+// Names accepted by `--weight`, one per winner in the overall tally
+pub const WEIGHTED_TESTS: [&str; 11] = [
+    "serialization", "deserialization", "payload", "compressed", "cpu", "memory",
+    "network", "latency", "init", "throughput", "schema",
+];
+
+// Metrics combined into the efficiency score, see `efficiency_scores`
+pub const SCORED_METRICS: [&str; 3] = ["serialization", "deserialization", "payload"];
+
+// Bytes spent by each format on one category of fields
+pub struct FieldBytes {
+    pub category: &'static str,
+    pub json: usize,
+    pub protobuf: usize,
+}
+
+// Order of the tests in the full run
+#[derive(Clone, Copy, Debug)]
+pub enum TestOrder {
+    Sequential,
+    // Shuffled with the given seed
+    Shuffled(u64),
+}
+
+// Format the table can be expressed relative to (see `relative_results`)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BaseFormat {
+    Json,
+    Protobuf,
+}
+
+impl BaseFormat {
+    pub fn name(&self) -> &'static str {
+        match self {
+            BaseFormat::Json => "JSON",
+            BaseFormat::Protobuf => "Protobuf",
+        }
+    }
 }
 
+#[derive(Clone)]
 pub struct PerformanceTester {
     results: Option<BenchmarkResults>,
     data_size: usize,
     iterations: usize,
+    verbose: bool,
+    auto_iterations: bool,
+    // Selected formats, see `with_formats`
+    formats: Vec<Format>,
+    codecs: Vec<Arc<dyn Codec>>,
+    // zstd compression level for the payload test
+    zstd_level: i32,
+    // Passes over the full suite, averaged
+    runs: usize,
+    // Untimed calls before each measured loop; None = 10% of `iterations`
+    warmup_iterations: Option<usize>,
+    // Number of distinct records rotated through by timing loops (1 = reuse the same record)
+    data_pool: usize,
+    // Probability that each optional field is absent in the optional-fields test
+    null_fraction: f64,
+    verify: bool,
+    fail_fast: bool,
+    verification_failures: Vec<VerificationError>,
+    data_options: DataOptions,
+    tag: Option<String>,
+    per_test_timeout: Option<Duration>,
+    sparklines: bool,
+    // Render formats as rows and metrics as columns
+    transpose: bool,
+    // Wrap table cells wider than this many characters
+    column_width: Option<usize>,
+    // Entries in the map-only test
+    map_size: usize,
+    // Values in the numeric-array test
+    array_size: usize,
+    order: TestOrder,
+    // Show table values as multiples of this format
+    relative_to: Option<BaseFormat>,
+    show_memory: bool,
+    weights: HashMap<String, f64>,
+    score_weights: HashMap<String, f64>,
+    worker_threads: usize,
+    normalize: bool,
+    producers: usize,
+    // Shared with the clones that run tests on blocking threads
+    volume: Arc<Mutex<ByteVolume>>,
+    // Per-iteration samples of the tests that keep them, for --raw-timings
+    raw_timings: Arc<Mutex<Vec<RawTimings>>>,
+}
+
+// Every iteration's duration for one test and format, in milliseconds
+struct RawTimings {
+    test: &'static str,
+    format: &'static str,
+    samples: Vec<f64>,
 }
 
 impl PerformanceTester {
@@ -63,88 +1007,704 @@ impl PerformanceTester {
             results: None,
             data_size,
             iterations,
+            verbose: false,
+            auto_iterations: false,
+            formats: vec![Format::Json, Format::Protobuf],
+            codecs: Vec::new(),
+            warmup_iterations: None,
+            zstd_level: zstd::DEFAULT_COMPRESSION_LEVEL,
+            runs: 1,
+            data_pool: 1,
+            null_fraction: 0.5,
+            verify: false,
+            fail_fast: false,
+            verification_failures: Vec::new(),
+            data_options: DataOptions::default(),
+            tag: None,
+            per_test_timeout: None,
+            sparklines: false,
+            transpose: false,
+            column_width: None,
+            map_size: 1000,
+            array_size: 10000,
+            order: TestOrder::Sequential,
+            relative_to: None,
+            show_memory: false,
+            weights: HashMap::new(),
+            score_weights: HashMap::new(),
+            worker_threads: num_cpus::get(),
+            normalize: false,
+            producers: 1,
+            volume: Arc::new(Mutex::new(ByteVolume::default())),
+            raw_timings: Arc::new(Mutex::new(Vec::new())),
         }
     }
 
-    // 1. Test serialization speed
-    pub fn test_serialization_speed(&self) -> BenchmarkMetric {
-        println!("{}", "Testing serialization speed...".green());
-        
-        let (json_data, proto_data) = generate_test_data(self.data_size);
+    pub fn with_size(mut self, data_size: usize) -> Self {
+        self.data_size = data_size;
+        self
+    }
+
+    pub fn with_iterations(mut self, iterations: usize) -> Self {
+        self.iterations = iterations;
+        self
+    }
+
+    pub fn with_verbose(mut self, verbose: bool) -> Self {
+        self.verbose = verbose;
+        self
+    }
+
+    pub fn with_auto_iterations(mut self, auto_iterations: bool) -> Self {
+        self.auto_iterations = auto_iterations;
+        self
+    }
+
+    // Run `warmup_iterations` untimed calls before the serialization, deserialization,
+    // CPU and throughput loops (0 = measure from the first call)
+    pub fn with_warmup(mut self, warmup_iterations: usize) -> Self {
+        self.warmup_iterations = Some(warmup_iterations);
+        self
+    }
+
+    // Run the full suite `runs` times and report each metric's mean, with its
+    // run-to-run variation
+    pub fn with_runs(mut self, runs: usize) -> Self {
+        self.runs = runs.max(1);
+        self
+    }
+
+    // Compression level (1-22) for the payload test's zstd sizes
+    pub fn with_zstd_level(mut self, level: i32) -> Self {
+        self.zstd_level = level;
+        self
+    }
+
+    // Rotate through `pool_size` distinct pre-generated records in timing loops
+    pub fn with_fresh_data(mut self, pool_size: usize) -> Self {
+        self.data_pool = pool_size.max(1);
+        self
+    }
+
+    pub fn with_null_fraction(mut self, null_fraction: f64) -> Self {
+        self.null_fraction = null_fraction.clamp(0.0, 1.0);
+        self
+    }
+
+    // Check round-trip correctness after each test; with `fail_fast` the first
+    // failure aborts `run_all_tests`, otherwise failures are collected
+    pub fn with_verify(mut self, verify: bool, fail_fast: bool) -> Self {
+        self.verify = verify;
+        self.fail_fast = fail_fast;
+        self
+    }
+
+    pub fn verification_failures(&self) -> &[VerificationError] {
+        &self.verification_failures
+    }
+
+    // Verify that the data a test measures survives a round trip through each format
+    pub fn verify_test(&self, test: &str) -> Result<(), VerificationError> {
+        let fail = |message: String| VerificationError { test: test.to_string(), message };
+        let (json_data, proto_data) = self.test_data();
         
-        // JSON serialization
-        let json_start = Instant::now();
-        for _ in 0..self.iterations {
-            let _ = serde_json::to_string(&json_data).unwrap();
+        match test {
+            // Compression must be lossless
+            "payload" => {
+                for format in &self.formats {
+                    let bytes = format.serialize(&json_data, &proto_data);
+                    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+                    encoder.write_all(&bytes).map_err(|e| fail(e.to_string()))?;
+                    let compressed = encoder.finish().map_err(|e| fail(e.to_string()))?;
+                    let mut decompressed = Vec::new();
+                    GzDecoder::new(compressed.as_slice())
+                        .read_to_end(&mut decompressed)
+                        .map_err(|e| fail(e.to_string()))?;
+                    if decompressed != bytes {
+                        return Err(fail(format!("{} gzip round trip changed the payload", format.label())));
+                    }
+                }
+                Ok(())
+            },
+            // An old reader must keep every field it knows about; skipped without
+            // both JSON and protobuf, like the test
+            "schema" if !self.measures(Format::Json) || !self.measures(Format::Protobuf) => Ok(()),
+            "schema" => {
+                let (_, proto_evolved) = generate_evolved_test_data(&self.test_data().0);
+                let decoded = Person::decode(proto_evolved.encode_to_vec().as_slice())
+                    .map_err(|e| fail(e.to_string()))?;
+                if decoded.name != proto_evolved.name || decoded.id != proto_evolved.id
+                    || decoded.phones.len() != proto_evolved.phones.len()
+                    || decoded.addresses.len() != proto_evolved.addresses.len()
+                    || decoded.metadata != proto_evolved.metadata {
+                    return Err(fail("Protobuf forwards-compatible decode lost known fields".to_string()));
+                }
+                Ok(())
+            },
+            // Nothing is encoded or decoded
+            "init" => Ok(()),
+            // Everything else measures encode/decode of the standard record
+            _ => {
+                for format in &self.formats {
+                    let unchanged = match format.deserialize(&format.serialize(&json_data, &proto_data)).map_err(fail)? {
+                        Decoded::Serde(decoded) => serde_json::to_value(&decoded).ok() == serde_json::to_value(&json_data).ok(),
+                        Decoded::Protobuf(decoded) => decoded == proto_data,
+                    };
+                    if !unchanged {
+                        return Err(fail(format!("{} round trip changed the record", format.label())));
+                    }
+                }
+                Ok(())
+            },
         }
-        let json_time = json_start.elapsed().as_secs_f64() * 1000.0 / self.iterations as f64;
-        
-        // Protobuf serialization
-        let proto_start = Instant::now();
-        for _ in 0..self.iterations {
-            let mut buf = Vec::new();
-            proto_data.encode(&mut buf).unwrap();
+    }
+
+    // Run verification for a test if enabled
+    fn check(&mut self, test: &str) -> Result<(), VerificationError> {
+        if !self.verify {
+            return Ok(());
         }
-        let proto_time = proto_start.elapsed().as_secs_f64() * 1000.0 / self.iterations as f64;
-        
-        let diff_percent = (json_time / proto_time) * 100.0;
-        let winner = if json_time < proto_time { "JSON".to_string() } else { "Protobuf".to_string() };
-        
-        println!("JSON: {:.4} ms per op", json_time);
-        println!("Protobuf: {:.4} ms per op", proto_time);
-        
-        BenchmarkMetric {
-            json: json_time,
-            protobuf: proto_time,
-            difference_percent: diff_percent,
-            winner,
+        match self.verify_test(test) {
+            Ok(()) => Ok(()),
+            Err(e) if self.fail_fast => Err(e),
+            Err(e) => {
+                eprintln!("{}", format!("Verification failed: {}", e).red());
+                self.verification_failures.push(e);
+                Ok(())
+            }
         }
     }
 
-    // 2. Test deserialization speed
-    pub fn test_deserialization_speed(&self) -> BenchmarkMetric {
-        println!("{}", "Testing deserialization speed...".green());
-        
-        let (json_data, proto_data) = generate_test_data(self.data_size);
+    // Draw metadata keys from `unique_keys` distinct stems
+    pub fn with_metadata_unique_keys(mut self, unique_keys: Option<usize>) -> Self {
+        self.data_options.metadata_unique_keys = unique_keys;
+        self
+    }
+
+    // Randomize ids, names, emails, phone numbers and metadata values from `seed`
+    // (None = the fixed synthetic record)
+    pub fn with_seed(mut self, seed: Option<u64>) -> Self {
+        self.data_options.seed = seed;
+        self
+    }
+
+    // Generate plausible names and addresses from `seed` instead of synthetic text
+    pub fn with_realistic(mut self, seed: Option<u64>) -> Self {
+        self.data_options.realistic_seed = seed;
+        self
+    }
+
+    // Mix non-ASCII characters into the generated text
+    pub fn with_charset(mut self, charset: TextCharset) -> Self {
+        self.data_options.charset = charset;
+        self
+    }
+
+    // Free-form label recorded in the run configuration
+    pub fn with_tag(mut self, tag: Option<String>) -> Self {
+        self.tag = tag;
+        self
+    }
+
+    // Give up on any single test in the full run after `timeout`
+    pub fn with_per_test_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.per_test_timeout = timeout;
+        self
+    }
+
+    // Run the full suite in the default order or shuffled, to expose order-dependent results
+    pub fn with_order(mut self, order: TestOrder) -> Self {
+        self.order = order;
+        self
+    }
+
+    // Render table and Markdown values as multiples of one format; structured
+    // output (JSON, flattened, Influx) keeps the raw values
+    pub fn with_relative_to(mut self, relative_to: Option<BaseFormat>) -> Self {
+        self.relative_to = relative_to;
+        self
+    }
+
+    // Add peak-memory columns to the results table (always on with --verbose)
+    pub fn with_show_memory(mut self, show_memory: bool) -> Self {
+        self.show_memory = show_memory;
+        self
+    }
+
+    // Per-test weights for the overall winner, keyed by `WEIGHTED_TESTS` names;
+    // unlisted tests weigh 1
+    pub fn with_weights(mut self, weights: HashMap<String, f64>) -> Self {
+        self.weights = weights;
+        self
+    }
+
+    // Per-metric weights for the efficiency score, keyed by `SCORED_METRICS`
+    // names; unlisted metrics weigh 1
+    pub fn with_score_weights(mut self, score_weights: HashMap<String, f64>) -> Self {
+        self.score_weights = score_weights;
+        self
+    }
+
+    // Worker threads of the runtime the tester runs on, reported with the results
+    pub fn with_worker_threads(mut self, worker_threads: usize) -> Self {
+        self.worker_threads = worker_threads;
+        self
+    }
+
+    // Also report throughput relative to a calibration workload
+    pub fn with_normalize(mut self, normalize: bool) -> Self {
+        self.normalize = normalize;
+        self
+    }
+
+    // Also run the throughput test on `producers` threads at once
+    pub fn with_producers(mut self, producers: usize) -> Self {
+        self.producers = producers.max(1);
+        self
+    }
+
+    // Number of entries in the map-only test
+    pub fn with_map_size(mut self, map_size: usize) -> Self {
+        self.map_size = map_size;
+        self
+    }
+
+    pub fn with_array_size(mut self, array_size: usize) -> Self {
+        self.array_size = array_size;
+        self
+    }
+
+    // Add a column to the results table visualizing JSON vs protobuf magnitudes
+    pub fn with_sparklines(mut self, sparklines: bool) -> Self {
+        self.sparklines = sparklines;
+        self
+    }
+
+    // Print one row per format and one column per metric
+    pub fn with_transpose(mut self, transpose: bool) -> Self {
+        self.transpose = transpose;
+        self
+    }
+
+    // Wrap results table cells at `column_width` characters
+    pub fn with_column_width(mut self, column_width: Option<usize>) -> Self {
+        self.column_width = column_width;
+        self
+    }
+
+    // Snapshot of the parameters that affect this run's results
+    pub fn config(&self) -> BenchConfig {
+        let mut formats: Vec<String> = [Format::Json, Format::Protobuf].into_iter()
+            .filter(|format| self.measures(*format))
+            .map(|format| format.name().to_string())
+            .collect();
+        formats.extend(self.codecs.iter().map(|codec| codec.name().to_string()));
+        
+        BenchConfig {
+            data_size: self.data_size,
+            iterations: self.iterations,
+            warmup_iterations: self.warmup_iterations(),
+            zstd_level: self.zstd_level,
+            runs: self.runs,
+            auto_iterations: self.auto_iterations,
+            normalize: self.normalize,
+            per_test_timeout_secs: self.per_test_timeout.map(|timeout| timeout.as_secs()),
+            data_pool: self.data_pool,
+            null_fraction: self.null_fraction,
+            map_size: self.map_size,
+            array_size: self.array_size,
+            order_seed: match self.order {
+                TestOrder::Sequential => None,
+                TestOrder::Shuffled(seed) => Some(seed),
+            },
+            metadata_unique_keys: self.data_options.metadata_unique_keys,
+            data_seed: self.data_options.seed,
+            realistic_seed: self.data_options.realistic_seed,
+            charset: (self.data_options.charset != TextCharset::Ascii)
+                .then(|| format!("{:?}", self.data_options.charset).to_lowercase()),
+            formats,
+            worker_threads: self.worker_threads,
+            allocator: alloc_tracker::ALLOCATOR.to_string(),
+            producers: self.producers,
+            tag: self.tag.clone(),
+        }
+    }
+
+    // The standard record, shaped by the configured data options
+    fn test_data(&self) -> (JsonPerson, test_data::Person) {
+        generate_test_data_with(self.data_size, &self.data_options)
+    }
+
+    // Records for the timing loops; generated up front so generation isn't measured
+    fn data_pool(&self) -> Vec<(JsonPerson, test_data::Person)> {
+        if self.data_pool > 1 {
+            generate_test_data_pool(self.data_size, self.data_pool, &self.data_options)
+        } else {
+            vec![self.test_data()]
+        }
+    }
+
+    // Every crossover metric for a record of `size` elements
+    fn crossover_probe(&self, size: usize) -> [(f64, f64); 4] {
+        let (json_data, proto_data) = generate_test_data_with(size, &self.data_options);
+        let json_bytes = serde_json::to_vec(&json_data).unwrap();
+        let proto_bytes = proto_data.encode_to_vec();
+        
+        let serialization = (
+            self.time_per_op(|| { let _ = serde_json::to_vec(&json_data).unwrap(); }),
+            self.time_per_op(|| { let _ = proto_data.encode_to_vec(); }),
+        );
+        let deserialization = (
+            self.time_per_op(|| { let _: JsonPerson = serde_json::from_slice(&json_bytes).unwrap(); }),
+            self.time_per_op(|| { let _ = Person::decode(proto_bytes.as_slice()).unwrap(); }),
+        );
+        let payload = (json_bytes.len() as f64, proto_bytes.len() as f64);
+        let compressed = (gzip_len(&json_bytes) as f64, gzip_len(&proto_bytes) as f64);
+        [serialization, deserialization, payload, compressed]
+    }
+
+    // Mean encoded size of the pool's records: (JSON bytes, protobuf bytes)
+    fn mean_encoded_sizes(pool: &[(JsonPerson, test_data::Person)]) -> (f64, f64) {
+        let json: usize = pool.iter().map(|(json_data, _)| serde_json::to_vec(json_data).unwrap().len()).sum();
+        let proto: usize = pool.iter().map(|(_, proto_data)| proto_data.encoded_len()).sum();
+        (json as f64 / pool.len() as f64, proto as f64 / pool.len() as f64)
+    }
+
+    // Add a codec to every results-table test except schema evolution
+    pub fn with_codec(mut self, codec: Box<dyn Codec>) -> Self {
+        self.codecs.push(Arc::from(codec));
+        self
+    }
+
+    // Compare only `formats` (default JSON and protobuf): tests skip JSON or protobuf
+    // when it is left out, and the codec of every additional format is added, in
+    // order, skipping repeats
+    pub fn with_formats(mut self, formats: &[Format]) -> Self {
+        self.formats.clear();
+        for &format in formats {
+            if self.formats.contains(&format) {
+                continue;
+            }
+            self.formats.push(format);
+            if let Some(codec) = format.codec() {
+                self = self.with_codec(codec);
+            }
+        }
+        self
+    }
+
+    // Whether `format` was selected (see `with_formats`)
+    pub fn measures(&self, format: Format) -> bool {
+        self.formats.contains(&format)
+    }
+
+    // `value` when `format` was selected, otherwise NaN, which marks it as not
+    // measured: it can't win and shows as "-"
+    fn selected_value(&self, format: Format, value: f64) -> f64 {
+        if self.measures(format) { value } else { f64::NAN }
+    }
+
+    // Write the sample record's serialized forms to `<prefix>.json` and `<prefix>.pb`
+    // for inspection with a hex viewer
+    pub fn dump_bytes(&self, prefix: &str) -> std::io::Result<(String, String)> {
+        let (json_data, proto_data) = self.test_data();
+        let json_path = format!("{}.json", prefix);
+        let proto_path = format!("{}.pb", prefix);
+        
+        std::fs::write(&json_path, serde_json::to_vec(&json_data).unwrap())?;
+        std::fs::write(&proto_path, proto_data.encode_to_vec())?;
+        
+        Ok((json_path, proto_path))
+    }
+
+    // Keep a test's per-iteration samples for --raw-timings; a format that wasn't
+    // selected has none
+    fn record_samples(&self, test: &'static str, json_samples: &[f64], proto_samples: &[f64]) {
+        let mut raw_timings = self.raw_timings.lock().unwrap();
+        raw_timings.retain(|timings| timings.test != test);
+        for (format, samples) in [("json", json_samples), ("protobuf", proto_samples)] {
+            if !samples.is_empty() {
+                raw_timings.push(RawTimings { test, format, samples: samples.to_vec() });
+            }
+        }
+    }
+
+    // Write the kept samples as CSV (test,format,iteration,ms); returns the number of rows
+    pub fn write_raw_timings(&self, path: &str) -> std::io::Result<usize> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(writer, "test,format,iteration,ms")?;
+        let mut rows = 0;
+        for timings in self.raw_timings.lock().unwrap().iter() {
+            for (iteration, ms) in timings.samples.iter().enumerate() {
+                // Nanosecond resolution, without binary float noise
+                writeln!(writer, "{},{},{},{:.6}", timings.test, timings.format, iteration, ms)?;
+                rows += 1;
+            }
+        }
+        writer.flush()?;
+        Ok(rows)
+    }
+
+    // Add a test's processed bytes and the time spent on them to the run totals,
+    // for the formats that were selected
+    fn record_volume(&self, json_bytes: f64, json_ms: f64, protobuf_bytes: f64, protobuf_ms: f64) {
+        let mut volume = self.volume.lock().unwrap();
+        if self.measures(Format::Json) {
+            volume.json_bytes += json_bytes;
+            volume.json_ms += json_ms;
+        }
+        if self.measures(Format::Protobuf) {
+            volume.protobuf_bytes += protobuf_bytes;
+            volume.protobuf_ms += protobuf_ms;
+        }
+    }
+
+    // Print `<format><label>: <value>` for JSON and protobuf where selected, e.g.
+    // `JSON execution time: 1.23 ms`
+    fn print_measured(&self, label: &str, values: &[(Format, f64)], value: impl Fn(f64) -> String) {
+        for (format, measured) in values {
+            if self.measures(*format) {
+                eprintln!("{}{}: {}", format.label(), label, value(*measured));
+            }
+        }
+    }
+
+    // Compare the batched per-op means, with a winner only when Welch's t-test on
+    // the per-iteration samples finds the difference significant
+    fn compare_samples(&self, json_mean: f64, proto_mean: f64, json_samples: &[f64], proto_samples: &[f64]) -> BenchmarkMetric {
+        let mut metric = BenchmarkMetric::lower_is_better(json_mean, proto_mean);
+        if let Some(test) = stats::welch_t_test(json_samples, proto_samples) {
+            if self.verbose {
+                eprintln!("  Welch's t = {:.3}, df = {:.1}", test.t, test.degrees_of_freedom);
+            }
+            metric.p_value = Some(test.p_value);
+            metric.rank(true);
+        }
+        metric
+    }
+
+    // Time `op` and return the mean duration of a single call in milliseconds.
+    // In adaptive mode the batch size doubles until the per-op time changes by less
+    // than AUTO_ITER_THRESHOLD between consecutive batches or AUTO_ITER_MAX_SECS is spent.
+    fn time_per_op<F: FnMut()>(&self, mut op: F) -> f64 {
+        if !self.auto_iterations {
+            let start = Instant::now();
+            for _ in 0..self.iterations {
+                op();
+            }
+            return start.elapsed().as_secs_f64() * 1000.0 / self.iterations as f64;
+        }
+        
+        let budget_start = Instant::now();
+        let mut batch = 16;
+        let mut previous: Option<f64> = None;
+        loop {
+            let start = Instant::now();
+            for _ in 0..batch {
+                op();
+            }
+            let per_op = start.elapsed().as_secs_f64() * 1000.0 / batch as f64;
+            
+            let converged = previous.is_some_and(|prev| ((per_op - prev) / prev).abs() < AUTO_ITER_THRESHOLD);
+            if converged || budget_start.elapsed().as_secs_f64() >= AUTO_ITER_MAX_SECS {
+                if self.verbose {
+                    eprintln!("  {} after {} iterations", if converged { "Converged" } else { "Time limit reached" }, batch);
+                }
+                return per_op;
+            }
+            
+            previous = Some(per_op);
+            batch *= 2;
+        }
+    }
+
+    fn warmup_iterations(&self) -> usize {
+        self.warmup_iterations.unwrap_or(self.iterations / 10)
+    }
+
+    // Call `op` untimed to warm caches, branch predictors and the allocator
+    fn warm_up<F: FnMut()>(&self, mut op: F) {
+        for _ in 0..self.warmup_iterations() {
+            op();
+        }
+    }
+
+    // Warm up and time `op` when `format` was selected: the per-op mean comes from
+    // `time_per_op`, like the additional codecs (so --iterations-auto applies), and
+    // the per-iteration samples from `time_samples` feed the Welch test and
+    // percentiles. A NaN mean, no samples and no peak when it wasn't selected.
+    fn format_samples<F: FnMut()>(&self, format: Format, mut op: F) -> (f64, Vec<f64>, Option<usize>) {
+        if !self.measures(format) {
+            return (f64::NAN, Vec::new(), None);
+        }
+        self.warm_up(&mut op);
+        let mean = self.time_per_op(&mut op);
+        let (samples, peak) = self.time_samples(op);
+        (mean, samples, peak)
+    }
+
+    // Time each of `iterations` calls to `op` individually, in milliseconds. Also
+    // returns the heap high-water mark reached by `op` above the starting heap size,
+    // when the heap tracker is built in.
+    fn time_samples<F: FnMut()>(&self, mut op: F) -> (Vec<f64>, Option<usize>) {
+        // Allocated up front so the sample buffer doesn't count towards the peak
+        let mut samples = Vec::with_capacity(self.iterations.max(2));
+        let baseline = alloc_tracker::current_bytes();
+        alloc_tracker::reset_peak();
+        for _ in 0..samples.capacity() {
+            let start = Instant::now();
+            op();
+            samples.push(start.elapsed().as_secs_f64() * 1000.0);
+        }
+        let peak = alloc_tracker::peak_bytes().saturating_sub(baseline);
+        (samples, alloc_tracker::TRACKING.then_some(peak))
+    }
+
+    // Print the latency distribution of each selected format's samples. Every sample
+    // includes one clock read, so warn when that is a sizeable share of the median op.
+    fn print_percentiles(&self, json_samples: &[f64], proto_samples: &[f64]) {
+        let mut fastest_p50 = f64::INFINITY;
+        for (name, samples) in [("JSON", json_samples), ("Protobuf", proto_samples)] {
+            if samples.is_empty() {
+                continue;
+            }
+            let p = percentiles(samples);
+            eprintln!("{} latency: min {:.4} / p50 {:.4} / p95 {:.4} / p99 {:.4} / max {:.4} ms, std dev {:.4} ms",
+                     name, p.min, p.p50, p.p95, p.p99, p.max, p.std_dev);
+            fastest_p50 = fastest_p50.min(p.p50);
+        }
+        
+        let overhead = timer_overhead_ms();
+        if overhead * 10.0 > fastest_p50 {
+            eprintln!("{}", format!("Clock reads take {:.1} ns, over 10% of the median op; percentiles are inflated \
+                                    (raise --size for more stable tails)", overhead * 1e6).yellow());
+        }
+    }
+
+    // 1. Test serialization speed
+    pub fn test_serialization_speed(&self) -> BenchmarkMetric {
+        eprintln!("{}", "Testing serialization speed...".green());
+        
+        let pool = self.data_pool();
+        
+        // JSON serialization
+        let mut json_inputs = pool.iter().cycle();
+        let json_op = || {
+            let (json_data, _) = json_inputs.next().unwrap();
+            let _ = serde_json::to_string(json_data).unwrap();
+        };
+        let (json_mean, json_samples, json_peak) = self.format_samples(Format::Json, json_op);
+        
+        // Protobuf serialization
+        let mut proto_inputs = pool.iter().cycle();
+        let proto_op = || {
+            let (_, proto_data) = proto_inputs.next().unwrap();
+            let mut buf = Vec::new();
+            proto_data.encode(&mut buf).unwrap();
+        };
+        let (proto_mean, proto_samples, proto_peak) = self.format_samples(Format::Protobuf, proto_op);
+        
+        let (json_size, proto_size) = Self::mean_encoded_sizes(&pool);
+        self.record_volume(json_size * json_samples.len() as f64, json_samples.iter().sum(),
+                           proto_size * proto_samples.len() as f64, proto_samples.iter().sum());
+        
+        self.record_samples("serialization", &json_samples, &proto_samples);
+        let mut metric = self.compare_samples(json_mean, proto_mean, &json_samples, &proto_samples);
+        if alloc_tracker::TRACKING {
+            metric.peak_memory = Some(PeakMemory { json_bytes: json_peak, protobuf_bytes: proto_peak });
+        }
+        
+        self.print_measured("", &[(Format::Json, metric.json), (Format::Protobuf, metric.protobuf)], |value| format!("{:.4} ms per op", value));
+        if let Some(p_value) = metric.p_value {
+            eprintln!("p-value: {:.4}", p_value);
+        }
+        self.print_percentiles(&json_samples, &proto_samples);
+        
+        // Additional codecs
+        let mut others = Vec::new();
+        for codec in &self.codecs {
+            let mut codec_inputs = pool.iter().cycle();
+            let mut codec_op = || {
+                let (json_data, _) = codec_inputs.next().unwrap();
+                let _ = codec.serialize(json_data);
+            };
+            self.warm_up(&mut codec_op);
+            let codec_time = self.time_per_op(codec_op);
+            eprintln!("{}: {:.4} ms per op", codec.name(), codec_time);
+            others.push((codec.name().to_string(), codec_time));
+        }
+        
+        metric.with_others(others, true)
+    }
+
+    // 2. Test deserialization speed
+    pub fn test_deserialization_speed(&self) -> BenchmarkMetric {
+        eprintln!("{}", "Testing deserialization speed...".green());
+        
+        let pool = self.data_pool();
         
         // Prepare serialized data
-        let json_string = serde_json::to_string(&json_data).unwrap();
-        let mut proto_bytes = Vec::new();
-        proto_data.encode(&mut proto_bytes).unwrap();
+        let json_strings: Vec<String> = pool.iter()
+            .map(|(json_data, _)| serde_json::to_string(json_data).unwrap())
+            .collect();
+        let proto_buffers: Vec<Vec<u8>> = pool.iter()
+            .map(|(_, proto_data)| proto_data.encode_to_vec())
+            .collect();
         
         // JSON deserialization
-        let json_start = Instant::now();
-        for _ in 0..self.iterations {
-            let _: JsonPerson = serde_json::from_str(&json_string).unwrap();
-        }
-        let json_time = json_start.elapsed().as_secs_f64() * 1000.0 / self.iterations as f64;
+        let mut json_inputs = json_strings.iter().cycle();
+        let json_op = || {
+            let _: JsonPerson = serde_json::from_str(json_inputs.next().unwrap()).unwrap();
+        };
+        let (json_mean, json_samples, json_peak) = self.format_samples(Format::Json, json_op);
         
         // Protobuf deserialization
-        let proto_start = Instant::now();
-        for _ in 0..self.iterations {
-            let _: Person = Person::decode(proto_bytes.as_slice()).unwrap();
-        }
-        let proto_time = proto_start.elapsed().as_secs_f64() * 1000.0 / self.iterations as f64;
+        let mut proto_inputs = proto_buffers.iter().cycle();
+        let proto_op = || {
+            let _: Person = Person::decode(proto_inputs.next().unwrap().as_slice()).unwrap();
+        };
+        let (proto_mean, proto_samples, proto_peak) = self.format_samples(Format::Protobuf, proto_op);
+        
+        let (json_size, proto_size) = Self::mean_encoded_sizes(&pool);
+        self.record_volume(json_size * json_samples.len() as f64, json_samples.iter().sum(),
+                           proto_size * proto_samples.len() as f64, proto_samples.iter().sum());
         
-        let diff_percent = (json_time / proto_time) * 100.0;
-        let winner = if json_time < proto_time { "JSON".to_string() } else { "Protobuf".to_string() };
+        self.record_samples("deserialization", &json_samples, &proto_samples);
+        let mut metric = self.compare_samples(json_mean, proto_mean, &json_samples, &proto_samples);
+        if alloc_tracker::TRACKING {
+            metric.peak_memory = Some(PeakMemory { json_bytes: json_peak, protobuf_bytes: proto_peak });
+        }
         
-        println!("JSON: {:.4} ms per op", json_time);
-        println!("Protobuf: {:.4} ms per op", proto_time);
+        self.print_measured("", &[(Format::Json, metric.json), (Format::Protobuf, metric.protobuf)], |value| format!("{:.4} ms per op", value));
+        if let Some(p_value) = metric.p_value {
+            eprintln!("p-value: {:.4}", p_value);
+        }
+        self.print_percentiles(&json_samples, &proto_samples);
         
-        BenchmarkMetric {
-            json: json_time,
-            protobuf: proto_time,
-            difference_percent: diff_percent,
-            winner,
+        // Additional codecs
+        let mut others = Vec::new();
+        for codec in &self.codecs {
+            let codec_buffers: Vec<Vec<u8>> = pool.iter()
+                .map(|(json_data, _)| codec.serialize(json_data))
+                .collect();
+            let mut codec_inputs = codec_buffers.iter().cycle();
+            let mut codec_op = || {
+                codec.access(codec_inputs.next().unwrap());
+            };
+            self.warm_up(&mut codec_op);
+            let codec_time = self.time_per_op(codec_op);
+            eprintln!("{}: {:.4} ms per op", codec.name(), codec_time);
+            others.push((codec.name().to_string(), codec_time));
         }
+        
+        metric.with_others(others, true)
     }
 
     // 3. Test payload size
     pub fn test_payload_size(&self) -> PayloadSizeMetric {
-        println!("{}", "Testing payload size...".green());
+        eprintln!("{}", "Testing payload size...".green());
         
-        let (json_data, proto_data) = generate_test_data(self.data_size);
+        let (json_data, proto_data) = self.test_data();
         
         // JSON serialization
         let json_string = serde_json::to_string(&json_data).unwrap();
@@ -167,120 +1727,320 @@ impl PerformanceTester {
         let proto_compressed = encoder.finish().unwrap();
         let proto_compressed_size = proto_compressed.len();
         
-        let uncompressed_diff = (json_size as f64 / proto_size as f64) * 100.0;
-        let uncompressed_winner = if json_size < proto_size { "JSON".to_string() } else { "Protobuf".to_string() };
+        // Compression alone, on bytes serialized up front. Only write_all + finish are
+        // timed; the encoder and its output buffer are created before the clock starts.
+        let iterations = self.iterations.max(1);
+        let gzip_ms = |format: Format, bytes: &[u8]| {
+            if !self.measures(format) {
+                return f64::NAN;
+            }
+            let mut total = Duration::ZERO;
+            for _ in 0..iterations {
+                let mut encoder = GzEncoder::new(Vec::with_capacity(bytes.len()), Compression::default());
+                let start = Instant::now();
+                encoder.write_all(bytes).unwrap();
+                let compressed = encoder.finish().unwrap();
+                total += start.elapsed();
+                std::hint::black_box(compressed);
+            }
+            total.as_secs_f64() * 1000.0 / iterations as f64
+        };
+        let json_compress_ms = gzip_ms(Format::Json, json_string.as_bytes());
+        let proto_compress_ms = gzip_ms(Format::Protobuf, &proto_bytes);
+        let json_ratio = self.selected_value(Format::Json, json_size as f64 / json_compressed_size as f64);
+        let proto_ratio = self.selected_value(Format::Protobuf, proto_size as f64 / proto_compressed_size as f64);
+        
+        // zstd over the same bytes
+        let json_zstd_size = zstd::encode_all(json_string.as_bytes(), self.zstd_level).unwrap().len();
+        let proto_zstd_size = zstd::encode_all(proto_bytes.as_slice(), self.zstd_level).unwrap().len();
+        
+        let json_sizes = (json_size, json_compressed_size, json_zstd_size, json_ratio, json_compress_ms);
+        let proto_sizes = (proto_size, proto_compressed_size, proto_zstd_size, proto_ratio, proto_compress_ms);
+        for (format, name, (size, compressed, zstd, ratio, ms)) in [(Format::Json, "JSON", json_sizes), (Format::Protobuf, "Protobuf", proto_sizes)] {
+            if self.measures(format) {
+                eprintln!("{} size: {} bytes (uncompressed), {} bytes (compressed), {} bytes (zstd level {}); gzip {:.2}x smaller in {:.4} ms",
+                        name, size, compressed, zstd, self.zstd_level, ratio, ms);
+            }
+        }
+        let both = self.measures(Format::Json) && self.measures(Format::Protobuf);
+        if both {
+            warn_if_protobuf_larger(json_size, proto_size);
+        }
+        
+        if self.verbose && both {
+            print_field_breakdown(&field_byte_breakdown(&json_data, &proto_data));
+        }
+        
+        // Size/speed tradeoff across gzip levels (fastest, default, best); a format
+        // that wasn't selected gets 0 bytes in NaN ms
+        let mut gzip_levels = Vec::new();
+        for level in [1, 6, 9] {
+            let mut sizes = [0; 2];
+            let mut times = [f64::NAN; 2];
+            for (i, (format, bytes)) in [(Format::Json, json_string.as_bytes()), (Format::Protobuf, proto_bytes.as_slice())].into_iter().enumerate() {
+                if !self.measures(format) {
+                    continue;
+                }
+                times[i] = self.time_per_op(|| {
+                    let mut encoder = GzEncoder::new(Vec::new(), Compression::new(level));
+                    encoder.write_all(bytes).unwrap();
+                    sizes[i] = encoder.finish().unwrap().len();
+                });
+            }
+            gzip_levels.push(CompressionLevelMetric {
+                level,
+                json_bytes: sizes[0],
+                protobuf_bytes: sizes[1],
+                json_ms: times[0],
+                protobuf_ms: times[1],
+            });
+        }
+        print_gzip_levels(&gzip_levels, self.measures(Format::Json), self.measures(Format::Protobuf));
         
-        let compressed_diff = (json_compressed_size as f64 / proto_compressed_size as f64) * 100.0;
-        let compressed_winner = if json_compressed_size < proto_compressed_size { "JSON".to_string() } else { "Protobuf".to_string() };
+        // Varint vs fixed-width integers for large values, when both formats are compared
+        let integer_encoding = if both { self.integer_encoding() } else { IntegerEncodingMetric::default() };
         
-        println!("JSON size: {} bytes (uncompressed), {} bytes (compressed)",
-                json_size, json_compressed_size);
-        println!("Protobuf size: {} bytes (uncompressed), {} bytes (compressed)",
-                proto_size, proto_compressed_size);
+        // Additional codecs, compared against protobuf and compressed like the other two
+        let mut uncompressed_others = Vec::new();
+        let mut compressed_others = Vec::new();
+        let mut zstd_others = Vec::new();
+        for codec in &self.codecs {
+            let codec_bytes = codec.serialize(&json_data);
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(&codec_bytes).unwrap();
+            let codec_compressed_size = encoder.finish().unwrap().len();
+            let codec_zstd_size = zstd::encode_all(codec_bytes.as_slice(), self.zstd_level).unwrap().len();
+            
+            let share = if self.measures(Format::Protobuf) {
+                format!(", {:.2}% of protobuf", codec_bytes.len() as f64 / proto_size as f64 * 100.0)
+            } else {
+                String::new()
+            };
+            eprintln!("{} size: {} bytes (uncompressed{}), {} bytes (compressed), {} bytes (zstd)",
+                    codec.name(), codec_bytes.len(), share, codec_compressed_size, codec_zstd_size);
+            uncompressed_others.push((codec.name().to_string(), codec_bytes.len() as f64));
+            compressed_others.push((codec.name().to_string(), codec_compressed_size as f64));
+            zstd_others.push((codec.name().to_string(), codec_zstd_size as f64));
+        }
         
+        let sizes = |json: usize, protobuf: usize, others| {
+            BenchmarkMetric::lower_is_better(self.selected_value(Format::Json, json as f64),
+                                             self.selected_value(Format::Protobuf, protobuf as f64))
+                .with_others(others, true)
+        };
         PayloadSizeMetric {
-            uncompressed: BenchmarkMetric {
-                json: json_size as f64,
-                protobuf: proto_size as f64,
-                difference_percent: uncompressed_diff,
-                winner: uncompressed_winner,
-            },
-            compressed: BenchmarkMetric {
-                json: json_compressed_size as f64,
-                protobuf: proto_compressed_size as f64,
-                difference_percent: compressed_diff,
-                winner: compressed_winner,
-            },
+            uncompressed: sizes(json_size, proto_size, uncompressed_others),
+            compressed: sizes(json_compressed_size, proto_compressed_size, compressed_others),
+            zstd_compressed: sizes(json_zstd_size, proto_zstd_size, zstd_others),
+            json_compress_ms,
+            proto_compress_ms,
+            json_ratio,
+            proto_ratio,
+            gzip_levels,
+            integer_encoding,
         }
     }
 
+    // Encoded size of `--size` records of large integers as JSON, protobuf varints
+    // and protobuf fixed64
+    fn integer_encoding(&self) -> IntegerEncodingMetric {
+        let (json_ints, varint_ints, fixed_ints) = generate_large_int_test_data(self.data_size);
+        let varint_bytes = varint_ints.encode_to_vec();
+        let fixed_bytes = fixed_ints.encode_to_vec();
+        // Both layouts must carry the same values
+        let varint_decoded = LargeIntsVarintList::decode(varint_bytes.as_slice()).unwrap();
+        let fixed_decoded = LargeIntsFixedList::decode(fixed_bytes.as_slice()).unwrap();
+        assert!(varint_decoded.records.iter().zip(&fixed_decoded.records).all(|(v, f)| {
+            (v.timestamp_ns, v.hash, v.offset, v.sequence) == (f.timestamp_ns, f.hash, f.offset, f.sequence)
+        }));
+        let integer_encoding = IntegerEncodingMetric {
+            records: self.data_size,
+            json_bytes: serde_json::to_vec(&json_ints).unwrap().len(),
+            varint_bytes: varint_bytes.len(),
+            fixed_bytes: fixed_bytes.len(),
+        };
+        eprintln!("Large integers ({} records): JSON {} bytes, Protobuf varint {} bytes, Protobuf fixed64 {} bytes",
+                integer_encoding.records, integer_encoding.json_bytes,
+                integer_encoding.varint_bytes, integer_encoding.fixed_bytes);
+        integer_encoding
+    }
+
     // 4. Test CPU usage (using execution time as a proxy)
     pub fn test_cpu_usage(&self) -> BenchmarkMetric {
-        println!("{}", "Testing CPU usage (via execution time)...".green());
+        eprintln!("{}", "Testing CPU usage (via execution time)...".green());
         
-        let (json_data, proto_data) = generate_test_data(self.data_size);
+        let pool = self.data_pool();
         let heavy_workload = self.iterations * 10; // More iterations for CPU stress
         
         // JSON CPU usage
-        let json_start = Instant::now();
-        for _ in 0..heavy_workload {
-            let json_string = serde_json::to_string(&json_data).unwrap();
-            let _: JsonPerson = serde_json::from_str(&json_string).unwrap();
-        }
-        let json_time = json_start.elapsed().as_secs_f64() * 1000.0;
+        let json_time = if self.measures(Format::Json) {
+            let mut json_inputs = pool.iter().cycle();
+            self.warm_up(|| {
+                let (json_data, _) = json_inputs.next().unwrap();
+                let json_string = serde_json::to_string(json_data).unwrap();
+                let _: JsonPerson = serde_json::from_str(&json_string).unwrap();
+            });
+            let json_start = Instant::now();
+            for (json_data, _) in pool.iter().cycle().take(heavy_workload) {
+                let json_string = serde_json::to_string(json_data).unwrap();
+                let _: JsonPerson = serde_json::from_str(&json_string).unwrap();
+            }
+            json_start.elapsed().as_secs_f64() * 1000.0
+        } else {
+            f64::NAN
+        };
         
         // Protobuf CPU usage
-        let proto_start = Instant::now();
-        for _ in 0..heavy_workload {
-            let mut buf = Vec::new();
-            proto_data.clone().encode(&mut buf).unwrap();
-            let _: Person = Person::decode(buf.as_slice()).unwrap();
-        }
-        let proto_time = proto_start.elapsed().as_secs_f64() * 1000.0;
+        let proto_time = if self.measures(Format::Protobuf) {
+            let mut proto_inputs = pool.iter().cycle();
+            self.warm_up(|| {
+                let (_, proto_data) = proto_inputs.next().unwrap();
+                let mut buf = Vec::new();
+                proto_data.clone().encode(&mut buf).unwrap();
+                let _: Person = Person::decode(buf.as_slice()).unwrap();
+            });
+            let proto_start = Instant::now();
+            for (_, proto_data) in pool.iter().cycle().take(heavy_workload) {
+                let mut buf = Vec::new();
+                proto_data.clone().encode(&mut buf).unwrap();
+                let _: Person = Person::decode(buf.as_slice()).unwrap();
+            }
+            proto_start.elapsed().as_secs_f64() * 1000.0
+        } else {
+            f64::NAN
+        };
         
-        let diff_percent = (json_time / proto_time) * 100.0;
-        let winner = if json_time < proto_time { "JSON".to_string() } else { "Protobuf".to_string() };
+        // Each round trip writes and reads the encoded record once
+        let (json_size, proto_size) = Self::mean_encoded_sizes(&pool);
+        self.record_volume(2.0 * json_size * heavy_workload as f64, json_time,
+                           2.0 * proto_size * heavy_workload as f64, proto_time);
         
-        println!("JSON execution time: {:.2} ms", json_time);
-        println!("Protobuf execution time: {:.2} ms", proto_time);
+        self.print_measured(" execution time", &[(Format::Json, json_time), (Format::Protobuf, proto_time)], |ms| format!("{:.2} ms", ms));
         
-        BenchmarkMetric {
-            json: json_time,
-            protobuf: proto_time,
-            difference_percent: diff_percent,
-            winner,
+        // Additional codecs, with the same round trip
+        let mut others = Vec::new();
+        for codec in &self.codecs {
+            let mut codec_inputs = pool.iter().cycle();
+            self.warm_up(|| {
+                let (json_data, _) = codec_inputs.next().unwrap();
+                let _ = codec.deserialize(&codec.serialize(json_data));
+            });
+            let codec_start = Instant::now();
+            for (json_data, _) in pool.iter().cycle().take(heavy_workload) {
+                let _ = codec.deserialize(&codec.serialize(json_data));
+            }
+            let codec_time = codec_start.elapsed().as_secs_f64() * 1000.0;
+            eprintln!("{} execution time: {:.2} ms", codec.name(), codec_time);
+            others.push((codec.name().to_string(), codec_time));
         }
+        
+        BenchmarkMetric::lower_is_better(json_time, proto_time).with_others(others, true)
     }
 
     // 5. Test memory usage (estimating via allocation counts)
+    // Peak heap (above the starting heap size) while `iterations` records are encoded,
+    // decoded and kept, from the counting allocator
     pub fn test_memory_usage(&self) -> BenchmarkMetric {
-        println!("{}", "Testing memory usage (estimation)...".green());
+        eprintln!("{}", "Testing memory usage...".green());
         
-        let (json_data, proto_data) = generate_test_data(self.data_size);
+        let pool = self.data_pool();
         
-        // We can't directly measure memory usage easily, use proxy of time spent creating objects
-        let json_start = Instant::now();
-        let mut json_objects = Vec::with_capacity(self.iterations);
-        for _ in 0..self.iterations {
-            let json_string = serde_json::to_string(&json_data).unwrap();
-            let parsed: JsonPerson = serde_json::from_str(&json_string).unwrap();
-            json_objects.push(parsed);
-        }
-        // Force cleanup by clearing vector
-        json_objects.clear();
-        let json_time = json_start.elapsed().as_secs_f64() * 1000.0;
+        // JSON
+        let (json_time, json_peak, json_total) = if self.measures(Format::Json) {
+            let baseline = alloc_tracker::current_bytes();
+            let total_start = alloc_tracker::total_bytes();
+            alloc_tracker::reset_peak();
+            let json_start = Instant::now();
+            let mut json_objects = Vec::with_capacity(self.iterations);
+            for (json_data, _) in pool.iter().cycle().take(self.iterations) {
+                let json_string = serde_json::to_string(json_data).unwrap();
+                let parsed: JsonPerson = serde_json::from_str(&json_string).unwrap();
+                json_objects.push(parsed);
+            }
+            let json_time = json_start.elapsed().as_secs_f64() * 1000.0;
+            let json_peak = alloc_tracker::peak_bytes().saturating_sub(baseline);
+            let json_total = alloc_tracker::total_bytes() - total_start;
+            drop(json_objects);
+            (json_time, json_peak, json_total)
+        } else {
+            (f64::NAN, 0, 0)
+        };
         
-        let proto_start = Instant::now();
-        let mut proto_objects = Vec::with_capacity(self.iterations);
-        for _ in 0..self.iterations {
-            let mut buf = Vec::new();
-            proto_data.clone().encode(&mut buf).unwrap();
-            let parsed = Person::decode(buf.as_slice()).unwrap();
-            proto_objects.push(parsed);
-        }
-        // Force cleanup
-        proto_objects.clear();
-        let proto_time = proto_start.elapsed().as_secs_f64() * 1000.0;
+        // Protobuf
+        let (proto_time, proto_peak, proto_total) = if self.measures(Format::Protobuf) {
+            let baseline = alloc_tracker::current_bytes();
+            let total_start = alloc_tracker::total_bytes();
+            alloc_tracker::reset_peak();
+            let proto_start = Instant::now();
+            let mut proto_objects = Vec::with_capacity(self.iterations);
+            for (_, proto_data) in pool.iter().cycle().take(self.iterations) {
+                let mut buf = Vec::new();
+                proto_data.encode(&mut buf).unwrap();
+                let parsed = Person::decode(buf.as_slice()).unwrap();
+                proto_objects.push(parsed);
+            }
+            let proto_time = proto_start.elapsed().as_secs_f64() * 1000.0;
+            let proto_peak = alloc_tracker::peak_bytes().saturating_sub(baseline);
+            let proto_total = alloc_tracker::total_bytes() - total_start;
+            drop(proto_objects);
+            (proto_time, proto_peak, proto_total)
+        } else {
+            (f64::NAN, 0, 0)
+        };
         
-        let diff_percent = (json_time / proto_time) * 100.0;
-        let winner = if json_time < proto_time { "JSON".to_string() } else { "Protobuf".to_string() };
+        let (json_size, proto_size) = Self::mean_encoded_sizes(&pool);
+        self.record_volume(2.0 * json_size * self.iterations as f64, json_time,
+                           2.0 * proto_size * self.iterations as f64, proto_time);
         
-        println!("JSON memory operation time: {:.2} ms", json_time);
-        println!("Protobuf memory operation time: {:.2} ms", proto_time);
+        // Additional codecs: (name, peak, total)
+        let mut codec_heaps = Vec::new();
+        for codec in &self.codecs {
+            let baseline = alloc_tracker::current_bytes();
+            let total_start = alloc_tracker::total_bytes();
+            alloc_tracker::reset_peak();
+            let mut codec_objects = Vec::with_capacity(self.iterations);
+            for (json_data, _) in pool.iter().cycle().take(self.iterations) {
+                codec_objects.push(codec.deserialize(&codec.serialize(json_data)));
+            }
+            let codec_peak = alloc_tracker::peak_bytes().saturating_sub(baseline);
+            codec_heaps.push((codec.name(), codec_peak, alloc_tracker::total_bytes() - total_start));
+            drop(codec_objects);
+        }
         
-        BenchmarkMetric {
-            json: json_time,
-            protobuf: proto_time,
-            difference_percent: diff_percent,
-            winner,
+        if !alloc_tracker::TRACKING {
+            eprintln!("{}", "Heap tracking is off (built without the `alloc-tracker` feature); no winner".yellow());
+            let others = codec_heaps.iter().map(|(name, _, _)| (name.to_string(), 0.0)).collect();
+            let mut metric = BenchmarkMetric::lower_is_better(self.selected_value(Format::Json, 0.0),
+                                                              self.selected_value(Format::Protobuf, 0.0))
+                .with_others(others, true);
+            metric.ratio = 1.0;
+            metric.percent_faster = 0.0;
+            metric.winner = "-".to_string();
+            return metric;
+        }
+        
+        let json_heap = (json_peak, json_total);
+        let proto_heap = (proto_peak, proto_total);
+        for (format, name, (peak, total)) in [(Format::Json, "JSON", json_heap), (Format::Protobuf, "Protobuf", proto_heap)] {
+            if self.measures(format) {
+                eprintln!("{} peak heap: {} bytes ({} bytes allocated in total)", name, peak, total);
+            }
+        }
+        for (name, peak, total) in &codec_heaps {
+            eprintln!("{} peak heap: {} bytes ({} bytes allocated in total)", name, peak, total);
         }
+        
+        let others = codec_heaps.iter().map(|(name, peak, _)| (name.to_string(), *peak as f64)).collect();
+        BenchmarkMetric::lower_is_better(self.selected_value(Format::Json, json_peak as f64),
+                                         self.selected_value(Format::Protobuf, proto_peak as f64))
+            .with_others(others, true)
     }
 
     // 6. Test network transfer time (simulation)
     pub async fn test_network_transfer(&self) -> BenchmarkMetric {
-        println!("{}", "Testing network transfer time (simulation)...".green());
+        eprintln!("{}", "Testing network transfer time (simulation)...".green());
         
-        let (json_data, proto_data) = generate_test_data(self.data_size);
+        let (json_data, proto_data) = self.test_data();
         let latency_ms = 50.0; // Base network latency in milliseconds
         
         // Prepare serialized data
@@ -291,38 +2051,46 @@ impl PerformanceTester {
         proto_data.encode(&mut proto_bytes).unwrap();
         let proto_size = proto_bytes.len();
         
-        // Simulate network with artificial latency
-        let simulate_network = |size: usize, latency: f64| -> f64 {
-            // Base latency + additional time based on payload size
-            // Simulating ~10Mbps connection
-            latency + (size as f64 * 8.0) / (10.0 * 1024.0 * 1024.0) * 1000.0
+        // Time to put the payload on the wire, simulating ~10Mbps connection
+        let transmission = |size: usize| -> f64 {
+            (size as f64 * 8.0) / (10.0 * 1024.0 * 1024.0) * 1000.0
         };
+        let json_transmission = self.selected_value(Format::Json, transmission(json_size));
+        let proto_transmission = self.selected_value(Format::Protobuf, transmission(proto_size));
         
-        // JSON network test
-        let json_network_time = simulate_network(json_size, latency_ms);
-        
-        // Protobuf network test
-        let proto_network_time = simulate_network(proto_size, latency_ms);
+        // Base latency + additional time based on payload size
+        let json_network_time = latency_ms + json_transmission;
+        let proto_network_time = latency_ms + proto_transmission;
         
-        let diff_percent = (json_network_time / proto_network_time) * 100.0;
-        let winner = if json_network_time < proto_network_time { "JSON".to_string() } else { "Protobuf".to_string() };
+        // Small payloads transmit in microseconds, so the fixed latency dominates the totals
+        self.print_measured(" network time", &[(Format::Json, json_network_time), (Format::Protobuf, proto_network_time)],
+                            |ms| format!("{:.2} ms per request ({:.4} ms transmission + {:.2} ms latency)",
+                                         ms, ms - latency_ms, latency_ms));
         
-        println!("JSON network time: {:.2} ms per request", json_network_time);
-        println!("Protobuf network time: {:.2} ms per request", proto_network_time);
-        
-        BenchmarkMetric {
-            json: json_network_time,
-            protobuf: proto_network_time,
-            difference_percent: diff_percent,
-            winner,
+        // Additional codecs, over the same simulated connection
+        let mut others = Vec::new();
+        for codec in &self.codecs {
+            let codec_transmission = transmission(codec.serialize(&json_data).len());
+            let codec_network_time = latency_ms + codec_transmission;
+            eprintln!("{} network time: {:.2} ms per request ({:.4} ms transmission + {:.2} ms latency)",
+                    codec.name(), codec_network_time, codec_transmission, latency_ms);
+            others.push((codec.name().to_string(), codec_network_time));
         }
+        
+        let mut metric = BenchmarkMetric::lower_is_better(json_network_time, proto_network_time).with_others(others, true);
+        metric.network = Some(NetworkComponents {
+            json_transmission_ms: json_transmission,
+            protobuf_transmission_ms: proto_transmission,
+            base_latency_ms: latency_ms,
+        });
+        metric
     }
 
     // 7. Test latency under load
     pub async fn test_latency_under_load(&self) -> BenchmarkMetric {
-        println!("{}", "Testing latency under load...".green());
+        eprintln!("{}", "Testing latency under load...".green());
         
-        let (json_data, proto_data) = generate_test_data(self.data_size);
+        let (json_data, proto_data) = self.test_data();
         let concurrent_ops = 10;
         let iter_per_thread = 10;
         
@@ -333,143 +2101,312 @@ impl PerformanceTester {
         let proto_bytes = proto_bytes; // Make immutable
         
         // JSON concurrent load test
-        let json_start = Instant::now();
-        let mut json_handles = Vec::new();
-        
-        for _ in 0..concurrent_ops {
-            let json_str = json_string.clone();
-            let handle = tokio::spawn(async move {
-                for _ in 0..iter_per_thread {
-                    let _: JsonPerson = serde_json::from_str(&json_str).unwrap();
-                    // Simulate some work
-                    tokio::time::sleep(Duration::from_millis(1)).await;
-                }
-            });
-            json_handles.push(handle);
-        }
-        
-        for handle in json_handles {
-            handle.await.unwrap();
-        }
-        
-        let json_time = json_start.elapsed().as_secs_f64() * 1000.0;
+        let json_time = if self.measures(Format::Json) {
+            let json_start = Instant::now();
+            let mut json_handles = Vec::new();
+            
+            for _ in 0..concurrent_ops {
+                let json_str = json_string.clone();
+                let handle = tokio::spawn(async move {
+                    for _ in 0..iter_per_thread {
+                        let _: JsonPerson = serde_json::from_str(&json_str).unwrap();
+                        // Simulate some work
+                        tokio::time::sleep(Duration::from_millis(1)).await;
+                    }
+                });
+                json_handles.push(handle);
+            }
+            
+            for handle in json_handles {
+                handle.await.unwrap();
+            }
+            
+            json_start.elapsed().as_secs_f64() * 1000.0
+        } else {
+            f64::NAN
+        };
         
         // Protobuf concurrent load test
-        let proto_start = Instant::now();
-        let mut proto_handles = Vec::new();
-        
-        for _ in 0..concurrent_ops {
-            let proto_data = proto_bytes.clone();
-            let handle = tokio::spawn(async move {
-                for _ in 0..iter_per_thread {
-                    let _: Person = Person::decode(proto_data.as_slice()).unwrap();
-                    // Simulate some work
-                    tokio::time::sleep(Duration::from_millis(1)).await;
-                }
-            });
-            proto_handles.push(handle);
-        }
-        
-        for handle in proto_handles {
-            handle.await.unwrap();
-        }
-        
-        let proto_time = proto_start.elapsed().as_secs_f64() * 1000.0;
-        
-        let diff_percent = (json_time / proto_time) * 100.0;
-        let winner = if json_time < proto_time { "JSON".to_string() } else { "Protobuf".to_string() };
+        let proto_time = if self.measures(Format::Protobuf) {
+            let proto_start = Instant::now();
+            let mut proto_handles = Vec::new();
+            
+            for _ in 0..concurrent_ops {
+                let proto_data = proto_bytes.clone();
+                let handle = tokio::spawn(async move {
+                    for _ in 0..iter_per_thread {
+                        let _: Person = Person::decode(proto_data.as_slice()).unwrap();
+                        // Simulate some work
+                        tokio::time::sleep(Duration::from_millis(1)).await;
+                    }
+                });
+                proto_handles.push(handle);
+            }
+            
+            for handle in proto_handles {
+                handle.await.unwrap();
+            }
+            
+            proto_start.elapsed().as_secs_f64() * 1000.0
+        } else {
+            f64::NAN
+        };
         
-        println!("JSON latency under load: {:.2} ms", json_time);
-        println!("Protobuf latency under load: {:.2} ms", proto_time);
+        self.print_measured(" latency under load", &[(Format::Json, json_time), (Format::Protobuf, proto_time)], |ms| format!("{:.2} ms", ms));
         
-        BenchmarkMetric {
-            json: json_time,
-            protobuf: proto_time,
-            difference_percent: diff_percent,
-            winner,
+        // Additional codecs, under the same concurrent load
+        let mut others = Vec::new();
+        for codec in &self.codecs {
+            let codec_bytes = Arc::new(codec.serialize(&json_data));
+            let codec_start = Instant::now();
+            let mut codec_handles = Vec::new();
+            
+            for _ in 0..concurrent_ops {
+                let codec = Arc::clone(codec);
+                let codec_bytes = Arc::clone(&codec_bytes);
+                let handle = tokio::spawn(async move {
+                    for _ in 0..iter_per_thread {
+                        let _ = codec.deserialize(&codec_bytes);
+                        // Simulate some work
+                        tokio::time::sleep(Duration::from_millis(1)).await;
+                    }
+                });
+                codec_handles.push(handle);
+            }
+            
+            for handle in codec_handles {
+                handle.await.unwrap();
+            }
+            
+            let codec_time = codec_start.elapsed().as_secs_f64() * 1000.0;
+            eprintln!("{} latency under load: {:.2} ms", codec.name(), codec_time);
+            others.push((codec.name().to_string(), codec_time));
         }
+        
+        BenchmarkMetric::lower_is_better(json_time, proto_time).with_others(others, true)
     }
 
-    // 8. Parser initialization time
+    // 8. Parser initialization time: the cold-start cost of the first decode over the
+    // steady-state decode. Neither side has runtime setup (serde derives and prost
+    // structs are plain generated code), so this is mostly page faults, cache misses
+    // and first allocations, and is only truly cold when `init` runs first.
     pub fn test_parser_initialization(&self) -> BenchmarkMetric {
-        println!("{}", "Testing parser initialization time...".green());
+        eprintln!("{}", "Testing parser initialization time...".green());
         
-        // For JSON, initialization is negligible as it's built into the standard library
-        let json_init_time = 0.01; // Negligible initialization time
+        let (json_data, proto_data) = self.test_data();
+        let json_str = serde_json::to_string(&json_data).unwrap();
+        let proto_bytes = proto_data.encode_to_vec();
         
-        // For Protobuf, we can't use prost_build here since it's a build dependency
-        // We'll simulate the initialization with a more realistic but fixed value
-        let proto_init_time = 5.0; // Example fixed value
+        let json = self.measures(Format::Json);
+        let protobuf = self.measures(Format::Protobuf);
         
-        let diff_percent = (json_init_time / proto_init_time) * 100.0;
-        let winner = if json_init_time < proto_init_time { "JSON".to_string() } else { "Protobuf".to_string() };
+        // First decode of each format in this process
+        let mut json_cold = f64::NAN;
+        if json {
+            let start = Instant::now();
+            let _: JsonPerson = std::hint::black_box(serde_json::from_str(&json_str).unwrap());
+            json_cold = start.elapsed().as_secs_f64() * 1000.0;
+        }
         
-        println!("JSON initialization: {:.2} ms (built-in)", json_init_time);
-        println!("Protobuf initialization: {:.2} ms", proto_init_time);
+        let mut proto_cold = f64::NAN;
+        if protobuf {
+            let start = Instant::now();
+            let mut person = Person::default();
+            person.merge(proto_bytes.as_slice()).unwrap();
+            std::hint::black_box(&person);
+            proto_cold = start.elapsed().as_secs_f64() * 1000.0;
+        }
         
-        BenchmarkMetric {
-            json: json_init_time,
-            protobuf: proto_init_time,
-            difference_percent: diff_percent,
-            winner,
+        // Steady state, after the first decode
+        let mut json_steady = f64::NAN;
+        if json {
+            json_steady = self.time_per_op(|| {
+                let _: JsonPerson = std::hint::black_box(serde_json::from_str(&json_str).unwrap());
+            });
+        }
+        let mut proto_steady = f64::NAN;
+        if protobuf {
+            proto_steady = self.time_per_op(|| {
+                std::hint::black_box(Person::decode(proto_bytes.as_slice()).unwrap());
+            });
+        }
+        
+        // A first call faster than the average is noise, not negative setup
+        let json_init_time = self.selected_value(Format::Json, (json_cold - json_steady).max(0.0));
+        let proto_init_time = self.selected_value(Format::Protobuf, (proto_cold - proto_steady).max(0.0));
+        
+        let json_times = (json_cold, json_steady, json_init_time);
+        let proto_times = (proto_cold, proto_steady, proto_init_time);
+        for (selected, name, (cold, steady, init)) in [(json, "JSON", json_times), (protobuf, "Protobuf", proto_times)] {
+            if selected {
+                eprintln!("{} first decode: {:.4} ms, steady state: {:.4} ms, initialization: {:.4} ms", name, cold, steady, init);
+            }
+        }
+        
+        // Additional codecs: first decode over steady state, like the two above
+        let mut others = Vec::new();
+        for codec in &self.codecs {
+            let codec_bytes = codec.serialize(&json_data);
+            let start = Instant::now();
+            std::hint::black_box(codec.deserialize(&codec_bytes));
+            let codec_cold = start.elapsed().as_secs_f64() * 1000.0;
+            let codec_steady = self.time_per_op(|| {
+                std::hint::black_box(codec.deserialize(&codec_bytes));
+            });
+            let codec_init_time = (codec_cold - codec_steady).max(0.0);
+            eprintln!("{} first decode: {:.4} ms, steady state: {:.4} ms, initialization: {:.4} ms",
+                    codec.name(), codec_cold, codec_steady, codec_init_time);
+            others.push((codec.name().to_string(), codec_init_time));
         }
+        
+        BenchmarkMetric::lower_is_better(json_init_time, proto_init_time).with_others(others, true)
     }
 
     // 9. Test throughput
     pub fn test_throughput(&self) -> ThroughputMetric {
-        println!("{}", "Testing throughput...".green());
+        eprintln!("{}", "Testing throughput...".green());
         
-        let (json_data, proto_data) = generate_test_data(self.data_size);
+        let pool = self.data_pool();
         let duration_ms = 1000.0; // 1 second test
         
         // JSON throughput
-        let mut json_counter = 0;
-        let json_start = Instant::now();
+        let (json_counter, json_elapsed, json_throughput) = if self.measures(Format::Json) {
+            let mut json_counter = 0;
+            let mut json_inputs = pool.iter().cycle();
+            let mut json_op = || {
+                let (json_data, _) = json_inputs.next().unwrap();
+                let json_string = serde_json::to_string(json_data).unwrap();
+                let _: JsonPerson = serde_json::from_str(&json_string).unwrap();
+            };
+            self.warm_up(&mut json_op);
+            let json_start = Instant::now();
+            
+            while json_start.elapsed().as_secs_f64() * 1000.0 < duration_ms {
+                json_op();
+                json_counter += 1;
+            }
+            
+            let json_elapsed = json_start.elapsed().as_secs_f64();
+            let json_throughput = json_counter as f64 / json_elapsed;
+            (json_counter, json_elapsed, json_throughput)
+        } else {
+            (0, f64::NAN, f64::NAN)
+        };
         
-        while json_start.elapsed().as_secs_f64() * 1000.0 < duration_ms {
-            let json_string = serde_json::to_string(&json_data).unwrap();
-            let _: JsonPerson = serde_json::from_str(&json_string).unwrap();
-            json_counter += 1;
-        }
+        // Protobuf throughput
+        let (proto_counter, proto_elapsed, proto_throughput) = if self.measures(Format::Protobuf) {
+            let mut proto_counter = 0;
+            let mut proto_inputs = pool.iter().cycle();
+            let mut proto_op = || {
+                let (_, proto_data) = proto_inputs.next().unwrap();
+                let mut buf = Vec::new();
+                proto_data.clone().encode(&mut buf).unwrap();
+                let _: Person = Person::decode(buf.as_slice()).unwrap();
+            };
+            self.warm_up(&mut proto_op);
+            let proto_start = Instant::now();
+            
+            while proto_start.elapsed().as_secs_f64() * 1000.0 < duration_ms {
+                proto_op();
+                proto_counter += 1;
+            }
+            
+            let proto_elapsed = proto_start.elapsed().as_secs_f64();
+            let proto_throughput = proto_counter as f64 / proto_elapsed;
+            (proto_counter, proto_elapsed, proto_throughput)
+        } else {
+            (0, f64::NAN, f64::NAN)
+        };
         
-        let json_elapsed = json_start.elapsed().as_secs_f64();
-        let json_throughput = json_counter as f64 / json_elapsed;
+        let (json_size, proto_size) = Self::mean_encoded_sizes(&pool);
+        self.record_volume(2.0 * json_size * json_counter as f64, json_elapsed * 1000.0,
+                           2.0 * proto_size * proto_counter as f64, proto_elapsed * 1000.0);
         
-        // Protobuf throughput
-        let mut proto_counter = 0;
-        let proto_start = Instant::now();
+        self.print_measured(" throughput", &[(Format::Json, json_throughput), (Format::Protobuf, proto_throughput)], |ops| format!("{:.2} ops/s", ops));
         
-        while proto_start.elapsed().as_secs_f64() * 1000.0 < duration_ms {
-            let mut buf = Vec::new();
-            proto_data.clone().encode(&mut buf).unwrap();
-            let _: Person = Person::decode(buf.as_slice()).unwrap();
-            proto_counter += 1;
+        // Additional codecs, with the same round trip
+        let mut others = Vec::new();
+        for codec in &self.codecs {
+            let mut codec_counter = 0;
+            let mut codec_inputs = pool.iter().cycle();
+            let mut codec_op = || {
+                let (json_data, _) = codec_inputs.next().unwrap();
+                let _ = codec.deserialize(&codec.serialize(json_data));
+            };
+            self.warm_up(&mut codec_op);
+            let codec_start = Instant::now();
+            
+            while codec_start.elapsed().as_secs_f64() * 1000.0 < duration_ms {
+                codec_op();
+                codec_counter += 1;
+            }
+            
+            let codec_throughput = codec_counter as f64 / codec_start.elapsed().as_secs_f64();
+            eprintln!("{} throughput: {:.2} ops/s", codec.name(), codec_throughput);
+            others.push((codec.name().to_string(), codec_throughput));
         }
         
-        let proto_elapsed = proto_start.elapsed().as_secs_f64();
-        let proto_throughput = proto_counter as f64 / proto_elapsed;
-        
-        let diff_percent = (json_throughput / proto_throughput) * 100.0;
-        let winner = if json_throughput > proto_throughput { "JSON".to_string() } else { "Protobuf".to_string() };
+        let normalized = self.normalize.then(|| {
+            let calibration_ns = calibration::calibrate();
+            let normalized = NormalizedThroughput {
+                calibration_ns,
+                json: calibration::normalize(json_throughput, calibration_ns),
+                protobuf: calibration::normalize(proto_throughput, calibration_ns),
+            };
+            eprintln!("Calibration op: {:.0} ns; normalized throughput: JSON {:.4}, Protobuf {:.4}",
+                    calibration_ns, normalized.json, normalized.protobuf);
+            normalized
+        });
         
-        println!("JSON throughput: {:.2} ops/s", json_throughput);
-        println!("Protobuf throughput: {:.2} ops/s", proto_throughput);
+        let parallel = (self.producers > 1).then(|| {
+            let duration = Duration::from_millis(duration_ms as u64);
+            let (json, json_fairness) = if self.measures(Format::Json) {
+                parallel_ops_per_sec(&pool, self.producers, duration, |(json_data, _)| {
+                    let json_string = serde_json::to_string(json_data).unwrap();
+                    let _: JsonPerson = serde_json::from_str(&json_string).unwrap();
+                })
+            } else {
+                (f64::NAN, f64::NAN)
+            };
+            let (protobuf, protobuf_fairness) = if self.measures(Format::Protobuf) {
+                parallel_ops_per_sec(&pool, self.producers, duration, |(_, proto_data)| {
+                    let buf = proto_data.encode_to_vec();
+                    let _: Person = Person::decode(buf.as_slice()).unwrap();
+                })
+            } else {
+                (f64::NAN, f64::NAN)
+            };
+            eprintln!("{} producers: JSON {:.2} ops/s (fairness {:.3}), Protobuf {:.2} ops/s (fairness {:.3})",
+                    self.producers, json, json_fairness, protobuf, protobuf_fairness);
+            ParallelThroughput { producers: self.producers, json, protobuf, json_fairness, protobuf_fairness }
+        });
         
-        ThroughputMetric {
+        let mut metric = ThroughputMetric {
             json: json_throughput,
             protobuf: proto_throughput,
-            difference_percent: diff_percent,
-            winner,
-        }
+            ratio: json_throughput / proto_throughput,
+            percent_faster: f64::NAN,
+            winner: String::new(),
+            status: TestStatus::Completed,
+            normalized,
+            parallel,
+            others,
+        };
+        metric.rank();
+        metric
     }
 
     // 10. Test schema evolution handling
     pub fn test_schema_evolution(&self) -> SchemaEvolutionMetric {
-        println!("{}", "Testing schema evolution handling...".green());
+        eprintln!("{}", "Testing schema evolution handling...".green());
+        if !self.measures(Format::Json) || !self.measures(Format::Protobuf) {
+            eprintln!("{}", "Skipped: schema evolution compares JSON with protobuf, and --formats leaves one out".yellow());
+            return SchemaEvolutionMetric::skipped();
+        }
         
         // Generate both standard and evolved test data
-        let (_, proto_basic) = generate_test_data(self.data_size);
-        let (json_evolved_data, proto_evolved) = generate_evolved_test_data(self.data_size);
+        let (json_basic, proto_basic) = self.test_data();
+        let (json_evolved_data, proto_evolved) = generate_evolved_test_data(&json_basic);
         
         // 1. Test backwards compatibility: New schema reading old data
         // This simulates when a newer client reads data created by an older service
@@ -479,8 +2416,7 @@ impl PerformanceTester {
         proto_basic.encode(&mut orig_bytes).unwrap();
         
         // Try to convert between the schemas manually (simulating schema evolution)
-        let backwards_start = Instant::now();
-        for _ in 0..self.iterations {
+        let backwards_time = self.time_per_op(|| {
             // In real schema evolution, we'd do conversion from old to new format
             let basic_decoded = Person::decode(orig_bytes.as_slice()).unwrap();
             
@@ -510,8 +2446,7 @@ impl PerformanceTester {
             // Access some fields to ensure they're processed
             let _ = evolved.name;
             let _ = evolved.additional_field;
-        }
-        let backwards_time = backwards_start.elapsed().as_secs_f64() * 1000.0 / self.iterations as f64;
+        });
         
         // 2. Test forwards compatibility: Old schema reading new data
         // This simulates when an older client reads data created by a newer service
@@ -521,8 +2456,7 @@ impl PerformanceTester {
         proto_evolved.encode(&mut evolved_bytes).unwrap();
         
         // Test forwards compatibility
-        let forwards_start = Instant::now();
-        for _ in 0..self.iterations {
+        let forwards_time = self.time_per_op(|| {
             // In real schema evolution, we'd strip unknown fields when reading with old schema
             let evolved_decoded = evolved::Person::decode(evolved_bytes.as_slice()).unwrap();
             
@@ -548,14 +2482,12 @@ impl PerformanceTester {
             // Access some fields to ensure they're deserialized
             let _ = basic.name;
             let _ = basic.phones;
-        }
-        let forwards_time = forwards_start.elapsed().as_secs_f64() * 1000.0 / self.iterations as f64;
+        });
         
         // For JSON, schema evolution handling
-        let json_start = Instant::now();
         let json_string = serde_json::to_string(&json_evolved_data).unwrap();
         
-        for _ in 0..self.iterations {
+        let json_time = self.time_per_op(|| {
             // Simulate a client that only understands original schema
             let parsed_full: serde_json::Value = serde_json::from_str(&json_string).unwrap();
             
@@ -599,20 +2531,71 @@ impl PerformanceTester {
             
             // Convert back to JSON string (simulating storage or further processing)
             let _ = serde_json::to_string(&serde_json::Value::Object(filtered_data)).unwrap();
-        }
+        });
         
-        let json_time = json_start.elapsed().as_secs_f64() * 1000.0 / self.iterations as f64;
         let proto_avg = (backwards_time + forwards_time) / 2.0;
         
+        // 3. Per-field decode cost: the same record decoded natively by each schema
+        let (_, proto_same) = self.test_data();
+        let basic_bytes = proto_same.encode_to_vec();
+        let basic_ms = self.time_per_op(|| {
+            let _ = Person::decode(basic_bytes.as_slice()).unwrap();
+        });
+        let evolved_ms = self.time_per_op(|| {
+            let _ = evolved::Person::decode(evolved_bytes.as_slice()).unwrap();
+        });
+        let added_values = proto_evolved.phones.iter().filter(|p| p.is_primary).count()
+            + proto_evolved.addresses.iter().filter(|a| !a.additional_info.is_empty()).count()
+            + usize::from(!proto_evolved.additional_field.is_empty())
+            + usize::from(proto_evolved.priority != 0);
+        let delta_ms = evolved_ms - basic_ms;
+        let decode_cost = EvolvedDecodeCost {
+            basic_ms,
+            evolved_ms,
+            delta_ms,
+            added_values,
+            added_bytes: evolved_bytes.len().saturating_sub(basic_bytes.len()),
+            per_value_ns: if added_values > 0 { delta_ms * 1e6 / added_values as f64 } else { 0.0 },
+        };
+        
+        // 4. Removed field: a later schema without `email` (tag 3 reserved) reads old data.
+        // prost skips the unknown tag, and `id`, though deprecated, still decodes.
+        let reduced_ms = self.time_per_op(|| {
+            let _ = reduced::Person::decode(orig_bytes.as_slice()).unwrap();
+        });
+        let removed_field = match reduced::Person::decode(orig_bytes.as_slice()) {
+            Ok(decoded) => {
+                #[allow(deprecated)]
+                let id = decoded.id;
+                let intact = decoded.name == proto_basic.name
+                    && id == proto_basic.id
+                    && decoded.phones.iter().map(|p| (&p.number, p.r#type))
+                        .eq(proto_basic.phones.iter().map(|p| (&p.number, p.r#type)))
+                    && decoded.addresses.iter().map(|a| (&a.street, &a.city, &a.state, &a.zip, &a.country))
+                        .eq(proto_basic.addresses.iter().map(|a| (&a.street, &a.city, &a.state, &a.zip, &a.country)))
+                    && decoded.metadata == proto_basic.metadata;
+                RemovedFieldDecode {
+                    decoded: intact,
+                    ms: reduced_ms,
+                    dropped_bytes: orig_bytes.len() - decoded.encode_to_vec().len(),
+                }
+            },
+            Err(_) => RemovedFieldDecode { decoded: false, ms: reduced_ms, dropped_bytes: 0 },
+        };
+        
         let winner = if json_time < proto_avg { 
             "JSON".to_string() 
         } else { 
             "Protobuf".to_string() 
         };
         
-        println!("JSON schema evolution: {:.4} ms per op", json_time);
-        println!("Protobuf backwards compatibility: {:.4} ms per op", backwards_time);
-        println!("Protobuf forwards compatibility: {:.4} ms per op", forwards_time);
+        eprintln!("JSON schema evolution: {:.4} ms per op", json_time);
+        eprintln!("Protobuf backwards compatibility: {:.4} ms per op", backwards_time);
+        eprintln!("Protobuf forwards compatibility: {:.4} ms per op", forwards_time);
+        eprintln!("Protobuf decode, basic vs evolved schema: {:.4} ms vs {:.4} ms ({:+.4} ms for {} added values, {:+.1} ns each)",
+                basic_ms, evolved_ms, delta_ms, added_values, decode_cost.per_value_ns);
+        eprintln!("Protobuf decode with a removed (reserved) field: {} in {:.4} ms, {} bytes of the removed field dropped",
+                if removed_field.decoded { "succeeded" } else { "FAILED" }, removed_field.ms, removed_field.dropped_bytes);
         
         SchemaEvolutionMetric {
             json: json_time,
@@ -620,182 +2603,3278 @@ impl PerformanceTester {
             protobuf_forwards: forwards_time,
             protobuf_average: proto_avg,
             winner,
+            status: TestStatus::Completed,
+            decode_cost,
+            removed_field,
         }
     }
 
-    // Run all tests
-    pub async fn run_all_tests(&mut self) -> &BenchmarkResults {
-        println!("{}", format!("Running all tests with data size {} and {} iterations...", 
-                              self.data_size, self.iterations).blue().bold());
+    // 11. Test optional field encoding (JSON null vs proto3 optional presence)
+    pub fn test_optional_fields(&self) -> OptionalFieldsMetric {
+        eprintln!("{}", format!("Testing optional fields ({:.0}% absent)...", self.null_fraction * 100.0).green());
+        
+        let (json_profiles, proto_profiles) = generate_optional_test_data(self.data_size, self.null_fraction);
+        let wrapped_profiles = wrap_profiles(&proto_profiles);
+        
+        // JSON writes absent fields as explicit `null`, protobuf omits them entirely
+        let json_string = serde_json::to_string(&json_profiles).unwrap();
+        let proto_bytes = proto_profiles.encode_to_vec();
+        let wrapped_bytes = wrapped_profiles.encode_to_vec();
+        let json_size = json_string.len() as f64;
+        let proto_size = proto_bytes.len() as f64;
+        let wrapped_size = wrapped_bytes.len() as f64;
+        
+        let json_ser_time = self.time_per_op(|| {
+            let _ = serde_json::to_string(&json_profiles).unwrap();
+        });
+        let proto_ser_time = self.time_per_op(|| {
+            let _ = proto_profiles.encode_to_vec();
+        });
+        let wrapped_ser_time = self.time_per_op(|| {
+            let _ = wrapped_profiles.encode_to_vec();
+        });
+        
+        let json_de_time = self.time_per_op(|| {
+            let _: Vec<JsonProfile> = serde_json::from_str(&json_string).unwrap();
+        });
+        let proto_de_time = self.time_per_op(|| {
+            let _ = ProfileList::decode(proto_bytes.as_slice()).unwrap();
+        });
+        let wrapped_de_time = self.time_per_op(|| {
+            let _ = ProfileWrappedList::decode(wrapped_bytes.as_slice()).unwrap();
+        });
+        
+        eprintln!("JSON size: {} bytes, Protobuf size: {} bytes, Protobuf wrappers: {} bytes", json_size, proto_size, wrapped_size);
+        eprintln!("JSON serialization: {:.4} ms per op, Protobuf: {:.4} ms per op, Protobuf wrappers: {:.4} ms per op",
+                json_ser_time, proto_ser_time, wrapped_ser_time);
+        eprintln!("JSON deserialization: {:.4} ms per op, Protobuf: {:.4} ms per op, Protobuf wrappers: {:.4} ms per op",
+                json_de_time, proto_de_time, wrapped_de_time);
         
-        // Run the tests
-        let serialization = self.test_serialization_speed();
-        let deserialization = self.test_deserialization_speed();
-        let payload_size = self.test_payload_size();
-        let cpu_usage = self.test_cpu_usage();
-        let memory_usage = self.test_memory_usage();
-        let network_transfer = self.test_network_transfer().await;
-        let latency_under_load = self.test_latency_under_load().await;
-        let parser_init = self.test_parser_initialization();
-        let throughput = self.test_throughput();
-        let schema_evolution = self.test_schema_evolution();
-        
-        // Store results
-        self.results = Some(BenchmarkResults {
-            serialization,
-            deserialization,
-            payload_size,
-            cpu_usage,
-            memory_usage,
-            network_transfer,
-            latency_under_load,
-            parser_init,
-            throughput,
-            schema_evolution,
-        });
-        
-        println!("{}", "All tests completed!".green().bold());
-        
-        self.results.as_ref().unwrap()
+        let wrappers = |value: f64| vec![("protobuf wrappers".to_string(), value)];
+        OptionalFieldsMetric {
+            null_fraction: self.null_fraction,
+            payload_size: BenchmarkMetric::lower_is_better(json_size, proto_size).with_others(wrappers(wrapped_size), true),
+            serialization: BenchmarkMetric::lower_is_better(json_ser_time, proto_ser_time)
+                .with_others(wrappers(wrapped_ser_time), true),
+            deserialization: BenchmarkMetric::lower_is_better(json_de_time, proto_de_time)
+                .with_others(wrappers(wrapped_de_time), true),
+        }
     }
 
-    // Print results as a table
-    pub fn print_results(&self) {
-        if let Some(results) = &self.results {
-            println!("\n{}", "JSON vs Protocol Buffers Benchmark Results".blue().bold());
-            println!("{}", "===========================================".blue());
-            
-            let mut table = Table::new();
-            
-            table.add_row(row![bFg->"Test", bFg->"JSON", bFg->"Protobuf", bFg->"Difference", bFg->"Winner"]);
-            
-            // Add serialization results
-            table.add_row(row![
-                "Serialization (ms/op)",
-                format!("{:.4}", results.serialization.json),
-                format!("{:.4}", results.serialization.protobuf),
-                format!("{:.2}%", results.serialization.difference_percent),
-                results.serialization.winner
-            ]);
-            
-            // Add deserialization results
-            table.add_row(row![
-                "Deserialization (ms/op)",
-                format!("{:.4}", results.deserialization.json),
-                format!("{:.4}", results.deserialization.protobuf),
-                format!("{:.2}%", results.deserialization.difference_percent),
-                results.deserialization.winner
-            ]);
-            
-            // Add payload size results
-            table.add_row(row![
-                "Payload Size (bytes)",
-                format!("{:.0}", results.payload_size.uncompressed.json),
-                format!("{:.0}", results.payload_size.uncompressed.protobuf),
-                format!("{:.2}%", results.payload_size.uncompressed.difference_percent),
-                results.payload_size.uncompressed.winner
-            ]);
-            
-            table.add_row(row![
-                "Compressed Size (bytes)",
-                format!("{:.0}", results.payload_size.compressed.json),
-                format!("{:.0}", results.payload_size.compressed.protobuf),
-                format!("{:.2}%", results.payload_size.compressed.difference_percent),
-                results.payload_size.compressed.winner
-            ]);
-            
-            // Add CPU usage results
-            table.add_row(row![
-                "CPU Usage (ms)",
-                format!("{:.2}", results.cpu_usage.json),
-                format!("{:.2}", results.cpu_usage.protobuf),
-                format!("{:.2}%", results.cpu_usage.difference_percent),
-                results.cpu_usage.winner
-            ]);
-            
-            // Add memory usage results
-            table.add_row(row![
-                "Memory Usage (proxy ms)",
-                format!("{:.2}", results.memory_usage.json),
-                format!("{:.2}", results.memory_usage.protobuf),
-                format!("{:.2}%", results.memory_usage.difference_percent),
-                results.memory_usage.winner
-            ]);
-            
-            // Add network transfer results
-            table.add_row(row![
-                "Network Transfer (ms)",
-                format!("{:.2}", results.network_transfer.json),
-                format!("{:.2}", results.network_transfer.protobuf),
-                format!("{:.2}%", results.network_transfer.difference_percent),
-                results.network_transfer.winner
-            ]);
-            
-            // Add latency under load results
-            table.add_row(row![
-                "Latency Under Load (ms)",
-                format!("{:.2}", results.latency_under_load.json),
-                format!("{:.2}", results.latency_under_load.protobuf),
-                format!("{:.2}%", results.latency_under_load.difference_percent),
-                results.latency_under_load.winner
-            ]);
-            
-            // Add parser initialization results
-            table.add_row(row![
-                "Parser Init (ms)",
-                format!("{:.2}", results.parser_init.json),
-                format!("{:.2}", results.parser_init.protobuf),
-                format!("{:.2}%", results.parser_init.difference_percent),
-                results.parser_init.winner
-            ]);
-            
-            // Add throughput results
-            table.add_row(row![
-                "Throughput (ops/s)",
-                format!("{:.2}", results.throughput.json),
-                format!("{:.2}", results.throughput.protobuf),
-                format!("{:.2}%", results.throughput.difference_percent),
-                results.throughput.winner
-            ]);
-            
-            // Add schema evolution results
-            table.add_row(row![
-                "Schema Evolution (ms/op)",
-                format!("{:.4}", results.schema_evolution.json),
-                format!("B: {:.4} / F: {:.4}", 
-                       results.schema_evolution.protobuf_backwards,
-                       results.schema_evolution.protobuf_forwards),
-                format!("{:.2}%", 
-                       (results.schema_evolution.json / results.schema_evolution.protobuf_average) * 100.0),
-                results.schema_evolution.winner
-            ]);
-            
-            // Print the table
-            table.printstd();
-            
-            // Count winners
-            let mut json_wins = 0;
-            let mut proto_wins = 0;
-            
-            if results.serialization.winner == "JSON" { json_wins += 1; } else { proto_wins += 1; }
-            if results.deserialization.winner == "JSON" { json_wins += 1; } else { proto_wins += 1; }
-            if results.payload_size.uncompressed.winner == "JSON" { json_wins += 1; } else { proto_wins += 1; }
-            if results.payload_size.compressed.winner == "JSON" { json_wins += 1; } else { proto_wins += 1; }
-            if results.cpu_usage.winner == "JSON" { json_wins += 1; } else { proto_wins += 1; }
-            if results.memory_usage.winner == "JSON" { json_wins += 1; } else { proto_wins += 1; }
-            if results.network_transfer.winner == "JSON" { json_wins += 1; } else { proto_wins += 1; }
-            if results.latency_under_load.winner == "JSON" { json_wins += 1; } else { proto_wins += 1; }
-            if results.parser_init.winner == "JSON" { json_wins += 1; } else { proto_wins += 1; }
-            if results.throughput.winner == "JSON" { json_wins += 1; } else { proto_wins += 1; }
-            if results.schema_evolution.winner == "JSON" { json_wins += 1; } else { proto_wins += 1; }
-            
-            println!("\n{}", format!("Overall winner: {} ({} wins vs {} wins)", 
-                                    if json_wins > proto_wins { "JSON" } else { "Protocol Buffers" },
-                                    if json_wins > proto_wins { json_wins } else { proto_wins },
-                                    if json_wins > proto_wins { proto_wins } else { json_wins }
-                                    ).green().bold());
+    // 12. Test unknown field handling (JSON catch-all retention vs prost dropping)
+    pub fn test_unknown_fields(&self) -> UnknownFieldsMetric {
+        eprintln!("{}", "Testing unknown field round-trip...".green());
+        
+        // Data written by a newer service, read and re-written by an older one
+        let (json_evolved, proto_evolved) = generate_evolved_test_data(&self.test_data().0);
+        let json_string = serde_json::to_string(&json_evolved).unwrap();
+        let proto_bytes = proto_evolved.encode_to_vec();
+        
+        // JSON with #[serde(flatten)] catch-all maps keeps unknown fields
+        let json_retain = self.time_per_op(|| {
+            let person: JsonPersonOpen = serde_json::from_str(&json_string).unwrap();
+            let _ = serde_json::to_string(&person).unwrap();
+        });
+        
+        // Plain serde structs silently skip unknown fields
+        let json_drop = self.time_per_op(|| {
+            let person: JsonPerson = serde_json::from_str(&json_string).unwrap();
+            let _ = serde_json::to_string(&person).unwrap();
+        });
+        
+        // prost discards unknown fields on decode
+        let protobuf_drop = self.time_per_op(|| {
+            let person = Person::decode(proto_bytes.as_slice()).unwrap();
+            let _ = person.encode_to_vec();
+        });
+        
+        // Unless the wrapper copies them aside and writes them back out
+        let protobuf_retain = self.time_per_op(|| {
+            let person = RetainingPerson::decode(proto_bytes.as_slice()).unwrap();
+            let _ = person.encode_to_vec();
+        });
+        
+        // Fidelity of a single round trip
+        let original: serde_json::Value = serde_json::from_str(&json_string).unwrap();
+        let retained = serde_json::to_string(&serde_json::from_str::<JsonPersonOpen>(&json_string).unwrap()).unwrap();
+        let dropped = serde_json::to_string(&serde_json::from_str::<JsonPerson>(&json_string).unwrap()).unwrap();
+        let proto_round_trip = Person::decode(proto_bytes.as_slice()).unwrap().encode_to_vec();
+        let proto_retained = RetainingPerson::decode(proto_bytes.as_slice()).unwrap().encode_to_vec();
+        
+        let json_retain_lossless = serde_json::from_str::<serde_json::Value>(&retained).unwrap() == original;
+        let json_drop_lossless = serde_json::from_str::<serde_json::Value>(&dropped).unwrap() == original;
+        let protobuf_lossless = evolved::Person::decode(proto_round_trip.as_slice()).unwrap() == proto_evolved;
+        let protobuf_retain_lossless = evolved::Person::decode(proto_retained.as_slice()).unwrap() == proto_evolved;
+        
+        let json_retain_fidelity = retained.len() as f64 / json_string.len() as f64 * 100.0;
+        let json_drop_fidelity = dropped.len() as f64 / json_string.len() as f64 * 100.0;
+        let protobuf_fidelity = proto_round_trip.len() as f64 / proto_bytes.len() as f64 * 100.0;
+        let protobuf_retain_fidelity = proto_retained.len() as f64 / proto_bytes.len() as f64 * 100.0;
+        
+        eprintln!("JSON (catch-all): {:.4} ms per op, {:.2}% of payload kept, lossless: {}",
+                json_retain, json_retain_fidelity, json_retain_lossless);
+        eprintln!("JSON (plain): {:.4} ms per op, {:.2}% of payload kept, lossless: {}",
+                json_drop, json_drop_fidelity, json_drop_lossless);
+        eprintln!("Protobuf (prost): {:.4} ms per op, {:.2}% of payload kept, lossless: {}",
+                protobuf_drop, protobuf_fidelity, protobuf_lossless);
+        eprintln!("Protobuf (retaining): {:.4} ms per op, {:.2}% of payload kept, lossless: {}",
+                protobuf_retain, protobuf_retain_fidelity, protobuf_retain_lossless);
+        
+        UnknownFieldsMetric {
+            json_retain,
+            json_drop,
+            protobuf_drop,
+            protobuf_retain,
+            json_retain_fidelity,
+            json_drop_fidelity,
+            protobuf_fidelity,
+            protobuf_retain_fidelity,
+            json_retain_lossless,
+            json_drop_lossless,
+            protobuf_lossless,
+            protobuf_retain_lossless,
+        }
+    }
+
+    // 13. Test a message type loaded from a descriptor set at runtime
+    pub fn test_message<M: TestMessage>(&self, message: &M) -> MessageMetric {
+        eprintln!("{}", format!("Testing message {}...", message.name()).green());
+        
+        let (json_record, proto_record) = message.generate(self.data_size);
+        
+        let json_string = message.serialize_json(&json_record);
+        let proto_bytes = message.encode(&proto_record);
+        let json_size = json_string.len() as f64;
+        let proto_size = proto_bytes.len() as f64;
+        
+        let json_ser_time = self.time_per_op(|| {
+            let _ = message.serialize_json(&json_record);
+        });
+        let proto_ser_time = self.time_per_op(|| {
+            let _ = message.encode(&proto_record);
+        });
+        
+        let json_de_time = self.time_per_op(|| {
+            let _ = message.deserialize_json(&json_string);
+        });
+        let proto_de_time = self.time_per_op(|| {
+            let _ = message.decode(&proto_bytes);
+        });
+        
+        eprintln!("JSON size: {} bytes, Protobuf size: {} bytes", json_size, proto_size);
+        eprintln!("JSON serialization: {:.4} ms per op, Protobuf: {:.4} ms per op", json_ser_time, proto_ser_time);
+        eprintln!("JSON deserialization: {:.4} ms per op, Protobuf: {:.4} ms per op", json_de_time, proto_de_time);
+        
+        MessageMetric {
+            message: message.name(),
+            serialization: BenchmarkMetric::lower_is_better(json_ser_time, proto_ser_time),
+            deserialization: BenchmarkMetric::lower_is_better(json_de_time, proto_de_time),
+            payload_size: BenchmarkMetric::lower_is_better(json_size, proto_size),
+        }
+    }
+
+    // 14. Test serializing directly into a gzip stream
+    pub fn test_stream_compression(&self) -> StreamCompressionMetric {
+        eprintln!("{}", "Testing serialization into a compressed stream...".green());
+        
+        let (json_data, proto_data) = self.test_data();
+        
+        // Two steps: serialize to an in-memory buffer, then compress it
+        let json_two_step = self.time_per_op(|| {
+            let json_string = serde_json::to_string(&json_data).unwrap();
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(json_string.as_bytes()).unwrap();
+            let _ = encoder.finish().unwrap();
+        });
+        let proto_two_step = self.time_per_op(|| {
+            let proto_bytes = proto_data.encode_to_vec();
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(&proto_bytes).unwrap();
+            let _ = encoder.finish().unwrap();
+        });
+        
+        // One pass: the serializer writes into the encoder as it goes
+        let json_one_pass = self.time_per_op(|| {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            serde_json::to_writer(&mut encoder, &json_data).unwrap();
+            let _ = encoder.finish().unwrap();
+        });
+        let proto_one_pass = self.time_per_op(|| {
+            // prost encodes into a BufMut, so adapt the encoder's Write
+            let mut writer = WriterBuf::new(GzEncoder::new(Vec::new(), Compression::default()));
+            proto_data.encode(&mut writer).unwrap();
+            let _ = writer.into_inner().unwrap().finish().unwrap();
+        });
+        
+        eprintln!("JSON: {:.4} ms two-step, {:.4} ms one-pass", json_two_step, json_one_pass);
+        eprintln!("Protobuf: {:.4} ms two-step, {:.4} ms one-pass", proto_two_step, proto_one_pass);
+        
+        StreamCompressionMetric {
+            two_step: BenchmarkMetric::lower_is_better(json_two_step, proto_two_step),
+            one_pass: BenchmarkMetric::lower_is_better(json_one_pass, proto_one_pass),
+        }
+    }
+
+    // 15. Test decoding a stream of messages from a reader without buffering the input
+    pub fn test_streaming_decode(&self) -> StreamingDecodeMetric {
+        eprintln!("{}", "Testing streaming decode...".green());
+        
+        let (json_data, proto_data) = self.test_data();
+        let messages = self.iterations;
+        let dir = std::env::temp_dir();
+        let json_path = dir.join(format!("protobuf-json-benchmark-{}.ndjson", std::process::id()));
+        let proto_path = dir.join(format!("protobuf-json-benchmark-{}.pb", std::process::id()));
+        
+        // Write the inputs: newline-delimited JSON and length-delimited protobuf
+        let mut writer = BufWriter::new(File::create(&json_path).unwrap());
+        for _ in 0..messages {
+            serde_json::to_writer(&mut writer, &json_data).unwrap();
+            writer.write_all(b"\n").unwrap();
+        }
+        writer.flush().unwrap();
+        drop(writer);
+        let mut writer = BufWriter::new(File::create(&proto_path).unwrap());
+        let mut buf = Vec::new();
+        for _ in 0..messages {
+            buf.clear();
+            proto_data.encode_length_delimited(&mut buf).unwrap();
+            writer.write_all(&buf).unwrap();
+        }
+        writer.flush().unwrap();
+        drop(writer);
+        drop((json_data, proto_data, buf));
+        
+        let json_input_bytes = std::fs::metadata(&json_path).unwrap().len();
+        let protobuf_input_bytes = std::fs::metadata(&proto_path).unwrap().len();
+        
+        // JSON: one line at a time into a reused buffer
+        let baseline = alloc_tracker::current_bytes();
+        alloc_tracker::reset_peak();
+        let json_start = Instant::now();
+        let mut reader = BufReader::new(File::open(&json_path).unwrap());
+        let mut line = String::new();
+        let mut json_decoded = 0;
+        while reader.read_line(&mut line).unwrap() > 0 {
+            let person: JsonPerson = serde_json::from_str(&line).unwrap();
+            std::hint::black_box(person);
+            json_decoded += 1;
+            line.clear();
+        }
+        drop((reader, line));
+        let json_time = json_start.elapsed().as_secs_f64() * 1000.0;
+        let json_peak = alloc_tracker::peak_bytes().saturating_sub(baseline) as f64;
+        
+        // Protobuf: read each varint length prefix, then exactly that many bytes
+        let baseline = alloc_tracker::current_bytes();
+        alloc_tracker::reset_peak();
+        let proto_start = Instant::now();
+        let mut reader = BufReader::new(File::open(&proto_path).unwrap());
+        let mut message = Vec::new();
+        let mut proto_decoded = 0;
+        while let Some(len) = read_length_delimiter(&mut reader).unwrap() {
+            message.resize(len, 0);
+            reader.read_exact(&mut message).unwrap();
+            let person = Person::decode(message.as_slice()).unwrap();
+            std::hint::black_box(person);
+            proto_decoded += 1;
+        }
+        drop((reader, message));
+        let proto_time = proto_start.elapsed().as_secs_f64() * 1000.0;
+        let proto_peak = alloc_tracker::peak_bytes().saturating_sub(baseline) as f64;
+        
+        let _ = std::fs::remove_file(&json_path);
+        let _ = std::fs::remove_file(&proto_path);
+        assert_eq!(json_decoded, messages);
+        assert_eq!(proto_decoded, messages);
+        
+        let peak_memory = if alloc_tracker::TRACKING {
+            eprintln!("JSON: {} bytes streamed in {:.2} ms, peak {} bytes", json_input_bytes, json_time, json_peak);
+            eprintln!("Protobuf: {} bytes streamed in {:.2} ms, peak {} bytes", protobuf_input_bytes, proto_time, proto_peak);
+            Some(BenchmarkMetric::lower_is_better(json_peak, proto_peak))
         } else {
-            println!("No results to print. Run the tests first.");
+            eprintln!("JSON: {} bytes streamed in {:.2} ms", json_input_bytes, json_time);
+            eprintln!("Protobuf: {} bytes streamed in {:.2} ms", protobuf_input_bytes, proto_time);
+            eprintln!("{}", "Heap tracking is off (built without the `alloc-tracker` feature); no peak memory".yellow());
+            None
+        };
+        
+        StreamingDecodeMetric {
+            messages,
+            json_input_bytes,
+            protobuf_input_bytes,
+            time: BenchmarkMetric::lower_is_better(json_time, proto_time),
+            peak_memory,
+        }
+    }
+
+    // 16. Test a large map on its own (JSON object vs protobuf repeated map entries)
+    pub fn test_map_serialization(&self) -> MapSerializationMetric {
+        eprintln!("{}", format!("Testing map serialization ({} entries)...", self.map_size).green());
+        
+        let (json_map, proto_map) = generate_map_test_data(self.map_size);
+        
+        let json_string = serde_json::to_string(&json_map).unwrap();
+        let proto_bytes = proto_map.encode_to_vec();
+        let json_size = json_string.len() as f64;
+        let proto_size = proto_bytes.len() as f64;
+        
+        let json_ser_time = self.time_per_op(|| {
+            let _ = serde_json::to_string(&json_map).unwrap();
+        });
+        let proto_ser_time = self.time_per_op(|| {
+            let _ = proto_map.encode_to_vec();
+        });
+        
+        let json_de_time = self.time_per_op(|| {
+            let _: HashMap<String, String> = serde_json::from_str(&json_string).unwrap();
+        });
+        let proto_de_time = self.time_per_op(|| {
+            let _ = MetadataMap::decode(proto_bytes.as_slice()).unwrap();
+        });
+        
+        eprintln!("JSON size: {} bytes, Protobuf size: {} bytes", json_size, proto_size);
+        eprintln!("JSON serialization: {:.4} ms per op, Protobuf: {:.4} ms per op", json_ser_time, proto_ser_time);
+        eprintln!("JSON deserialization: {:.4} ms per op, Protobuf: {:.4} ms per op", json_de_time, proto_de_time);
+        
+        MapSerializationMetric {
+            entries: self.map_size,
+            payload_size: BenchmarkMetric::lower_is_better(json_size, proto_size),
+            serialization: BenchmarkMetric::lower_is_better(json_ser_time, proto_ser_time),
+            deserialization: BenchmarkMetric::lower_is_better(json_de_time, proto_de_time),
+        }
+    }
+
+    // 17. Test decoding binary IDs stored as protobuf string vs bytes vs base64 JSON
+    pub fn test_binary_fields(&self) -> BinaryFieldsMetric {
+        eprintln!("{}", "Testing string vs bytes fields...".green());
+        
+        let (json_ids, string_ids, bytes_ids) = generate_id_test_data(self.data_size);
+        
+        let json_string = serde_json::to_string(&json_ids).unwrap();
+        let string_buf = string_ids.encode_to_vec();
+        let bytes_buf = bytes_ids.encode_to_vec();
+        
+        // `string` fields are checked for valid UTF-8 on decode, `bytes` are copied as-is
+        let json_decode = self.time_per_op(|| {
+            let _: JsonIdList = serde_json::from_str(&json_string).unwrap();
+        });
+        let protobuf_string_decode = self.time_per_op(|| {
+            let _ = IdListString::decode(string_buf.as_slice()).unwrap();
+        });
+        let protobuf_bytes_decode = self.time_per_op(|| {
+            let _ = IdListBytes::decode(bytes_buf.as_slice()).unwrap();
+        });
+        
+        eprintln!("JSON (base64): {} bytes, {:.4} ms per decode", json_string.len(), json_decode);
+        eprintln!("Protobuf string (hex): {} bytes, {:.4} ms per decode", string_buf.len(), protobuf_string_decode);
+        eprintln!("Protobuf bytes: {} bytes, {:.4} ms per decode", bytes_buf.len(), protobuf_bytes_decode);
+        
+        BinaryFieldsMetric {
+            ids: self.data_size,
+            json_size: json_string.len(),
+            protobuf_string_size: string_buf.len(),
+            protobuf_bytes_size: bytes_buf.len(),
+            json_decode,
+            protobuf_string_decode,
+            protobuf_bytes_decode,
+        }
+    }
+
+    // 18. Test how decoders handle randomly mutated input
+    pub fn test_fuzzed_input(&self) -> FuzzMetric {
+        eprintln!("{}", format!("Testing {} mutated inputs...", self.iterations).green());
+        
+        let (json_data, proto_data) = self.test_data();
+        let json_bytes = serde_json::to_vec(&json_data).unwrap();
+        let proto_bytes = proto_data.encode_to_vec();
+        let mut rng = StdRng::seed_from_u64(42);
+        
+        // Panics are expected and counted, so keep their messages out of the output
+        let default_hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(|_| {}));
+        
+        let mut json = DecodeOutcomes::default();
+        let mut protobuf = DecodeOutcomes::default();
+        for _ in 0..self.iterations {
+            let mutated = mutate(&json_bytes, &mut rng);
+            json.record(std::panic::catch_unwind(|| serde_json::from_slice::<JsonPerson>(&mutated)));
+            
+            let mutated = mutate(&proto_bytes, &mut rng);
+            protobuf.record(std::panic::catch_unwind(|| Person::decode(mutated.as_slice())));
+        }
+        
+        std::panic::set_hook(default_hook);
+        
+        for (name, outcomes) in [("JSON", &json), ("Protobuf", &protobuf)] {
+            eprintln!("{}: {} rejected, {} accepted, {} panicked",
+                    name, outcomes.rejected, outcomes.accepted, outcomes.panicked);
+        }
+        
+        FuzzMetric {
+            mutations: self.iterations,
+            json,
+            protobuf,
+        }
+    }
+
+    // 19. Test expressing a partial update (JSON Merge Patch vs protobuf FieldMask)
+    pub fn test_partial_update(&self) -> PartialUpdateMetric {
+        eprintln!("{}", "Testing partial updates...".green());
+        
+        let (json_data, proto_data) = self.test_data();
+        let (merge_patch, proto_patch) = generate_partial_update(&json_data, &proto_data);
+        let full_json_size = serde_json::to_vec(&json_data).unwrap().len();
+        let full_protobuf_size = proto_data.encoded_len();
+        
+        // The receiver's stored copy of the record
+        let json_stored = serde_json::to_value(&json_data).unwrap();
+        let proto_stored = Person::decode(proto_data.encode_to_vec().as_slice()).unwrap();
+        
+        let json_bytes = serde_json::to_vec(&merge_patch).unwrap();
+        let proto_bytes = proto_patch.encode_to_vec();
+        let json_size = json_bytes.len() as f64;
+        let proto_size = proto_bytes.len() as f64;
+        
+        let json_ser_time = self.time_per_op(|| {
+            let _ = serde_json::to_vec(&merge_patch).unwrap();
+        });
+        let proto_ser_time = self.time_per_op(|| {
+            let _ = proto_patch.encode_to_vec();
+        });
+        
+        let apply_json = || {
+            let mut target = json_stored.clone();
+            let patch: serde_json::Value = serde_json::from_slice(&json_bytes).unwrap();
+            apply_merge_patch(&mut target, &patch);
+            serde_json::from_value::<JsonPerson>(target).unwrap()
+        };
+        let apply_proto = || {
+            let mut target = proto_stored.clone();
+            apply_field_mask(&mut target, PersonPatch::decode(proto_bytes.as_slice()).unwrap());
+            target
+        };
+        
+        // Both encodings must describe the same update
+        let (json_updated, proto_updated) = (apply_json(), apply_proto());
+        assert_eq!(json_updated.email, proto_updated.email);
+        assert_eq!(json_updated.phones.len(), proto_updated.phones.len());
+        assert_eq!(json_updated.metadata, proto_updated.metadata);
+        
+        let json_apply_time = self.time_per_op(|| {
+            let _ = apply_json();
+        });
+        let proto_apply_time = self.time_per_op(|| {
+            let _ = apply_proto();
+        });
+        
+        eprintln!("Full record: JSON {} bytes, Protobuf {} bytes", full_json_size, full_protobuf_size);
+        eprintln!("Patch size: JSON Merge Patch {} bytes, Protobuf with FieldMask {} bytes", json_size, proto_size);
+        eprintln!("Patch serialization: JSON {:.4} ms per op, Protobuf: {:.4} ms per op", json_ser_time, proto_ser_time);
+        eprintln!("Patch apply: JSON {:.4} ms per op, Protobuf: {:.4} ms per op", json_apply_time, proto_apply_time);
+        
+        PartialUpdateMetric {
+            full_json_size,
+            full_protobuf_size,
+            payload_size: BenchmarkMetric::lower_is_better(json_size, proto_size),
+            serialization: BenchmarkMetric::lower_is_better(json_ser_time, proto_ser_time),
+            apply: BenchmarkMetric::lower_is_better(json_apply_time, proto_apply_time),
+        }
+    }
+
+    // 20. Test an integer-keyed map (JSON string keys vs protobuf int32 keys)
+    pub fn test_int_key_map(&self) -> MapSerializationMetric {
+        eprintln!("{}", format!("Testing integer-keyed map ({} entries)...", self.map_size).green());
+        
+        let (json_map, proto_map) = generate_int_map_test_data(self.map_size);
+        
+        let json_string = serde_json::to_string(&json_map).unwrap();
+        let proto_bytes = proto_map.encode_to_vec();
+        let json_size = json_string.len() as f64;
+        let proto_size = proto_bytes.len() as f64;
+        
+        // JSON formats each key as a quoted decimal string and parses it back to an integer
+        let json_ser_time = self.time_per_op(|| {
+            let _ = serde_json::to_string(&json_map).unwrap();
+        });
+        let proto_ser_time = self.time_per_op(|| {
+            let _ = proto_map.encode_to_vec();
+        });
+        
+        let json_de_time = self.time_per_op(|| {
+            let _: HashMap<i32, String> = serde_json::from_str(&json_string).unwrap();
+        });
+        let proto_de_time = self.time_per_op(|| {
+            let _ = IntKeyMap::decode(proto_bytes.as_slice()).unwrap();
+        });
+        
+        eprintln!("JSON size: {} bytes, Protobuf size: {} bytes", json_size, proto_size);
+        eprintln!("JSON serialization: {:.4} ms per op, Protobuf: {:.4} ms per op", json_ser_time, proto_ser_time);
+        eprintln!("JSON deserialization: {:.4} ms per op, Protobuf: {:.4} ms per op", json_de_time, proto_de_time);
+        
+        MapSerializationMetric {
+            entries: self.map_size,
+            payload_size: BenchmarkMetric::lower_is_better(json_size, proto_size),
+            serialization: BenchmarkMetric::lower_is_better(json_ser_time, proto_ser_time),
+            deserialization: BenchmarkMetric::lower_is_better(json_de_time, proto_de_time),
+        }
+    }
+
+    // 21. Test how absent optional fields interact with compression
+    pub fn test_sparsity(&self) -> SparsityMetric {
+        eprintln!("{}", format!("Testing field omission and compression ({} records)...", self.data_size).green());
+        
+        let rows: Vec<SparsityRow> = [0.0, 0.25, 0.5, 0.75, 1.0].into_iter()
+            .map(|null_fraction| {
+                let (json_profiles, proto_profiles) = generate_optional_test_data(self.data_size, null_fraction);
+                
+                let json_nulls = serde_json::to_vec(&json_profiles).unwrap();
+                let omitted: Vec<JsonProfileOmitted> = json_profiles.into_iter().map(JsonProfileOmitted::from).collect();
+                let json_omitted = serde_json::to_vec(&omitted).unwrap();
+                let protobuf = proto_profiles.encode_to_vec();
+                
+                SparsityRow {
+                    null_fraction,
+                    json_nulls: json_nulls.len(),
+                    json_nulls_gzip: gzip_len(&json_nulls),
+                    json_omitted: json_omitted.len(),
+                    json_omitted_gzip: gzip_len(&json_omitted),
+                    protobuf: protobuf.len(),
+                    protobuf_gzip: gzip_len(&protobuf),
+                }
+            })
+            .collect();
+        
+        print_sparsity(&rows);
+        
+        SparsityMetric { records: self.data_size, rows }
+    }
+
+    // 22. Test how the destination buffer affects serialization cost
+    pub fn test_sinks(&self) -> std::io::Result<Vec<SinkRow>> {
+        eprintln!("{}", "Testing serialization sinks...".green());
+        
+        let (json_data, proto_data) = self.test_data();
+        let json_len = serde_json::to_vec(&json_data).unwrap().len();
+        
+        // A growing Vec reallocates as it fills; prost's `encode_to_vec` would pre-size it
+        let vec_new = SinkRow {
+            sink: "Vec::new()",
+            json: self.time_per_op(|| {
+                let mut buf = Vec::new();
+                serde_json::to_writer(&mut buf, &json_data).unwrap();
+            }),
+            protobuf: Some(self.time_per_op(|| {
+                let mut buf = Vec::new();
+                proto_data.encode(&mut buf).unwrap();
+            })),
+        };
+        let vec_sized = SinkRow {
+            sink: "Vec::with_capacity",
+            json: self.time_per_op(|| {
+                let mut buf = Vec::with_capacity(json_len);
+                serde_json::to_writer(&mut buf, &json_data).unwrap();
+            }),
+            protobuf: Some(self.time_per_op(|| {
+                let mut buf = Vec::with_capacity(proto_data.encoded_len());
+                proto_data.encode(&mut buf).unwrap();
+            })),
+        };
+        let string = SinkRow {
+            sink: "String",
+            json: self.time_per_op(|| {
+                let _ = serde_json::to_string(&json_data).unwrap();
+            }),
+            protobuf: None,
+        };
+        
+        // Each op writes one record, flushes and rewinds, so the file stays one record long
+        let path = std::env::temp_dir().join(format!("protobuf-json-benchmark-{}.tmp", std::process::id()));
+        let mut writer = BufWriter::new(File::create(&path)?);
+        let json_file = self.time_per_op(|| {
+            serde_json::to_writer(&mut writer, &json_data).unwrap();
+            writer.flush().unwrap();
+            writer.rewind().unwrap();
+        });
+        let proto_file = self.time_per_op(|| {
+            let mut buf = WriterBuf::new(&mut writer);
+            proto_data.encode(&mut buf).unwrap();
+            buf.into_inner().unwrap();
+            writer.flush().unwrap();
+            writer.rewind().unwrap();
+        });
+        drop(writer);
+        std::fs::remove_file(&path)?;
+        let file = SinkRow { sink: "BufWriter<File>", json: json_file, protobuf: Some(proto_file) };
+        
+        let rows = vec![vec_new, vec_sized, string, file];
+        let mut table = Table::new();
+        table.add_row(row![bFg->"Sink", bFg->"JSON (ms/op)", bFg->"Protobuf (ms/op)"]);
+        for row in &rows {
+            let protobuf = row.protobuf.map_or("n/a".to_string(), |ms| format!("{:.4}", ms));
+            table.add_row(row![row.sink, format!("{:.4}", row.json), protobuf]);
         }
+        eprint!("{}", table);
+        
+        Ok(rows)
+    }
+
+    // 23. Test a data shape where protobuf is larger than JSON
+    pub fn test_small_numbers(&self) -> BenchmarkMetric {
+        eprintln!("{}", format!("Testing small numbers ({} values each)...", self.data_size).green());
+        
+        let (json_numbers, proto_numbers) = generate_small_number_test_data(self.data_size);
+        let json_size = serde_json::to_vec(&json_numbers).unwrap().len();
+        let proto_bytes = proto_numbers.encode_to_vec();
+        let proto_size = proto_bytes.len();
+        
+        // Negative varints are sign-extended to 64 bits and must come back unchanged
+        let decoded = SmallNumbers::decode(proto_bytes.as_slice()).unwrap();
+        assert_eq!(decoded.deltas, json_numbers.deltas);
+        
+        eprintln!("JSON size: {} bytes, Protobuf size: {} bytes", json_size, proto_size);
+        warn_if_protobuf_larger(json_size, proto_size);
+        
+        BenchmarkMetric::lower_is_better(json_size as f64, proto_size as f64)
+    }
+
+    // 24. Test transcoding JSON to protobuf and back
+    pub fn test_transcoding(&self) -> TranscodingMetric {
+        eprintln!("{}", "Testing transcoding...".green());
+        
+        let (json_data, proto_data) = self.test_data();
+        let json_bytes = serde_json::to_vec(&json_data).unwrap();
+        let proto_bytes = proto_data.encode_to_vec();
+        
+        let to_protobuf = |json_bytes: &[u8]| {
+            let person: JsonPerson = serde_json::from_slice(json_bytes).unwrap();
+            json_person_to_proto(&person).encode_to_vec()
+        };
+        let to_json = |proto_bytes: &[u8]| {
+            let person = Person::decode(proto_bytes).unwrap();
+            serde_json::to_vec(&proto_person_to_json(&person)).unwrap()
+        };
+        
+        // A round trip through protobuf must give back the same document
+        let round_trip: serde_json::Value = serde_json::from_slice(&to_json(&to_protobuf(&json_bytes))).unwrap();
+        assert_eq!(round_trip, serde_json::to_value(&json_data).unwrap());
+        
+        let json_to_protobuf = self.time_per_op(|| {
+            let _ = to_protobuf(&json_bytes);
+        });
+        let protobuf_to_json = self.time_per_op(|| {
+            let _ = to_json(&proto_bytes);
+        });
+        
+        eprintln!("JSON -> Protobuf: {:.4} ms per op ({:.0} ops/s)", json_to_protobuf, 1000.0 / json_to_protobuf);
+        eprintln!("Protobuf -> JSON: {:.4} ms per op ({:.0} ops/s)", protobuf_to_json, 1000.0 / protobuf_to_json);
+        
+        TranscodingMetric { json_to_protobuf, protobuf_to_json }
     }
-}
\ No newline at end of file
+
+    // 25. Test deallocation cost of decoded records
+    pub fn test_drop_cost(&self) -> DropCostMetric {
+        eprintln!("{}", format!("Testing drop cost ({} decoded objects)...", self.data_size).green());
+        
+        let (json_data, proto_data) = self.test_data();
+        let json_bytes = serde_json::to_vec(&json_data).unwrap();
+        let proto_bytes = proto_data.encode_to_vec();
+        
+        // Build the collection untimed, then time only its drop. black_box keeps
+        // the objects from being elided before the drop.
+        let (json_time, json_freed_bytes) = median_drop(|| {
+            (0..self.data_size)
+                .map(|_| serde_json::from_slice::<JsonPerson>(&json_bytes).unwrap())
+                .collect::<Vec<_>>()
+        });
+        let (proto_time, protobuf_freed_bytes) = median_drop(|| {
+            (0..self.data_size)
+                .map(|_| Person::decode(proto_bytes.as_slice()).unwrap())
+                .collect::<Vec<_>>()
+        });
+        
+        if alloc_tracker::TRACKING {
+            eprintln!("JSON drop time: {:.4} ms ({} bytes freed)", json_time, json_freed_bytes);
+            eprintln!("Protobuf drop time: {:.4} ms ({} bytes freed)", proto_time, protobuf_freed_bytes);
+        } else {
+            eprintln!("JSON drop time: {:.4} ms", json_time);
+            eprintln!("Protobuf drop time: {:.4} ms", proto_time);
+        }
+        
+        DropCostMetric {
+            objects: self.data_size,
+            time: BenchmarkMetric::lower_is_better(json_time, proto_time),
+            json_freed_bytes: alloc_tracker::TRACKING.then_some(json_freed_bytes),
+            protobuf_freed_bytes: alloc_tracker::TRACKING.then_some(protobuf_freed_bytes),
+        }
+    }
+
+    // 26. Test a batch of user-supplied records (--input)
+    pub fn test_input_batch(&self, records: &[JsonPerson]) -> InputBatchMetric {
+        eprintln!("{}", format!("Testing input batch ({} records)...", records.len()).green());
+        
+        let proto_records: Vec<Person> = records.iter().map(json_person_to_proto).collect();
+        let json_encoded: Vec<Vec<u8>> = records.iter().map(|r| serde_json::to_vec(r).unwrap()).collect();
+        let proto_encoded: Vec<Vec<u8>> = proto_records.iter().map(|r| r.encode_to_vec()).collect();
+        let json_size: usize = json_encoded.iter().map(Vec::len).sum();
+        let proto_size: usize = proto_encoded.iter().map(Vec::len).sum();
+        
+        let json_ser = time_passes(|| {
+            for record in records {
+                std::hint::black_box(serde_json::to_vec(record).unwrap());
+            }
+        });
+        let proto_ser = time_passes(|| {
+            for record in &proto_records {
+                std::hint::black_box(record.encode_to_vec());
+            }
+        });
+        let json_de = time_passes(|| {
+            for bytes in &json_encoded {
+                std::hint::black_box(serde_json::from_slice::<JsonPerson>(bytes).unwrap());
+            }
+        });
+        let proto_de = time_passes(|| {
+            for bytes in &proto_encoded {
+                std::hint::black_box(Person::decode(bytes.as_slice()).unwrap());
+            }
+        });
+        
+        self.record_volume(2.0 * json_size as f64 * INPUT_PASSES as f64, (json_ser + json_de) * INPUT_PASSES as f64,
+                           2.0 * proto_size as f64 * INPUT_PASSES as f64, (proto_ser + proto_de) * INPUT_PASSES as f64);
+        
+        eprintln!("JSON: {} bytes, serialize {:.4} ms, deserialize {:.4} ms per batch", json_size, json_ser, json_de);
+        eprintln!("Protobuf: {} bytes, serialize {:.4} ms, deserialize {:.4} ms per batch", proto_size, proto_ser, proto_de);
+        
+        InputBatchMetric {
+            records: records.len(),
+            serialization: BenchmarkMetric::lower_is_better(json_ser, proto_ser),
+            deserialization: BenchmarkMetric::lower_is_better(json_de, proto_de),
+            payload_size: BenchmarkMetric::lower_is_better(json_size as f64, proto_size as f64),
+        }
+    }
+
+    // 27. Test every format under every compressor
+    pub fn test_compression_matrix(&self) -> CompressionMatrix {
+        eprintln!("{}", "Testing compression matrix...".green());
+        
+        let (json_data, proto_data) = self.test_data();
+        let rows: Vec<(String, Vec<usize>)> = self.formats
+            .iter()
+            .map(|format| {
+                let bytes = format.serialize(&json_data, &proto_data);
+                let sizes = Compressor::ALL.iter().map(|compressor| compressor.compress(&bytes).len()).collect();
+                (format.label().to_string(), sizes)
+            })
+            .collect();
+        
+        let mut table = Table::new();
+        let mut header = vec![Cell::new("Format (bytes)").style_spec("bFg")];
+        header.extend(Compressor::ALL.iter().map(|compressor| Cell::new(compressor.name()).style_spec("bFg")));
+        table.add_row(Row::new(header));
+        for (format, sizes) in &rows {
+            let mut cells = vec![Cell::new(format)];
+            cells.extend(sizes.iter().map(|size| Cell::new(&size.to_string())));
+            table.add_row(Row::new(cells));
+        }
+        table.printstd();
+        
+        CompressionMatrix {
+            compressors: Compressor::ALL.iter().map(Compressor::name).collect(),
+            rows,
+        }
+    }
+
+    // 28. Test a numeric array on its own (JSON array vs protobuf packed doubles)
+    pub fn test_double_array(&self) -> DoubleArrayMetric {
+        eprintln!("{}", format!("Testing double array ({} values)...", self.array_size).green());
+        
+        let (json_array, proto_array) = generate_double_array_test_data(self.array_size);
+        let json_bytes = serde_json::to_vec(&json_array).unwrap();
+        let proto_bytes = proto_array.encode_to_vec();
+        let json_size = json_bytes.len() as f64;
+        let proto_size = proto_bytes.len() as f64;
+        
+        // JSON formats every double as shortest round-trip decimal text; packed
+        // protobuf copies 8 little-endian bytes per value
+        let json_ser_time = self.time_per_op(|| {
+            let _ = serde_json::to_vec(&json_array).unwrap();
+        });
+        let proto_ser_time = self.time_per_op(|| {
+            let _ = proto_array.encode_to_vec();
+        });
+        
+        let json_de_time = self.time_per_op(|| {
+            let _: JsonDoubleArray = serde_json::from_slice(&json_bytes).unwrap();
+        });
+        let proto_de_time = self.time_per_op(|| {
+            let _ = DoubleArray::decode(proto_bytes.as_slice()).unwrap();
+        });
+        
+        eprintln!("JSON size: {} bytes, Protobuf size: {} bytes", json_size, proto_size);
+        eprintln!("JSON serialization: {:.4} ms per op, Protobuf: {:.4} ms per op", json_ser_time, proto_ser_time);
+        eprintln!("JSON deserialization: {:.4} ms per op, Protobuf: {:.4} ms per op", json_de_time, proto_de_time);
+        
+        DoubleArrayMetric {
+            values: self.array_size,
+            payload_size: BenchmarkMetric::lower_is_better(json_size, proto_size),
+            serialization: BenchmarkMetric::lower_is_better(json_ser_time, proto_ser_time),
+            deserialization: BenchmarkMetric::lower_is_better(json_de_time, proto_de_time),
+        }
+    }
+
+    // 29. Find the --size at which protobuf overtakes JSON on each metric
+    pub fn test_crossover(&self) -> Vec<Crossover> {
+        eprintln!("{}", format!("Finding crossover sizes (1 to {})...", CROSSOVER_MAX_SIZE).green());
+        
+        // (JSON, protobuf) per metric at each probed size
+        let mut probes: BTreeMap<usize, [(f64, f64); 4]> = BTreeMap::new();
+        let mut probe = |size: usize| *probes.entry(size).or_insert_with(|| self.crossover_probe(size));
+        
+        // Sweep powers of two, then bisect between the last size JSON won and the
+        // first size protobuf won from then on
+        let sweep: Vec<usize> = std::iter::successors(Some(1), |size| Some(size * 2))
+            .take_while(|size| *size <= CROSSOVER_MAX_SIZE)
+            .collect();
+        let swept: Vec<[(f64, f64); 4]> = sweep.iter().map(|size| probe(*size)).collect();
+        
+        let mut crossovers = Vec::new();
+        for (m, metric) in CROSSOVER_METRICS.iter().enumerate() {
+            let protobuf_wins = |values: [(f64, f64); 4]| values[m].1 < values[m].0;
+            let first_win = (0..sweep.len()).rev()
+                .take_while(|i| protobuf_wins(swept[*i]))
+                .last();
+            let size = first_win.map(|i| {
+                if i == 0 {
+                    return sweep[0];
+                }
+                let (mut lo, mut hi) = (sweep[i - 1], sweep[i]);
+                while hi - lo > 1 {
+                    let mid = (lo + hi) / 2;
+                    if protobuf_wins(probe(mid)) { hi = mid } else { lo = mid }
+                }
+                hi
+            });
+            match size {
+                Some(1) => eprintln!("{}: protobuf ahead at every size", metric),
+                Some(size) => eprintln!("{}: crossover at {} elements", metric, size),
+                None => eprintln!("{}: no crossover up to {} elements", metric, CROSSOVER_MAX_SIZE),
+            }
+            crossovers.push(Crossover { metric, size });
+        }
+        crossovers
+    }
+
+    // 30. Test how much of protobuf's size advantage is JSON field names
+    pub fn test_short_keys(&self) -> ShortKeysMetric {
+        eprintln!("{}", "Testing short JSON keys...".green());
+        
+        let (json_data, proto_data) = self.test_data();
+        let json_bytes = serde_json::to_vec(&json_data).unwrap();
+        let short_bytes = serde_json::to_vec(&JsonPersonShortKeys::from(json_data)).unwrap();
+        let proto_bytes = proto_data.encode_to_vec();
+        
+        // Compared both raw and after gzip, which already shares repeated keys
+        let gap_closed = |json: usize, short: usize, protobuf: usize| {
+            (json as f64 - short as f64) / (json as f64 - protobuf as f64) * 100.0
+        };
+        let gap_closed_percent = gap_closed(json_bytes.len(), short_bytes.len(), proto_bytes.len());
+        let compressed_gap_closed_percent = gap_closed(gzip_len(&json_bytes), gzip_len(&short_bytes), gzip_len(&proto_bytes));
+        
+        let mut table = Table::new();
+        table.add_row(row![bFg->"Format", bFg->"Bytes", bFg->"Gzip bytes"]);
+        table.add_row(row!["JSON", json_bytes.len(), gzip_len(&json_bytes)]);
+        table.add_row(row!["JSON (short keys)", short_bytes.len(), gzip_len(&short_bytes)]);
+        table.add_row(row!["Protobuf", proto_bytes.len(), gzip_len(&proto_bytes)]);
+        eprint!("{}", table);
+        eprintln!("Short keys close {:.1}% of the size gap to protobuf ({:.1}% after gzip)",
+                gap_closed_percent, compressed_gap_closed_percent);
+        
+        ShortKeysMetric {
+            json: json_bytes.len(),
+            json_short_keys: short_bytes.len(),
+            protobuf: proto_bytes.len(),
+            gap_closed_percent,
+            compressed_gap_closed_percent,
+        }
+    }
+
+    // 31. Test records that share substructures in memory (neither format dedups them on the wire)
+    pub fn test_shared_data(&self) -> SharedDataMetric {
+        eprintln!("{}", format!("Testing shared addresses ({} records, {} shared addresses)...",
+                              self.data_size, SHARED_ADDRESSES).green());
+        
+        let before = alloc_tracker::current_bytes();
+        let shared = generate_shared_address_test_data(self.data_size, SHARED_ADDRESSES);
+        let shared_heap = alloc_tracker::current_bytes().saturating_sub(before);
+        
+        let before = alloc_tracker::current_bytes();
+        let materialized: Vec<JsonPerson> = shared.iter().map(JsonPerson::from).collect();
+        let materialized_heap = alloc_tracker::current_bytes().saturating_sub(before);
+        let proto_materialized: Vec<Person> = materialized.iter().map(json_person_to_proto).collect();
+        
+        // Every shared address is written out in full for every record
+        let json_bytes = serde_json::to_vec(&materialized).unwrap();
+        assert_eq!(serde_json::to_vec(&shared).unwrap(), json_bytes);
+        let protobuf_size: usize = proto_materialized.iter().map(|person| person.encoded_len()).sum();
+        
+        let json_materialized = self.time_per_op(|| {
+            let _ = serde_json::to_vec(&materialized).unwrap();
+        });
+        let json_shared = self.time_per_op(|| {
+            let _ = serde_json::to_vec(&shared).unwrap();
+        });
+        let protobuf_materialized = self.time_per_op(|| {
+            for person in &proto_materialized {
+                let _ = person.encode_to_vec();
+            }
+        });
+        // prost messages own their fields, so shared data must be copied out first
+        let protobuf_shared = self.time_per_op(|| {
+            for person in &shared {
+                let _ = shared_person_to_proto(person).encode_to_vec();
+            }
+        });
+        
+        if alloc_tracker::TRACKING {
+            eprintln!("Heap: materialized {} bytes, shared {} bytes", materialized_heap, shared_heap);
+        }
+        eprintln!("Wire: JSON {} bytes, Protobuf {} bytes for either representation", json_bytes.len(), protobuf_size);
+        eprintln!("JSON serialization: materialized {:.4} ms, shared {:.4} ms", json_materialized, json_shared);
+        eprintln!("Protobuf serialization: materialized {:.4} ms, shared {:.4} ms", protobuf_materialized, protobuf_shared);
+        
+        SharedDataMetric {
+            records: self.data_size,
+            distinct_addresses: SHARED_ADDRESSES,
+            materialized_heap: alloc_tracker::TRACKING.then_some(materialized_heap),
+            shared_heap: alloc_tracker::TRACKING.then_some(shared_heap),
+            json_size: json_bytes.len(),
+            protobuf_size,
+            json_materialized,
+            json_shared,
+            protobuf_materialized,
+            protobuf_shared,
+        }
+    }
+
+    // 32. Test stream decode under backpressure from a bounded channel and a slow consumer
+    pub async fn test_backpressure_decode(&self) -> BackpressureMetric {
+        eprintln!("{}", format!("Testing stream decode with backpressure (buffer {})...", BACKPRESSURE_BUFFER).green());
+        
+        let (json_data, proto_data) = self.test_data();
+        let messages = self.iterations;
+        
+        // The same framings as the streaming test: line-delimited JSON, length-delimited protobuf
+        let mut json_stream = Vec::new();
+        let mut proto_stream = Vec::new();
+        for _ in 0..messages {
+            serde_json::to_writer(&mut json_stream, &json_data).unwrap();
+            json_stream.push(b'\n');
+            proto_data.encode_length_delimited(&mut proto_stream).unwrap();
+        }
+        
+        // The producer decodes frames as fast as the channel accepts them
+        let json_start = Instant::now();
+        let (tx, rx) = tokio::sync::mpsc::channel::<JsonPerson>(BACKPRESSURE_BUFFER);
+        let producer = tokio::spawn(async move {
+            let mut waited = Duration::ZERO;
+            for line in json_stream.split(|byte| *byte == b'\n').filter(|line| !line.is_empty()) {
+                let person: JsonPerson = serde_json::from_slice(line).unwrap();
+                let send_start = Instant::now();
+                tx.send(person).await.unwrap();
+                waited += send_start.elapsed();
+            }
+            waited
+        });
+        let json_received = slow_consumer(rx).await;
+        let json_waited = producer.await.unwrap();
+        let json_time = json_start.elapsed().as_secs_f64();
+        
+        let proto_start = Instant::now();
+        let (tx, rx) = tokio::sync::mpsc::channel::<Person>(BACKPRESSURE_BUFFER);
+        let producer = tokio::spawn(async move {
+            let mut waited = Duration::ZERO;
+            let mut stream = proto_stream.as_slice();
+            while !stream.is_empty() {
+                let person = Person::decode_length_delimited(&mut stream).unwrap();
+                let send_start = Instant::now();
+                tx.send(person).await.unwrap();
+                waited += send_start.elapsed();
+            }
+            waited
+        });
+        let proto_received = slow_consumer(rx).await;
+        let proto_waited = producer.await.unwrap();
+        let proto_time = proto_start.elapsed().as_secs_f64();
+        
+        assert_eq!(json_received, messages);
+        assert_eq!(proto_received, messages);
+        
+        let json_msgs_per_sec = messages as f64 / json_time;
+        let protobuf_msgs_per_sec = messages as f64 / proto_time;
+        let json_send_wait_ms = json_waited.as_secs_f64() * 1000.0;
+        let protobuf_send_wait_ms = proto_waited.as_secs_f64() * 1000.0;
+        
+        // Once the consumer is the bottleneck both formats converge on its rate, and
+        // the faster decoder just spends longer blocked on send
+        eprintln!("JSON: {:.0} msgs/s, producer blocked {:.2} ms", json_msgs_per_sec, json_send_wait_ms);
+        eprintln!("Protobuf: {:.0} msgs/s, producer blocked {:.2} ms", protobuf_msgs_per_sec, protobuf_send_wait_ms);
+        
+        BackpressureMetric {
+            messages,
+            buffer: BACKPRESSURE_BUFFER,
+            json_msgs_per_sec,
+            protobuf_msgs_per_sec,
+            json_send_wait_ms,
+            protobuf_send_wait_ms,
+        }
+    }
+
+    // 33. Test decoding into a reused protobuf message
+    pub fn test_decode_reuse(&self) -> DecodeReuseMetric {
+        eprintln!("{}", "Testing decode with message reuse...".green());
+        
+        let (json_data, proto_data) = self.test_data();
+        let json_bytes = serde_json::to_vec(&json_data).unwrap();
+        let proto_bytes = proto_data.encode_to_vec();
+        
+        // serde has no public way to deserialize into an existing value
+        let json = self.time_per_op(|| {
+            let _: JsonPerson = serde_json::from_slice(&json_bytes).unwrap();
+        });
+        let protobuf_fresh = self.time_per_op(|| {
+            let _ = Person::decode(proto_bytes.as_slice()).unwrap();
+        });
+        
+        // `clear` keeps the capacity of top-level strings, the phones and
+        // addresses vectors and the metadata table, though the nested messages'
+        // own strings are still dropped and reallocated
+        let mut reused = Person::default();
+        let protobuf_reused = self.time_per_op(|| {
+            reused.clear();
+            reused.merge(proto_bytes.as_slice()).unwrap();
+        });
+        assert_eq!(reused, Person::decode(proto_bytes.as_slice()).unwrap());
+        
+        let improvement_percent = (protobuf_fresh - protobuf_reused) / protobuf_fresh * 100.0;
+        
+        eprintln!("JSON: {:.4} ms per op", json);
+        eprintln!("Protobuf (fresh): {:.4} ms per op", protobuf_fresh);
+        eprintln!("Protobuf (reused): {:.4} ms per op, {:.1}% faster than fresh", protobuf_reused, improvement_percent);
+        
+        DecodeReuseMetric {
+            json,
+            protobuf_fresh,
+            protobuf_reused,
+            improvement_percent,
+        }
+    }
+
+    // 34. Test building the in-memory records themselves
+    pub fn test_data_construction(&self) -> ConstructionMetric {
+        eprintln!("{}", "Testing data construction...".green());
+        
+        // Both builders produce the same shape; the timings include dropping the
+        // record again (see the drop-cost test for that part on its own)
+        let json = self.time_per_op(|| {
+            std::hint::black_box(build_json_person(self.data_size, &self.data_options));
+        });
+        let protobuf = self.time_per_op(|| {
+            std::hint::black_box(build_proto_person(self.data_size, &self.data_options));
+        });
+        let ratio = protobuf / json;
+        
+        eprintln!("JsonPerson: {:.4} ms per record", json);
+        eprintln!("Person: {:.4} ms per record ({:.2}x JsonPerson)", protobuf, ratio);
+        
+        ConstructionMetric { json, protobuf, ratio }
+    }
+
+    // 35. Test number precision through serde_json::Value
+    pub fn test_number_precision(&self) -> NumberPrecisionMetric {
+        eprintln!("{}", "Testing number precision...".green());
+        
+        let arbitrary_precision = cfg!(feature = "arbitrary-precision");
+        let (wide_json, literals) = generate_wide_number_json(self.data_size);
+        
+        // Without the feature, numbers outside i64/u64 become f64 and lose digits;
+        // with it, `Value` keeps the literal text, which costs time to store and convert
+        let json_value_parse = self.time_per_op(|| {
+            let _: serde_json::Value = serde_json::from_str(&wide_json).unwrap();
+        });
+        let parsed: serde_json::Value = serde_json::from_str(&wide_json).unwrap();
+        let json_value_serialize = self.time_per_op(|| {
+            let _ = serde_json::to_string(&parsed).unwrap();
+        });
+        
+        let records = parsed.as_array().unwrap();
+        let exact_percent: Vec<(&'static str, f64)> = literals[0].iter().map(|(field, _)| *field)
+            .enumerate()
+            .map(|(i, field)| {
+                let exact = records.iter().zip(&literals)
+                    .filter(|(record, literal)| serde_json::to_string(&record[field]).unwrap() == literal[i].1)
+                    .count();
+                (field, exact as f64 / records.len() as f64 * 100.0)
+            })
+            .collect();
+        
+        // The 64-bit scenario with typed targets and protobuf varints, which are
+        // exact either way
+        let (json_ints, varint_ints, _) = generate_large_int_test_data(self.data_size);
+        let int_json = serde_json::to_vec(&json_ints).unwrap();
+        let varint_bytes = varint_ints.encode_to_vec();
+        let json_typed_parse = self.time_per_op(|| {
+            let _: Vec<JsonLargeInts> = serde_json::from_slice(&int_json).unwrap();
+        });
+        let protobuf_decode = self.time_per_op(|| {
+            let _ = LargeIntsVarintList::decode(varint_bytes.as_slice()).unwrap();
+        });
+        
+        eprintln!("serde_json arbitrary_precision: {}", if arbitrary_precision { "enabled" } else { "disabled" });
+        eprintln!("Value parse: {:.4} ms, Value serialize: {:.4} ms", json_value_parse, json_value_serialize);
+        for (field, percent) in &exact_percent {
+            eprintln!("  {}: {:.1}% exact", field, percent);
+        }
+        eprintln!("Typed JSON parse (64-bit ints): {:.4} ms, Protobuf decode: {:.4} ms", json_typed_parse, protobuf_decode);
+        
+        NumberPrecisionMetric {
+            arbitrary_precision,
+            json_value_parse,
+            json_value_serialize,
+            json_typed_parse,
+            protobuf_decode,
+            exact_percent,
+        }
+    }
+
+    // 36. Test varint encoding across integer magnitudes
+    pub fn test_varint_magnitude(&self) -> Vec<VarintPoint> {
+        eprintln!("{}", "Testing varint size by magnitude...".green());
+        
+        // The largest value of each varint length (7 payload bits per byte) up to
+        // i64::MAX, then negatives, which int64 sign-extends to a 10-byte varint
+        let mut values: Vec<i64> = (1..=9).map(|bytes| ((1u64 << (7 * bytes)) - 1) as i64).collect();
+        values.extend([-1, -1_000_000]);
+        
+        let points: Vec<VarintPoint> = generate_int_value_test_data(&values).into_iter()
+            .map(|(json_value, proto_value)| {
+                let proto_bytes = proto_value.encode_to_vec();
+                assert_eq!(IntValue::decode(proto_bytes.as_slice()).unwrap().value, json_value.value);
+                VarintPoint {
+                    value: json_value.value,
+                    varint_bytes: prost::encoding::encoded_len_varint(proto_value.value as u64),
+                    decimal_bytes: json_value.value.to_string().len(),
+                    protobuf_bytes: proto_bytes.len(),
+                    json_bytes: serde_json::to_vec(&json_value).unwrap().len(),
+                    protobuf_ns: self.time_per_op(|| { let _ = proto_value.encode_to_vec(); }) * 1e6,
+                    json_ns: self.time_per_op(|| { let _ = serde_json::to_vec(&json_value).unwrap(); }) * 1e6,
+                }
+            })
+            .collect();
+        
+        eprintln!("{:>20} {:>7} {:>7} {:>8} {:>8} {:>10} {:>10}",
+                "value", "varint", "digits", "pb B", "json B", "pb ns", "json ns");
+        for point in &points {
+            eprintln!("{:>20} {:>7} {:>7} {:>8} {:>8} {:>10.1} {:>10.1}",
+                    point.value, point.varint_bytes, point.decimal_bytes, point.protobuf_bytes, point.json_bytes,
+                    point.protobuf_ns, point.json_ns);
+        }
+        
+        points
+    }
+
+    // 37. Test per-message compression with a shared zstd dictionary
+    pub fn test_dictionary_compression(&self) -> DictionaryCompressionMetric {
+        eprintln!("{}", "Testing dictionary compression...".green());
+        
+        // Train on one set of records and compress others, as a service would
+        // with a dictionary shipped ahead of time
+        let pool = generate_test_data_pool(self.data_size, DICTIONARY_TRAINING_RECORDS + DICTIONARY_TEST_RECORDS, &self.data_options);
+        let json_messages: Vec<Vec<u8>> = pool.iter().map(|(json, _)| serde_json::to_vec(json).unwrap()).collect();
+        let proto_messages: Vec<Vec<u8>> = pool.iter().map(|(_, proto)| proto.encode_to_vec()).collect();
+        
+        let rows: Vec<DictionaryRow> = [("JSON", json_messages), ("Protobuf", proto_messages)]
+            .into_iter()
+            .map(|(format, messages)| {
+                let (training, held_out) = messages.split_at(DICTIONARY_TRAINING_RECORDS);
+                let dictionary = train_zstd_dictionary(training, DICTIONARY_MAX_BYTES);
+                let (mut compressor, mut decompressor) = zstd_dictionary_codec(&dictionary);
+                
+                let average = |sizes: Vec<usize>| sizes.iter().sum::<usize>() as f64 / sizes.len() as f64;
+                let with_dictionary: Vec<Vec<u8>> = held_out.iter().map(|message| compressor.compress(message).unwrap()).collect();
+                for (compressed, message) in with_dictionary.iter().zip(held_out) {
+                    assert_eq!(&decompressor.decompress(compressed, message.len()).unwrap(), message);
+                }
+                
+                let mut next = held_out.iter().cycle();
+                let zstd_dictionary_ms = self.time_per_op(|| {
+                    let _ = compressor.compress(next.next().unwrap()).unwrap();
+                });
+                
+                DictionaryRow {
+                    format,
+                    dictionary_bytes: dictionary.len(),
+                    raw: average(held_out.iter().map(Vec::len).collect()),
+                    gzip: average(held_out.iter().map(|message| Compressor::Gzip.compress(message).len()).collect()),
+                    zstd: average(held_out.iter().map(|message| Compressor::Zstd.compress(message).len()).collect()),
+                    zstd_dictionary: average(with_dictionary.iter().map(Vec::len).collect()),
+                    zstd_dictionary_ms,
+                }
+            })
+            .collect();
+        
+        eprintln!("Average bytes per message over {} held-out records:", DICTIONARY_TEST_RECORDS);
+        for row in &rows {
+            eprintln!("{}: raw {:.1}, gzip {:.1}, zstd {:.1}, zstd + {}-byte dictionary {:.1} ({:.1}% of gzip, {:.4} ms)",
+                    row.format, row.raw, row.gzip, row.zstd, row.dictionary_bytes, row.zstd_dictionary,
+                    row.zstd_dictionary / row.gzip * 100.0, row.zstd_dictionary_ms);
+        }
+        
+        DictionaryCompressionMetric {
+            records: DICTIONARY_TEST_RECORDS,
+            rows,
+        }
+    }
+
+    // 38. Test monetary amounts as floats, decimal text and scaled integers
+    pub fn test_money(&self) -> Vec<RepresentationRow> {
+        eprintln!("{}", format!("Testing money representations ({} amounts)...", self.data_size).green());
+        
+        let data = generate_money_test_data(self.data_size);
+        let exact_percent = |decoded: Vec<Option<i64>>| {
+            let exact = decoded.iter().zip(&data.scaled).filter(|(amount, expected)| **amount == Some(**expected)).count();
+            exact as f64 / data.scaled.len() as f64 * 100.0
+        };
+        // A float is exact only if its shortest decimal form is the original amount
+        let float_amount = |value: f64| parse_money(&value.to_string());
+        
+        let json_number = serde_json::to_vec(&data.json_number).unwrap();
+        let json_string = serde_json::to_vec(&data.json_string).unwrap();
+        let proto_double = data.proto_double.encode_to_vec();
+        let proto_decimal = data.proto_decimal.encode_to_vec();
+        let proto_scaled = data.proto_scaled.encode_to_vec();
+        
+        let rows = vec![
+            RepresentationRow {
+                representation: "JSON number",
+                bytes: json_number.len(),
+                serialize_ms: self.time_per_op(|| { let _ = serde_json::to_vec(&data.json_number).unwrap(); }),
+                deserialize_ms: self.time_per_op(|| { let _: Vec<JsonMoney> = serde_json::from_slice(&json_number).unwrap(); }),
+                exact_percent: exact_percent(serde_json::from_slice::<Vec<JsonMoney>>(&json_number).unwrap()
+                    .iter().map(|money| float_amount(money.amount)).collect()),
+            },
+            RepresentationRow {
+                representation: "JSON string",
+                bytes: json_string.len(),
+                serialize_ms: self.time_per_op(|| { let _ = serde_json::to_vec(&data.json_string).unwrap(); }),
+                deserialize_ms: self.time_per_op(|| { let _: Vec<JsonMoneyString> = serde_json::from_slice(&json_string).unwrap(); }),
+                exact_percent: exact_percent(serde_json::from_slice::<Vec<JsonMoneyString>>(&json_string).unwrap()
+                    .iter().map(|money| parse_money(&money.amount)).collect()),
+            },
+            RepresentationRow {
+                representation: "Protobuf double",
+                bytes: proto_double.len(),
+                serialize_ms: self.time_per_op(|| { let _ = data.proto_double.encode_to_vec(); }),
+                deserialize_ms: self.time_per_op(|| { let _ = MoneyList::decode(proto_double.as_slice()).unwrap(); }),
+                exact_percent: exact_percent(MoneyList::decode(proto_double.as_slice()).unwrap()
+                    .entries.iter().map(|money| float_amount(money.amount_double)).collect()),
+            },
+            RepresentationRow {
+                representation: "Protobuf string",
+                bytes: proto_decimal.len(),
+                serialize_ms: self.time_per_op(|| { let _ = data.proto_decimal.encode_to_vec(); }),
+                deserialize_ms: self.time_per_op(|| { let _ = MoneyList::decode(proto_decimal.as_slice()).unwrap(); }),
+                exact_percent: exact_percent(MoneyList::decode(proto_decimal.as_slice()).unwrap()
+                    .entries.iter().map(|money| parse_money(&money.amount_decimal)).collect()),
+            },
+            RepresentationRow {
+                representation: "Protobuf scaled int64",
+                bytes: proto_scaled.len(),
+                serialize_ms: self.time_per_op(|| { let _ = data.proto_scaled.encode_to_vec(); }),
+                deserialize_ms: self.time_per_op(|| { let _ = MoneyList::decode(proto_scaled.as_slice()).unwrap(); }),
+                exact_percent: exact_percent(MoneyList::decode(proto_scaled.as_slice()).unwrap()
+                    .entries.iter().map(|money| Some(money.amount_scaled)).collect()),
+            },
+        ];
+        
+        // Decimal text and scaled integers must never lose a digit
+        for row in rows.iter().filter(|row| !row.representation.ends_with("number") && !row.representation.ends_with("double")) {
+            assert_eq!(row.exact_percent, 100.0, "{} lost precision", row.representation);
+        }
+        
+        for row in &rows {
+            eprintln!("{}: {} bytes, serialize {:.4} ms, deserialize {:.4} ms, {:.1}% exact",
+                    row.representation, row.bytes, row.serialize_ms, row.deserialize_ms, row.exact_percent);
+        }
+        
+        rows
+    }
+
+    // 39. Test serialization followed by a CRC32 of the payload
+    pub fn test_checksum(&self) -> ChecksumMetric {
+        eprintln!("{}", "Testing serialize + CRC32 checksum...".green());
+        
+        let (json_data, proto_data) = self.test_data();
+        let json_bytes = serde_json::to_vec(&json_data).unwrap();
+        let proto_bytes = proto_data.encode_to_vec();
+        
+        // Checksumming scales with payload size, so the larger format pays more
+        let json_checksum_ms = self.time_per_op(|| {
+            std::hint::black_box(crc32fast::hash(&json_bytes));
+        });
+        let protobuf_checksum_ms = self.time_per_op(|| {
+            std::hint::black_box(crc32fast::hash(&proto_bytes));
+        });
+        
+        // Both steps together, serializing into one reused buffer
+        let mut buffer = Vec::with_capacity(json_bytes.len());
+        let json_total = self.time_per_op(|| {
+            buffer.clear();
+            serde_json::to_writer(&mut buffer, &json_data).unwrap();
+            std::hint::black_box(crc32fast::hash(&buffer));
+        });
+        let protobuf_total = self.time_per_op(|| {
+            buffer.clear();
+            proto_data.encode(&mut buffer).unwrap();
+            std::hint::black_box(crc32fast::hash(&buffer));
+        });
+        
+        eprintln!("JSON: {} bytes, checksum {:.6} ms, serialize + checksum {:.4} ms",
+                json_bytes.len(), json_checksum_ms, json_total);
+        eprintln!("Protobuf: {} bytes, checksum {:.6} ms, serialize + checksum {:.4} ms",
+                proto_bytes.len(), protobuf_checksum_ms, protobuf_total);
+        
+        ChecksumMetric {
+            json_bytes: json_bytes.len(),
+            protobuf_bytes: proto_bytes.len(),
+            json_checksum_ms,
+            protobuf_checksum_ms,
+            serialize_and_checksum: BenchmarkMetric::lower_is_better(json_total, protobuf_total),
+        }
+    }
+
+    // 40. Test round-trip fidelity of non-ASCII text (emoji unless --charset is set)
+    pub fn test_charset(&self) -> CharsetMetric {
+        let charset = match self.data_options.charset {
+            TextCharset::Ascii => TextCharset::Emoji,
+            charset => charset,
+        };
+        eprintln!("{}", format!("Testing {:?} text round trip...", charset).green());
+        
+        let options = DataOptions { charset, ..self.data_options.clone() };
+        let (json_data, proto_data) = generate_test_data_with(self.data_size, &options);
+        let json_bytes = serde_json::to_vec(&json_data).unwrap();
+        let proto_bytes = proto_data.encode_to_vec();
+        // Non-ASCII only occurs inside strings, so escaping it char by char is valid JSON
+        let escaped = escape_non_ascii(std::str::from_utf8(&json_bytes).unwrap());
+        
+        let original = json_text_fields(&json_data);
+        let json_round_trip = json_text_fields(&serde_json::from_slice(&json_bytes).unwrap()) == original;
+        let escaped_json_round_trip = json_text_fields(&serde_json::from_str(&escaped).unwrap()) == original;
+        let protobuf_round_trip = json_text_fields(&proto_person_to_json(&Person::decode(proto_bytes.as_slice()).unwrap())) == original;
+        
+        let json_ser = self.time_per_op(|| {
+            std::hint::black_box(serde_json::to_vec(&json_data).unwrap());
+        });
+        let protobuf_ser = self.time_per_op(|| {
+            std::hint::black_box(proto_data.encode_to_vec());
+        });
+        let json_deser = self.time_per_op(|| {
+            std::hint::black_box(serde_json::from_slice::<JsonPerson>(&json_bytes).unwrap());
+        });
+        let protobuf_deser = self.time_per_op(|| {
+            std::hint::black_box(Person::decode(proto_bytes.as_slice()).unwrap());
+        });
+        
+        eprintln!("JSON: {} bytes ({} with \\u escapes), Protobuf: {} bytes",
+                json_bytes.len(), escaped.len(), proto_bytes.len());
+        eprintln!("Round trip: JSON {}, escaped JSON {}, Protobuf {}",
+                json_round_trip, escaped_json_round_trip, protobuf_round_trip);
+        
+        CharsetMetric {
+            charset,
+            json_bytes: json_bytes.len(),
+            escaped_json_bytes: escaped.len(),
+            protobuf_bytes: proto_bytes.len(),
+            serialization: BenchmarkMetric::lower_is_better(json_ser, protobuf_ser),
+            deserialization: BenchmarkMetric::lower_is_better(json_deser, protobuf_deser),
+            json_round_trip,
+            escaped_json_round_trip,
+            protobuf_round_trip,
+        }
+    }
+
+    // 41. Test reading a length-delimited protobuf file against the equivalent NDJSON file
+    pub fn test_records_file(&self, proto_file: &[u8], records: &[JsonPerson]) -> RecordsFileMetric {
+        eprintln!("{}", format!("Testing records file ({} records)...", records.len()).green());
+        
+        let mut json_file = Vec::new();
+        for record in records {
+            serde_json::to_writer(&mut json_file, record).unwrap();
+            json_file.push(b'\n');
+        }
+        let proto_records: Vec<Person> = records.iter().map(json_person_to_proto).collect();
+        
+        let json_encode = time_passes(|| {
+            let mut out = Vec::with_capacity(json_file.len());
+            for record in records {
+                serde_json::to_writer(&mut out, record).unwrap();
+                out.push(b'\n');
+            }
+            std::hint::black_box(out);
+        });
+        let proto_encode = time_passes(|| {
+            let mut out = Vec::with_capacity(proto_file.len());
+            for record in &proto_records {
+                record.encode_length_delimited(&mut out).unwrap();
+            }
+            std::hint::black_box(out);
+        });
+        let json_decode = time_passes(|| {
+            for record in serde_json::Deserializer::from_slice(&json_file).into_iter::<JsonPerson>() {
+                std::hint::black_box(record.unwrap());
+            }
+        });
+        // The file as read from disk, not a re-encoding of it
+        let proto_decode = time_passes(|| {
+            let mut buf = proto_file;
+            while !buf.is_empty() {
+                std::hint::black_box(Person::decode_length_delimited(&mut buf).unwrap());
+            }
+        });
+        
+        eprintln!("NDJSON: {} bytes, encode {:.4} ms, decode {:.4} ms per file", json_file.len(), json_encode, json_decode);
+        eprintln!("Protobuf: {} bytes, encode {:.4} ms, decode {:.4} ms per file", proto_file.len(), proto_encode, proto_decode);
+        
+        RecordsFileMetric {
+            records: records.len(),
+            encode: BenchmarkMetric::lower_is_better(json_encode, proto_encode),
+            decode: BenchmarkMetric::lower_is_better(json_decode, proto_decode),
+            file_size: BenchmarkMetric::lower_is_better(json_file.len() as f64, proto_file.len() as f64),
+        }
+    }
+
+    // 42. Test 64-bit IDs as JSON numbers, JSON strings and protobuf int64
+    pub fn test_string_ids(&self) -> Vec<RepresentationRow> {
+        eprintln!("{}", format!("Testing 64-bit IDs ({} records)...", self.data_size).green());
+        
+        let data = generate_string_id_test_data(self.data_size);
+        let exact_percent = |decoded: Vec<i64>| {
+            let exact = decoded.iter().zip(&data.ids).filter(|(id, expected)| id == expected).count();
+            exact as f64 / data.ids.len() as f64 * 100.0
+        };
+        
+        let json_number = serde_json::to_vec(&data.json_number).unwrap();
+        let json_string = serde_json::to_vec(&data.json_string).unwrap();
+        let proto = data.proto.encode_to_vec();
+        let json_number_ser = self.time_per_op(|| { let _ = serde_json::to_vec(&data.json_number).unwrap(); });
+        
+        let rows = vec![
+            RepresentationRow {
+                representation: "JSON number",
+                bytes: json_number.len(),
+                serialize_ms: json_number_ser,
+                deserialize_ms: self.time_per_op(|| { let _: Vec<JsonPersonInt64Id> = serde_json::from_slice(&json_number).unwrap(); }),
+                exact_percent: exact_percent(serde_json::from_slice::<Vec<JsonPersonInt64Id>>(&json_number).unwrap()
+                    .iter().map(|person| person.id).collect()),
+            },
+            // The same bytes read by a parser that has only doubles
+            RepresentationRow {
+                representation: "JSON number (f64 reader)",
+                bytes: json_number.len(),
+                serialize_ms: json_number_ser,
+                deserialize_ms: self.time_per_op(|| { let _: Vec<JsonFloatId> = serde_json::from_slice(&json_number).unwrap(); }),
+                exact_percent: exact_percent(serde_json::from_slice::<Vec<JsonFloatId>>(&json_number).unwrap()
+                    .iter().map(|person| person.id as i64).collect()),
+            },
+            RepresentationRow {
+                representation: "JSON string",
+                bytes: json_string.len(),
+                serialize_ms: self.time_per_op(|| { let _ = serde_json::to_vec(&data.json_string).unwrap(); }),
+                deserialize_ms: self.time_per_op(|| { let _: Vec<JsonPersonStringId> = serde_json::from_slice(&json_string).unwrap(); }),
+                exact_percent: exact_percent(serde_json::from_slice::<Vec<JsonPersonStringId>>(&json_string).unwrap()
+                    .iter().map(|person| person.id).collect()),
+            },
+            RepresentationRow {
+                representation: "Protobuf int64",
+                bytes: proto.len(),
+                serialize_ms: self.time_per_op(|| { let _ = data.proto.encode_to_vec(); }),
+                deserialize_ms: self.time_per_op(|| { let _ = PersonInt64IdList::decode(proto.as_slice()).unwrap(); }),
+                exact_percent: exact_percent(PersonInt64IdList::decode(proto.as_slice()).unwrap()
+                    .people.iter().map(|person| person.id).collect()),
+            },
+        ];
+        
+        // Only a double can lose digits
+        for row in rows.iter().filter(|row| !row.representation.ends_with("(f64 reader)")) {
+            assert_eq!(row.exact_percent, 100.0, "{} lost precision", row.representation);
+        }
+        
+        for row in &rows {
+            eprintln!("{}: {} bytes, serialize {:.4} ms, deserialize {:.4} ms, {:.1}% exact",
+                    row.representation, row.bytes, row.serialize_ms, row.deserialize_ms, row.exact_percent);
+        }
+        
+        rows
+    }
+
+    // 43. Test the cost of deterministic (canonical) bytes in each format
+    pub fn test_determinism(&self) -> DeterminismMetric {
+        eprintln!("{}", "Testing deterministic serialization...".green());
+        
+        let (json_data, proto_data) = self.test_data();
+        let proto_data = Person::decode(proto_data.encode_to_vec().as_slice()).unwrap();
+        // Equal records whose maps iterate in a different order: the same entries
+        // in fresh HashMaps, which get different hash seeds
+        let json_copy = JsonPerson { metadata: json_data.metadata.clone().into_iter().collect(), ..json_data.clone() };
+        let proto_copy = Person { metadata: proto_data.metadata.clone().into_iter().collect(), ..proto_data.clone() };
+        
+        let json_default_stable = serde_json::to_vec(&json_data).unwrap() == serde_json::to_vec(&json_copy).unwrap();
+        let protobuf_default_stable = proto_data.encode_to_vec() == proto_copy.encode_to_vec();
+        
+        let mut canonical = Vec::new();
+        encode_deterministic(&proto_data, &mut canonical);
+        let mut canonical_copy = Vec::new();
+        encode_deterministic(&proto_copy, &mut canonical_copy);
+        assert_eq!(canonical, canonical_copy, "deterministic protobuf encoding differs");
+        assert_eq!(Person::decode(canonical.as_slice()).unwrap(), proto_data);
+        assert_eq!(CanonicalJson.serialize(&json_data), CanonicalJson.serialize(&json_copy), "canonical JSON differs");
+        
+        let json_default = self.time_per_op(|| {
+            std::hint::black_box(serde_json::to_vec(&json_data).unwrap());
+        });
+        let protobuf_default = self.time_per_op(|| {
+            std::hint::black_box(proto_data.encode_to_vec());
+        });
+        let json_deterministic = self.time_per_op(|| {
+            std::hint::black_box(CanonicalJson.serialize(&json_data));
+        });
+        let protobuf_deterministic = self.time_per_op(|| {
+            // Sized up front like `encode_to_vec`; the length doesn't depend on entry order
+            let mut buf = Vec::with_capacity(proto_data.encoded_len());
+            encode_deterministic(&proto_data, &mut buf);
+            std::hint::black_box(buf);
+        });
+        
+        eprintln!("JSON: default {:.4} ms, canonical {:.4} ms ({:+.1}%), default stable: {}",
+                json_default, json_deterministic, (json_deterministic / json_default - 1.0) * 100.0, json_default_stable);
+        eprintln!("Protobuf: default {:.4} ms, sorted maps {:.4} ms ({:+.1}%), default stable: {}",
+                protobuf_default, protobuf_deterministic, (protobuf_deterministic / protobuf_default - 1.0) * 100.0, protobuf_default_stable);
+        
+        DeterminismMetric {
+            default: BenchmarkMetric::lower_is_better(json_default, protobuf_default),
+            deterministic: BenchmarkMetric::lower_is_better(json_deterministic, protobuf_deterministic),
+            json_default_stable,
+            protobuf_default_stable,
+        }
+    }
+
+    // 44. Test real round trips over a loopback TCP connection: each payload is written
+    // to an echo server and read back in full. Loopback has no wire delay, so this is
+    // the socket and kernel cost per payload size; `network` is the simulated WAN estimate.
+    pub async fn test_real_network(&self) -> std::io::Result<BenchmarkMetric> {
+        eprintln!("{}", format!("Testing network round trips over loopback TCP ({} iterations)...", self.iterations).green());
+        if self.iterations == 0 {
+            return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, "at least one iteration is needed to time a round trip"));
+        }
+        
+        let (json_data, proto_data) = self.test_data();
+        let json_bytes = serde_json::to_vec(&json_data).unwrap();
+        let proto_bytes = proto_data.encode_to_vec();
+        
+        // Echo every byte back until the client disconnects
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await?;
+            socket.set_nodelay(true)?;
+            let (mut reader, mut writer) = socket.split();
+            tokio::io::copy(&mut reader, &mut writer).await
+        });
+        
+        let mut stream = TcpStream::connect(addr).await?;
+        // Otherwise Nagle's algorithm holds back small writes waiting for an ACK
+        stream.set_nodelay(true)?;
+        let warmup = self.warmup_iterations();
+        let json_time = echo_round_trip_ms(&mut stream, &json_bytes, warmup, self.iterations).await?;
+        let proto_time = echo_round_trip_ms(&mut stream, &proto_bytes, warmup, self.iterations).await?;
+        drop(stream);
+        server.await.unwrap()?;
+        
+        eprintln!("JSON round trip: {:.4} ms ({} bytes each way)", json_time, json_bytes.len());
+        eprintln!("Protobuf round trip: {:.4} ms ({} bytes each way)", proto_time, proto_bytes.len());
+        
+        Ok(BenchmarkMetric::lower_is_better(json_time, proto_time))
+    }
+
+    // Run the per-op tests at a tenth of the iterations and at the full count, and warn
+    // about every test whose per-op time moves by more than `max_drift_percent`. A
+    // real per-op cost doesn't depend on how many ops are timed, so drift points at
+    // fixed overhead (setup inside the timed region) or noise swamping the measurement.
+    pub fn compare_iterations(&self, max_drift_percent: f64) -> Vec<IterationStability> {
+        let low = self.clone().with_iterations((self.iterations / 10).max(1));
+        let high = self.clone();
+        eprintln!("{}", format!("Comparing per-op times at {} and {} iterations...", low.iterations, high.iterations).green());
+        
+        // Each test's per-op (JSON, protobuf) milliseconds; CPU reports totals. Memory
+        // is left out: its peak heap bytes aren't a per-op time.
+        let tests: [(&'static str, PerOpTest); 3] = [
+            ("serialization", |tester| {
+                let metric = tester.test_serialization_speed();
+                (metric.json, metric.protobuf)
+            }),
+            ("deserialization", |tester| {
+                let metric = tester.test_deserialization_speed();
+                (metric.json, metric.protobuf)
+            }),
+            ("cpu", |tester| {
+                let metric = tester.test_cpu_usage();
+                let ops = (tester.iterations * 10) as f64;
+                (metric.json / ops, metric.protobuf / ops)
+            }),
+        ];
+        
+        tests.iter()
+            .map(|(test, run)| {
+                let (json_low, protobuf_low) = run(&low);
+                let (json_high, protobuf_high) = run(&high);
+                let ratio = stability_ratio(json_low, json_high).max(stability_ratio(protobuf_low, protobuf_high));
+                if (ratio - 1.0) * 100.0 > max_drift_percent {
+                    eprintln!("{}", format!("Warning: {} per-op time changed {:.2}x between {} and {} iterations; \
+                                           the measurement may be dominated by fixed overhead or noise",
+                                          test, ratio, low.iterations, high.iterations).yellow());
+                }
+                IterationStability {
+                    test,
+                    json: (json_low, json_high),
+                    protobuf: (protobuf_low, protobuf_high),
+                    ratio,
+                }
+            })
+            .collect()
+    }
+
+    // Run a synchronous test, on a blocking thread when a per-test timeout is set so
+    // the run can move on without it. A timed-out test keeps running in the background.
+    async fn run_test<T, F>(&mut self, test: &str, run: F) -> Result<T, VerificationError>
+    where
+        T: TimedOut + Send + 'static,
+        F: FnOnce(&PerformanceTester) -> T + Send + 'static,
+    {
+        let result = match self.per_test_timeout {
+            None => Some(run(self)),
+            Some(_) => {
+                let tester = self.clone();
+                let handle = tokio::task::spawn_blocking(move || run(&tester));
+                with_timeout(self.per_test_timeout, handle).await
+                    .map(|joined| joined.unwrap_or_else(|e| std::panic::resume_unwind(e.into_panic())))
+            }
+        };
+        self.finish_test(test, result)
+    }
+
+    // Verify a finished test, or substitute the timed-out placeholder
+    fn finish_test<T: TimedOut>(&mut self, test: &str, result: Option<T>) -> Result<T, VerificationError> {
+        match result {
+            Some(result) => {
+                self.check(test)?;
+                Ok(result)
+            },
+            None => {
+                eprintln!("{}", format!("Test {} timed out after {:?}", test, self.per_test_timeout.unwrap()).yellow());
+                Ok(T::timed_out())
+            }
+        }
+    }
+
+    fn aggregate_throughput(&self) -> AggregateThroughput {
+        let volume = self.volume.lock().unwrap();
+        AggregateThroughput {
+            json_bytes: volume.json_bytes as u64,
+            protobuf_bytes: volume.protobuf_bytes as u64,
+            json_mb_per_sec: volume.json_bytes / 1e6 / (volume.json_ms / 1000.0),
+            protobuf_mb_per_sec: volume.protobuf_bytes / 1e6 / (volume.protobuf_ms / 1000.0),
+        }
+    }
+
+    // Run all tests
+    pub async fn run_all_tests(&mut self) -> Result<&BenchmarkResults, VerificationError> {
+        eprintln!("{}", format!("Running all tests with data size {} and {} iterations...", 
+                              self.data_size, self.iterations).blue().bold());
+        
+        self.verification_failures.clear();
+        *self.volume.lock().unwrap() = ByteVolume::default();
+        self.raw_timings.lock().unwrap().clear();
+        
+        let mut order = SUITE.to_vec();
+        let order_seed = match self.order {
+            TestOrder::Sequential => None,
+            TestOrder::Shuffled(seed) => {
+                order.shuffle(&mut StdRng::seed_from_u64(seed));
+                eprintln!("Test order (seed {}): {}", seed, order.join(", "));
+                Some(seed)
+            }
+        };
+        
+        let mut runs = Vec::with_capacity(self.runs);
+        for run in 1..=self.runs {
+            if self.runs > 1 {
+                eprintln!("{}", format!("Run {} of {}", run, self.runs).blue());
+            }
+            runs.push(self.run_suite(&order, order_seed).await?);
+        }
+        self.results = Some(if self.runs > 1 { aggregate_runs(runs) } else { runs.pop().unwrap() });
+        
+        eprintln!("{}", "All tests completed!".green().bold());
+        
+        Ok(self.results.as_ref().unwrap())
+    }
+
+    // One pass over the suite in `order`, verifying each test when --verify is set
+    async fn run_suite(&mut self, order: &[&'static str], order_seed: Option<u64>) -> Result<BenchmarkResults, VerificationError> {
+        let mut serialization = None;
+        let mut deserialization = None;
+        let mut payload_size = None;
+        let mut cpu_usage = None;
+        let mut memory_usage = None;
+        let mut network_transfer = None;
+        let mut latency_under_load = None;
+        let mut parser_init = None;
+        let mut throughput = None;
+        let mut schema_evolution = None;
+        for &test in order {
+            match test {
+                "serialization" => serialization = Some(self.run_test(test, |t| t.test_serialization_speed()).await?),
+                "deserialization" => deserialization = Some(self.run_test(test, |t| t.test_deserialization_speed()).await?),
+                "payload" => payload_size = Some(self.run_test(test, |t| t.test_payload_size()).await?),
+                "cpu" => cpu_usage = Some(self.run_test(test, |t| t.test_cpu_usage()).await?),
+                "memory" => memory_usage = Some(self.run_test(test, |t| t.test_memory_usage()).await?),
+                "network" => {
+                    let result = with_timeout(self.per_test_timeout, self.test_network_transfer()).await;
+                    network_transfer = Some(self.finish_test(test, result)?);
+                },
+                "latency" => {
+                    let result = with_timeout(self.per_test_timeout, self.test_latency_under_load()).await;
+                    latency_under_load = Some(self.finish_test(test, result)?);
+                },
+                "init" => parser_init = Some(self.run_test(test, |t| t.test_parser_initialization()).await?),
+                "throughput" => throughput = Some(self.run_test(test, |t| t.test_throughput()).await?),
+                "schema" => schema_evolution = Some(self.run_test(test, |t| t.test_schema_evolution()).await?),
+                _ => unreachable!("unknown test {}", test),
+            }
+        }
+        
+        Ok(BenchmarkResults {
+            run_id: run_id(&self.config()),
+            test_order: order.iter().map(|test| test.to_string()).collect(),
+            order_seed,
+            worker_threads: self.worker_threads,
+            allocator: alloc_tracker::ALLOCATOR.to_string(),
+            serialization: serialization.unwrap(),
+            deserialization: deserialization.unwrap(),
+            payload_size: payload_size.unwrap(),
+            cpu_usage: cpu_usage.unwrap(),
+            memory_usage: memory_usage.unwrap(),
+            network_transfer: network_transfer.unwrap(),
+            latency_under_load: latency_under_load.unwrap(),
+            parser_init: parser_init.unwrap(),
+            throughput: throughput.unwrap(),
+            schema_evolution: schema_evolution.unwrap(),
+            bytes_processed: self.aggregate_throughput(),
+            runs: Vec::new(),
+        })
+    }
+
+    // Results of the last full run, if any
+    pub fn results(&self) -> Option<&BenchmarkResults> {
+        self.results.as_ref()
+    }
+
+    // Use results from an earlier run of the same configuration (see --cache)
+    // in place of running the suite
+    pub fn restore_results(&mut self, results: BenchmarkResults) -> &BenchmarkResults {
+        self.results.insert(results)
+    }
+
+    // The full results (payload, throughput and schema evolution details included)
+    // as a JSON document, via the serde derives; null before any run
+    pub fn results_as_json(&self) -> serde_json::Value {
+        serde_json::to_value(&self.results).unwrap()
+    }
+
+    // Render the main results table as GitHub-flavored Markdown
+    pub fn results_to_markdown(&self) -> String {
+        let Some(results) = &self.results else {
+            return String::new();
+        };
+        
+        let relative = self.relative_to.map(|base| relative_results(results, base));
+        let columns = Columns { relative: relative.is_some(), ..Columns::default() };
+        let rows = result_rows(relative.as_ref().unwrap_or(results), columns);
+        let mut markdown = format!("Run ID: `{}`\n\n", results.run_id);
+        if let Some(base) = self.relative_to {
+            markdown.push_str(&format!("Values relative to {} (= 1.00x)\n\n", base.name()));
+        }
+        for (i, row) in rows.iter().enumerate() {
+            markdown.push_str(&format!("| {} |\n", row.join(" | ")));
+            if i == 0 {
+                markdown.push_str(&format!("|{}\n", " --- |".repeat(row.len())));
+            }
+        }
+        markdown
+    }
+
+    // Render results as InfluxDB line protocol, one point per flattened metric, e.g.
+    // `benchmark,format=json,run_id=...,test=serialization,unit=ms value=0.12 <timestamp>`.
+    // Metrics without a format (differences, p-values) carry only `test` and `unit`.
+    pub fn results_to_influx(&self, timestamp_ns: u64) -> String {
+        let Some(results) = &self.results else {
+            return String::new();
+        };
+        
+        let mut common = format!(",run_id={}", influx_tag(&results.run_id));
+        if let Some(tag) = &self.tag {
+            common.push_str(&format!(",tag={}", influx_tag(tag)));
+        }
+        
+        let mut lines = String::new();
+        for (key, value) in flatten_results(results) {
+            let Some(value) = value.as_f64() else { continue };
+            let parts: Vec<&str> = key.split('.').collect();
+            let tags = match parts.as_slice() {
+                [test, format, unit] if *format != "difference" => {
+                    format!("format={}{},test={},unit={}", influx_tag(format), common, influx_tag(test), influx_tag(unit))
+                },
+                [test, rest @ ..] if !rest.is_empty() => {
+                    format!("{},test={},unit={}", &common[1..], influx_tag(test), influx_tag(&rest.join("_")))
+                },
+                _ => format!("{},test={}", &common[1..], influx_tag(&key)),
+            };
+            lines.push_str(&format!("benchmark,{} value={} {}\n", tags, value, timestamp_ns));
+        }
+        lines
+    }
+
+    // Print results as a table
+    // One line per test naming the winner and by what factor, then the overall winner
+    pub fn print_winners(&self) {
+        let Some(results) = &self.results else {
+            println!("No results to print. Run the tests first.");
+            return;
+        };
+        
+        let schema = &results.schema_evolution;
+        let tests = [
+            (&results.serialization.winner, results.serialization.percent_faster),
+            (&results.deserialization.winner, results.deserialization.percent_faster),
+            (&results.payload_size.uncompressed.winner, results.payload_size.uncompressed.percent_faster),
+            (&results.payload_size.compressed.winner, results.payload_size.compressed.percent_faster),
+            (&results.cpu_usage.winner, results.cpu_usage.percent_faster),
+            (&results.memory_usage.winner, results.memory_usage.percent_faster),
+            (&results.network_transfer.winner, results.network_transfer.percent_faster),
+            (&results.latency_under_load.winner, results.latency_under_load.percent_faster),
+            (&results.parser_init.winner, results.parser_init.percent_faster),
+            (&results.throughput.winner, results.throughput.percent_faster),
+            (&schema.winner, percent_faster(schema.json, schema.protobuf_average)),
+        ];
+        for (test, (winner, margin)) in WEIGHTED_TESTS.iter().zip(tests) {
+            // Ratio of the runner-up's to the winning value, whichever direction is better
+            let factor = 1.0 + margin / 100.0;
+            if factor.is_finite() && !matches!(winner.as_str(), "Inconclusive" | "-") {
+                println!("{}: {} ({:.2}x)", test, winner, factor);
+            } else {
+                println!("{}: {}", test, winner);
+            }
+        }
+        
+        let wins = tally_wins(results, &self.weights);
+        let points: Vec<String> = wins.iter().map(|(_, points)| points.to_string()).collect();
+        let overall = wins.first().map_or("-", |(winner, _)| display_name(winner));
+        println!("overall: {} ({})", overall, points.join(" vs "));
+        println!("{}", efficiency_verdict(results, &self.score_weights));
+    }
+
+    pub fn print_results(&self) {
+        if let Some(results) = &self.results {
+            println!("\n{}", "JSON vs Protocol Buffers Benchmark Results".blue().bold());
+            println!("{}", "===========================================".blue());
+            println!("Run ID: {}", results.run_id);
+            println!("Worker threads: {}", results.worker_threads);
+            println!("Allocator: {}", results.allocator);
+            if let Some(seed) = results.order_seed {
+                println!("Test order (seed {}): {}", seed, results.test_order.join(", "));
+            }
+            
+            // Relative values replace the raw ones in both tables
+            let relative = self.relative_to.map(|base| relative_results(results, base));
+            if let Some(base) = self.relative_to {
+                println!("Values relative to {} (= 1.00x)", base.name());
+            }
+            let raw = results;
+            let results = relative.as_ref().unwrap_or(raw);
+            
+            let columns = Columns {
+                sparkline: self.sparklines,
+                peak_memory: self.show_memory || self.verbose,
+                relative: relative.is_some(),
+            };
+            let rows = result_rows(results, columns);
+            let extra_metrics = extra_metrics(results);
+            let width = self.column_width;
+            
+            if self.transpose {
+                // Every format in one table, so the whole matrix is needed up front
+                let matrix = result_matrix(&rows, &extra_metrics, columns);
+                let mut table = Table::new();
+                let mut header = vec![header_cell("Format", width)];
+                header.extend(matrix.metrics.iter().map(|label| header_cell(label, width)));
+                table.add_row(Row::new(header));
+                for (format, cells) in &matrix.formats {
+                    let mut row = vec![table_cell(format, width)];
+                    row.extend(cells.iter().map(|value| table_cell(value, width)));
+                    table.add_row(Row::new(row));
+                }
+                let mut winners = vec![header_cell("Winner", width)];
+                winners.extend(matrix.winners.iter().map(|winner| table_cell(winner, width)));
+                table.add_row(Row::new(winners));
+                table.printstd();
+            } else {
+                let mut table = Table::new();
+                
+                table.add_row(Row::new(rows[0].iter().map(|label| header_cell(label, width)).collect()));
+                for row in &rows[1..] {
+                    table.add_row(Row::new(row.iter().map(|value| table_cell(value, width)).collect()));
+                }
+                
+                // Print the table
+                table.printstd();
+                
+                // Additional codecs get their own table, relative to protobuf
+                if extra_metrics.iter().any(|(_, _, others, _)| !others.is_empty()) {
+                    println!("\n{}", "Additional Formats".blue().bold());
+                    let mut extra_table = Table::new();
+                    // No comparison column when --formats left protobuf out
+                    let versus_protobuf = extra_metrics.iter().any(|(_, protobuf, _, _)| !protobuf.is_nan());
+                    let header = if versus_protobuf { &["Test", "Format", "Value", "vs Protobuf"][..] } else { &["Test", "Format", "Value"][..] };
+                    extra_table.add_row(Row::new(header.iter().map(|label| header_cell(label, width)).collect()));
+                    for (label, protobuf, others, precision) in extra_metrics {
+                        for (name, value) in others {
+                            let mut cells = vec![
+                                table_cell(label, width),
+                                table_cell(name, width),
+                                table_cell(&columns.value(*value, precision), width),
+                            ];
+                            if versus_protobuf {
+                                // "-" where there's nothing to compare, e.g. 0/0 heap without the tracker
+                                let ratio = value / protobuf;
+                                let versus = if ratio.is_finite() { format!("{:.2}x", ratio) } else { "-".to_string() };
+                                cells.push(table_cell(&versus, width));
+                            }
+                            extra_table.add_row(Row::new(cells));
+                        }
+                    }
+                    extra_table.printstd();
+                }
+            }
+            
+            // Count winners
+            let results = raw;
+            let wins = tally_wins(results, &self.weights);
+            let unit = if self.weights.is_empty() { "wins" } else { "points" };
+            // Two formats keep the short "7 wins vs 4 wins"; more name every runner-up
+            let verdict = match wins.as_slice() {
+                [] => "-".to_string(),
+                [(winner, points)] => format!("{} ({} {})", display_name(winner), points, unit),
+                [(winner, points), (_, runner_up)] => format!("{} ({} {} vs {} {})", display_name(winner), points, unit, runner_up, unit),
+                [(winner, points), rest @ ..] => {
+                    let rest: Vec<String> = rest.iter().map(|(name, points)| format!("{} {}", display_name(name), points)).collect();
+                    format!("{} ({} {} vs {})", display_name(winner), points, unit, rest.join(", "))
+                },
+            };
+            println!("\n{}", format!("Overall winner: {}", verdict).green().bold());
+            println!("{}", efficiency_verdict(results, &self.score_weights).green());
+            
+            // Formats left out of --formats process no bytes and have no variation
+            let total = &results.bytes_processed;
+            let processed: Vec<String> = [("JSON", total.json_bytes, total.json_mb_per_sec),
+                                          ("Protobuf", total.protobuf_bytes, total.protobuf_mb_per_sec)].iter()
+                .filter(|(_, bytes, _)| *bytes > 0)
+                .map(|(name, bytes, mb_per_sec)| format!("{} {:.2} MB at {:.2} MB/s", name, *bytes as f64 / 1e6, mb_per_sec))
+                .collect();
+            if !processed.is_empty() {
+                println!("Bytes processed: {}", processed.join(", "));
+            }
+            
+            if let Some(first) = raw.runs.first() {
+                println!("\n{}", format!("Run-to-run variation ({} runs, coefficient of variation)", first.json.samples.len()).blue().bold());
+                for metric in &raw.runs {
+                    let variation: Vec<String> = [("JSON", &metric.json), ("Protobuf", &metric.protobuf)].iter()
+                        .map(|(name, runs)| (name, runs.coefficient_of_variation()))
+                        .filter(|(_, cv)| !cv.is_nan())
+                        .map(|(name, cv)| format!("{} {:.2}%", name, cv))
+                        .collect();
+                    println!("{}: {}", metric.test, if variation.is_empty() { "-".to_string() } else { variation.join(", ") });
+                }
+            }
+        } else {
+            println!("No results to print. Run the tests first.");
+        }
+    }
+}
+
+// Weighted win totals for every format, most first (ties go to protobuf, then
+// JSON, then the additional codecs); each test adds its weight from `weights`
+// (default 1) to its winner. Timed-out and inconclusive tests count for no format.
+pub fn tally_wins(results: &BenchmarkResults, weights: &HashMap<String, f64>) -> Vec<(String, f64)> {
+    let winners = [
+        &results.serialization.winner,
+        &results.deserialization.winner,
+        &results.payload_size.uncompressed.winner,
+        &results.payload_size.compressed.winner,
+        &results.cpu_usage.winner,
+        &results.memory_usage.winner,
+        &results.network_transfer.winner,
+        &results.latency_under_load.winner,
+        &results.parser_init.winner,
+        &results.throughput.winner,
+        &results.schema_evolution.winner,
+    ];
+    
+    let mut wins: Vec<(String, f64)> = measured_formats(results, &["Protobuf", "JSON"]).into_iter()
+        .map(|name| (name, 0.0))
+        .collect();
+    for (test, winner) in WEIGHTED_TESTS.iter().zip(winners) {
+        let weight = weights.get(*test).copied().unwrap_or(1.0);
+        if let Some((_, points)) = wins.iter_mut().find(|(name, _)| name == winner) {
+            *points += weight;
+        }
+    }
+    // Stable, so ties keep the order above
+    wins.sort_by(|a, b| b.1.total_cmp(&a.1));
+    wins
+}
+
+// The formats a run measured: JSON and protobuf in the given order, each only
+// if it has a value (--formats can leave either out), then the additional codecs
+fn measured_formats(results: &BenchmarkResults, order: &[&str]) -> Vec<String> {
+    let metrics = [&results.serialization, &results.deserialization, &results.payload_size.uncompressed];
+    let measured = |name: &str| metrics.iter()
+        .any(|metric| !(if name == "JSON" { metric.json } else { metric.protobuf }).is_nan());
+    order.iter()
+        .filter(|name| measured(name))
+        .map(|name| name.to_string())
+        .chain(results.serialization.others.iter().map(|(name, _)| name.clone()))
+        .collect()
+}
+
+// How a format is named in the overall verdicts
+fn display_name(format: &str) -> &str {
+    if format == "Protobuf" { "Protocol Buffers" } else { format }
+}
+
+// Composite 0-100 efficiency score for JSON, protobuf and the additional codecs,
+// in that order. Each metric in `SCORED_METRICS` is normalized so the best format
+// gets 1 and every other best / value (all are lower-is-better), then the
+// normalized values are averaged using `weights` (default 1) and scaled to 100.
+// A format that wins every weighted metric scores 100. Timed-out metrics are
+// left out.
+pub fn efficiency_scores(results: &BenchmarkResults, weights: &HashMap<String, f64>) -> Vec<(String, f64)> {
+    let metrics = [&results.serialization, &results.deserialization, &results.payload_size.uncompressed];
+    
+    let mut total_weight = 0.0;
+    let mut scores: Vec<(String, f64)> = measured_formats(results, &["JSON", "Protobuf"]).into_iter()
+        .map(|name| (name, 0.0))
+        .collect();
+    for (name, metric) in SCORED_METRICS.iter().zip(metrics) {
+        if metric.status == TestStatus::Timeout {
+            continue;
+        }
+        let weight = weights.get(*name).copied().unwrap_or(1.0);
+        let values = rank_formats(metric.json, metric.protobuf, &metric.others, true);
+        let best = values[0].1;
+        total_weight += weight;
+        for (format, score) in &mut scores {
+            if let Some((_, value)) = values.iter().find(|(measured, _)| measured == format) {
+                *score += weight * best / value;
+            }
+        }
+    }
+    
+    for (_, score) in &mut scores {
+        *score = if total_weight == 0.0 { f64::NAN } else { *score / total_weight * 100.0 };
+    }
+    scores
+}
+
+// One-line efficiency verdict, e.g. `Efficiency score: JSON 48.2, Protobuf 100.0`
+fn efficiency_verdict(results: &BenchmarkResults, weights: &HashMap<String, f64>) -> String {
+    let scores: Vec<String> = efficiency_scores(results, weights).iter()
+        .map(|(format, score)| format!("{} {:.1}", format, score))
+        .collect();
+    let weights: Vec<String> = SCORED_METRICS.iter()
+        .map(|name| format!("{} {}", name, weights.get(*name).copied().unwrap_or(1.0)))
+        .collect();
+    format!("Efficiency score: {} (weights: {})", scores.join(", "), weights.join(", "))
+}
+
+// Escape commas, spaces and equals signs in an InfluxDB tag value
+fn influx_tag(value: &str) -> String {
+    value.replace(',', "\\,").replace(' ', "\\ ").replace('=', "\\=")
+}
+
+// Optional columns of the main results table
+#[derive(Clone, Copy, Default)]
+struct Columns {
+    sparkline: bool,
+    peak_memory: bool,
+    // Values are multiples of a base format rather than raw measurements
+    relative: bool,
+}
+
+impl Columns {
+    // A JSON or protobuf value cell
+    fn value(&self, value: f64, precision: usize) -> String {
+        if value.is_nan() {
+            "-".to_string()
+        } else if self.relative {
+            format!("{:.2}x", value)
+        } else {
+            format!("{:.*}", precision, value)
+        }
+    }
+
+    // Append this row's optional cells; tests without memory tracking leave those blank
+    fn extend(&self, row: &mut Vec<String>, json: f64, protobuf: f64, peak_memory: Option<PeakMemory>) {
+        if self.sparkline {
+            row.push(sparkline(json, protobuf));
+        }
+        if self.peak_memory {
+            let cell = |bytes: Option<usize>| bytes.map(|bytes| bytes.to_string()).unwrap_or_default();
+            match peak_memory {
+                Some(peak) => {
+                    row.push(cell(peak.json_bytes));
+                    row.push(cell(peak.protobuf_bytes));
+                },
+                None => row.extend([String::new(), String::new()]),
+            }
+        }
+    }
+}
+
+// The table's BenchmarkMetric rows with their test names, in table order
+fn table_metrics(results: &mut BenchmarkResults) -> [(&'static str, &mut BenchmarkMetric); 10] {
+    [
+        ("serialization", &mut results.serialization),
+        ("deserialization", &mut results.deserialization),
+        ("payload", &mut results.payload_size.uncompressed),
+        ("compressed", &mut results.payload_size.compressed),
+        ("zstd", &mut results.payload_size.zstd_compressed),
+        ("cpu", &mut results.cpu_usage),
+        ("memory", &mut results.memory_usage),
+        ("network", &mut results.network_transfer),
+        ("latency", &mut results.latency_under_load),
+        ("init", &mut results.parser_init),
+    ]
+}
+
+// One run's values of a table metric: JSON, protobuf, additional codecs and peak heap
+type RunValues = (f64, f64, Vec<f64>, Option<PeakMemory>);
+
+// Combine several passes over the suite: every table value and timing becomes its
+// mean across the runs (winners follow the means, without a significance verdict),
+// peak heap figures their maximum, and the per-run values are kept in `runs`. A
+// metric that timed out in any run stays timed out. Sizes that don't vary between
+// runs, e.g. the gzip level sweep's bytes, come from the last run.
+fn aggregate_runs(mut runs: Vec<BenchmarkResults>) -> BenchmarkResults {
+    let mut results = runs.last().unwrap().clone();
+    let mut aggregates = Vec::new();
+    
+    // NaN also marks a format left out of --formats, so timeouts go by status
+    let mut columns: Vec<Vec<RunValues>> = vec![Vec::new(); 10];
+    let mut timed_out = [false; 10];
+    for run in &mut runs {
+        for (i, (_, metric)) in table_metrics(run).into_iter().enumerate() {
+            timed_out[i] |= metric.status == TestStatus::Timeout;
+            columns[i].push((metric.json, metric.protobuf, metric.others.iter().map(|(_, value)| *value).collect(),
+                             metric.peak_memory));
+        }
+    }
+    for (((test, metric), column), timed_out) in table_metrics(&mut results).into_iter().zip(columns).zip(timed_out) {
+        let json = RunAggregate { samples: column.iter().map(|(json, _, _, _)| *json).collect() };
+        let protobuf = RunAggregate { samples: column.iter().map(|(_, protobuf, _, _)| *protobuf).collect() };
+        if timed_out {
+            *metric = BenchmarkMetric::timed_out();
+            continue;
+        }
+        for (k, (_, value)) in metric.others.iter_mut().enumerate() {
+            *value = stats::mean(&column.iter().map(|(_, _, others, _)| others[k]).collect::<Vec<_>>());
+        }
+        metric.set_values(json.mean(), protobuf.mean(), true);
+        if metric.peak_memory.is_some() {
+            let peaks = column.iter().filter_map(|(_, _, _, peak)| *peak);
+            metric.peak_memory = Some(PeakMemory {
+                json_bytes: peaks.clone().filter_map(|peak| peak.json_bytes).max(),
+                protobuf_bytes: peaks.filter_map(|peak| peak.protobuf_bytes).max(),
+            });
+        }
+        aggregates.push(MetricRuns { test: test.to_string(), json, protobuf });
+    }
+    
+    let mean_of = |value: fn(&BenchmarkResults) -> f64| stats::mean(&runs.iter().map(value).collect::<Vec<_>>());
+    let payload = &mut results.payload_size;
+    payload.json_compress_ms = mean_of(|run| run.payload_size.json_compress_ms);
+    payload.proto_compress_ms = mean_of(|run| run.payload_size.proto_compress_ms);
+    payload.json_ratio = mean_of(|run| run.payload_size.json_ratio);
+    payload.proto_ratio = mean_of(|run| run.payload_size.proto_ratio);
+    for (k, level) in payload.gzip_levels.iter_mut().enumerate() {
+        let level_runs: Vec<&CompressionLevelMetric> = runs.iter().filter_map(|run| run.payload_size.gzip_levels.get(k)).collect();
+        level.json_ms = stats::mean(&level_runs.iter().map(|run| run.json_ms).collect::<Vec<_>>());
+        level.protobuf_ms = stats::mean(&level_runs.iter().map(|run| run.protobuf_ms).collect::<Vec<_>>());
+    }
+    
+    // Higher throughput is better
+    let json = RunAggregate { samples: runs.iter().map(|run| run.throughput.json).collect() };
+    let protobuf = RunAggregate { samples: runs.iter().map(|run| run.throughput.protobuf).collect() };
+    let throughput = &mut results.throughput;
+    if runs.iter().any(|run| run.throughput.status == TestStatus::Timeout) {
+        *throughput = ThroughputMetric::timed_out();
+    } else {
+        throughput.json = json.mean();
+        throughput.protobuf = protobuf.mean();
+        throughput.ratio = throughput.json / throughput.protobuf;
+        for (k, (_, value)) in throughput.others.iter_mut().enumerate() {
+            *value = stats::mean(&runs.iter().map(|run| run.throughput.others[k].1).collect::<Vec<_>>());
+        }
+        throughput.rank();
+        aggregates.push(MetricRuns { test: "throughput".to_string(), json, protobuf });
+    }
+    
+    let json = RunAggregate { samples: runs.iter().map(|run| run.schema_evolution.json).collect() };
+    let protobuf = RunAggregate { samples: runs.iter().map(|run| run.schema_evolution.protobuf_average).collect() };
+    let schema = &mut results.schema_evolution;
+    if runs.iter().any(|run| run.schema_evolution.status == TestStatus::Timeout) {
+        *schema = SchemaEvolutionMetric::timed_out();
+    } else if schema.status == TestStatus::Completed {
+        schema.json = json.mean();
+        schema.protobuf_backwards = stats::mean(&runs.iter().map(|run| run.schema_evolution.protobuf_backwards).collect::<Vec<_>>());
+        schema.protobuf_forwards = stats::mean(&runs.iter().map(|run| run.schema_evolution.protobuf_forwards).collect::<Vec<_>>());
+        schema.protobuf_average = protobuf.mean();
+        schema.winner = if schema.json < schema.protobuf_average { "JSON" } else { "Protobuf" }.to_string();
+        aggregates.push(MetricRuns { test: "schema".to_string(), json, protobuf });
+    }
+    
+    results.runs = aggregates;
+    results
+}
+
+// A copy of the results with every JSON, protobuf and additional-codec value
+// divided by the base format's value for the same metric, so the base format
+// reads 1.0 and e.g. protobuf 0.4 means 0.4x JSON's time. Winner margins, winners
+// and peak memory are unchanged.
+pub fn relative_results(results: &BenchmarkResults, base: BaseFormat) -> BenchmarkResults {
+    let pick = |json: f64, protobuf: f64| match base {
+        BaseFormat::Json => json,
+        BaseFormat::Protobuf => protobuf,
+    };
+    let scale_metric = |metric: &mut BenchmarkMetric| {
+        let divisor = pick(metric.json, metric.protobuf);
+        metric.json /= divisor;
+        metric.protobuf /= divisor;
+        for (_, value) in &mut metric.others {
+            *value /= divisor;
+        }
+    };
+    
+    let mut relative = results.clone();
+    for metric in [
+        &mut relative.serialization,
+        &mut relative.deserialization,
+        &mut relative.payload_size.uncompressed,
+        &mut relative.payload_size.compressed,
+        &mut relative.payload_size.zstd_compressed,
+        &mut relative.cpu_usage,
+        &mut relative.memory_usage,
+        &mut relative.network_transfer,
+        &mut relative.latency_under_load,
+        &mut relative.parser_init,
+    ] {
+        scale_metric(metric);
+    }
+    
+    let payload = &mut relative.payload_size;
+    let divisor = pick(payload.json_compress_ms, payload.proto_compress_ms);
+    payload.json_compress_ms /= divisor;
+    payload.proto_compress_ms /= divisor;
+    let divisor = pick(payload.json_ratio, payload.proto_ratio);
+    payload.json_ratio /= divisor;
+    payload.proto_ratio /= divisor;
+    
+    let throughput = &mut relative.throughput;
+    let divisor = pick(throughput.json, throughput.protobuf);
+    throughput.json /= divisor;
+    throughput.protobuf /= divisor;
+    for (_, value) in &mut throughput.others {
+        *value /= divisor;
+    }
+    if let Some(parallel) = &mut throughput.parallel {
+        let divisor = pick(parallel.json, parallel.protobuf);
+        parallel.json /= divisor;
+        parallel.protobuf /= divisor;
+    }
+    if let Some(normalized) = &mut throughput.normalized {
+        let divisor = pick(normalized.json, normalized.protobuf);
+        normalized.json /= divisor;
+        normalized.protobuf /= divisor;
+    }
+    
+    let schema = &mut relative.schema_evolution;
+    let divisor = pick(schema.json, schema.protobuf_average);
+    schema.json /= divisor;
+    schema.protobuf_backwards /= divisor;
+    schema.protobuf_forwards /= divisor;
+    schema.protobuf_average /= divisor;
+    
+    relative
+}
+
+// Rows of the main results table as text, header first; shared by the table
+// and Markdown output
+fn result_rows(results: &BenchmarkResults, columns: Columns) -> Vec<Vec<String>> {
+    let mut header = vec!["Test".to_string(), "JSON".to_string(), "Protobuf".to_string(),
+                          "Winner Margin".to_string(), "Winner".to_string()];
+    if columns.sparkline {
+        header.push("J/P".to_string());
+    }
+    if columns.peak_memory {
+        header.push("JSON Peak (bytes)".to_string());
+        header.push("Protobuf Peak (bytes)".to_string());
+    }
+    let mut rows = vec![header];
+    
+    // Add serialization results
+    rows.push(metric_row("Serialization (ms/op)", &results.serialization, 4, columns));
+    
+    // Add deserialization results
+    rows.push(metric_row("Deserialization (ms/op)", &results.deserialization, 4, columns));
+    
+    // Add payload size results
+    rows.push(metric_row("Payload Size (bytes)", &results.payload_size.uncompressed, 0, columns));
+    
+    rows.push(metric_row("Compressed Size (bytes)", &results.payload_size.compressed, 0, columns));
+    
+    rows.push(metric_row("Zstd Size (bytes)", &results.payload_size.zstd_compressed, 0, columns));
+    
+    // Gzip cost and payoff
+    let payload = &results.payload_size;
+    if payload.compressed.status == TestStatus::Timeout {
+        rows.push(timeout_row("Gzip Time (ms/op)", columns));
+        rows.push(timeout_row("Gzip Ratio", columns));
+    } else {
+        let compress_time = BenchmarkMetric::lower_is_better(payload.json_compress_ms, payload.proto_compress_ms);
+        rows.push(metric_row("Gzip Time (ms/op)", &compress_time, 4, columns));
+        
+        // Higher is better
+        let mut ratio_row = vec![
+            "Gzip Ratio".to_string(),
+            columns.value(payload.json_ratio, 2),
+            columns.value(payload.proto_ratio, 2),
+        ];
+        ratio_row.extend(winner_cells(payload.json_ratio, payload.proto_ratio));
+        columns.extend(&mut ratio_row, payload.json_ratio, payload.proto_ratio, None);
+        rows.push(ratio_row);
+    }
+    
+    // Add CPU usage results
+    rows.push(metric_row("CPU Usage (ms)", &results.cpu_usage, 2, columns));
+    
+    // Add memory usage results
+    rows.push(metric_row("Memory Usage (bytes)", &results.memory_usage, 0, columns));
+    
+    // Add network transfer results
+    rows.push(metric_row("Network Transfer (ms)", &results.network_transfer, 2, columns));
+    
+    // Add latency under load results
+    rows.push(metric_row("Latency Under Load (ms)", &results.latency_under_load, 2, columns));
+    
+    // Add parser initialization results
+    rows.push(metric_row("Parser Init (ms)", &results.parser_init, 4, columns));
+    
+    // Add throughput results
+    if results.throughput.status == TestStatus::Timeout {
+        rows.push(timeout_row("Throughput (ops/s)", columns));
+    } else {
+        let mut throughput_row = vec![
+            "Throughput (ops/s)".to_string(),
+            columns.value(results.throughput.json, 2),
+            columns.value(results.throughput.protobuf, 2),
+            margin(results.throughput.percent_faster),
+            results.throughput.winner.clone(),
+        ];
+        columns.extend(&mut throughput_row, results.throughput.json, results.throughput.protobuf, None);
+        rows.push(throughput_row);
+        
+        if let Some(parallel) = &results.throughput.parallel {
+            let mut parallel_row = vec![
+                format!("Throughput x{} (ops/s)", parallel.producers),
+                columns.value(parallel.json, 2),
+                columns.value(parallel.protobuf, 2),
+            ];
+            parallel_row.extend(winner_cells(parallel.json, parallel.protobuf));
+            columns.extend(&mut parallel_row, parallel.json, parallel.protobuf, None);
+            rows.push(parallel_row);
+        }
+        
+        if let Some(normalized) = results.throughput.normalized {
+            let mut normalized_row = vec![
+                "Throughput (normalized)".to_string(),
+                columns.value(normalized.json, 4),
+                columns.value(normalized.protobuf, 4),
+            ];
+            normalized_row.extend(winner_cells(normalized.json, normalized.protobuf));
+            columns.extend(&mut normalized_row, normalized.json, normalized.protobuf, None);
+            rows.push(normalized_row);
+        }
+    }
+    
+    // Add schema evolution results
+    if results.schema_evolution.status == TestStatus::Timeout {
+        rows.push(timeout_row("Schema Evolution (ms/op)", columns));
+    } else if results.schema_evolution.status == TestStatus::Skipped {
+        let mut schema_row: Vec<String> = ["Schema Evolution (ms/op)", "-", "-", "-", "-"].iter().map(|cell| cell.to_string()).collect();
+        columns.extend(&mut schema_row, f64::NAN, f64::NAN, None);
+        rows.push(schema_row);
+    } else {
+        let mut schema_row = vec![
+            "Schema Evolution (ms/op)".to_string(),
+            columns.value(results.schema_evolution.json, 4),
+            format!("B: {} / F: {}",
+                   columns.value(results.schema_evolution.protobuf_backwards, 4),
+                   columns.value(results.schema_evolution.protobuf_forwards, 4)),
+            format!("{:.2}%", percent_faster(results.schema_evolution.json, results.schema_evolution.protobuf_average)),
+            results.schema_evolution.winner.clone(),
+        ];
+        columns.extend(&mut schema_row, results.schema_evolution.json, results.schema_evolution.protobuf_average, None);
+        rows.push(schema_row);
+    }
+    
+    rows
+}
+
+// A metric that additional codecs report: its table label, the protobuf value,
+// the codecs' values and the display precision
+type ExtraMetric<'a> = (&'static str, f64, &'a [(String, f64)], usize);
+
+// The metrics that additional codecs report
+fn extra_metrics(results: &BenchmarkResults) -> [ExtraMetric<'_>; 11] {
+    fn metric<'a>(label: &'static str, metric: &'a BenchmarkMetric, precision: usize) -> ExtraMetric<'a> {
+        (label, metric.protobuf, metric.others.as_slice(), precision)
+    }
+    [
+        metric("Serialization (ms/op)", &results.serialization, 4),
+        metric("Deserialization (ms/op)", &results.deserialization, 4),
+        metric("Payload Size (bytes)", &results.payload_size.uncompressed, 0),
+        metric("Compressed Size (bytes)", &results.payload_size.compressed, 0),
+        metric("Zstd Size (bytes)", &results.payload_size.zstd_compressed, 0),
+        metric("CPU Usage (ms)", &results.cpu_usage, 2),
+        metric("Memory Usage (bytes)", &results.memory_usage, 0),
+        metric("Network Transfer (ms)", &results.network_transfer, 2),
+        metric("Latency Under Load (ms)", &results.latency_under_load, 2),
+        metric("Parser Init (ms)", &results.parser_init, 4),
+        ("Throughput (ops/s)", results.throughput.protobuf, results.throughput.others.as_slice(), 2),
+    ]
+}
+
+// The results as a format-by-metric matrix, for the transposed table
+struct ResultMatrix {
+    metrics: Vec<String>,
+    // Each format's cell under every metric; "-" where the format wasn't measured
+    formats: Vec<(String, Vec<String>)>,
+    winners: Vec<String>,
+}
+
+// Collect the main table's rows and the additional codecs into one matrix
+fn result_matrix(rows: &[Vec<String>], extra_metrics: &[ExtraMetric], columns: Columns) -> ResultMatrix {
+    let rows = &rows[1..];
+    let mut formats: Vec<(String, Vec<String>)> = vec![
+        ("JSON".to_string(), rows.iter().map(|row| row[1].clone()).collect()),
+        ("Protobuf".to_string(), rows.iter().map(|row| row[2].clone()).collect()),
+    ];
+    
+    let mut names: Vec<&String> = Vec::new();
+    for (_, _, others, _) in extra_metrics {
+        for (name, _) in others.iter() {
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+    }
+    for name in names {
+        let cells = rows.iter()
+            .map(|row| {
+                extra_metrics.iter()
+                    .find(|(label, _, _, _)| *label == row[0])
+                    .and_then(|(_, _, others, precision)| {
+                        others.iter().find(|(other, _)| other == name).map(|(_, value)| columns.value(*value, *precision))
+                    })
+                    .unwrap_or_else(|| "-".to_string())
+            })
+            .collect();
+        formats.push((name.clone(), cells));
+    }
+    
+    // JSON and protobuf rows are all "-" when --formats left them out
+    formats.retain(|(_, cells)| cells.iter().any(|cell| cell != "-"));
+    
+    ResultMatrix {
+        metrics: rows.iter().map(|row| row[0].clone()).collect(),
+        formats,
+        winners: rows.iter().map(|row| row[4].clone()).collect(),
+    }
+}
+
+// A bold green header cell
+fn header_cell(text: &str, width: Option<usize>) -> Cell {
+    Cell::new(&wrap_cell(text, width)).style_spec("bFg")
+}
+
+// A body cell; numbers (including `%` and `x` suffixed ones) are right-aligned
+fn table_cell(text: &str, width: Option<usize>) -> Cell {
+    let cell = Cell::new(&wrap_cell(text, width));
+    let number = text.trim_end_matches(['%', 'x']);
+    if !number.is_empty() && number.parse::<f64>().is_ok() {
+        cell.style_spec("r")
+    } else {
+        cell
+    }
+}
+
+// Wrap text onto lines of at most `width` characters, breaking at spaces where
+// possible and splitting words that are longer than a line
+fn wrap_cell(text: &str, width: Option<usize>) -> String {
+    let Some(width) = width else {
+        return text.to_string();
+    };
+    let mut lines: Vec<String> = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        let chars: Vec<char> = word.chars().collect();
+        for chunk in chars.chunks(width) {
+            let chunk: String = chunk.iter().collect();
+            if !line.is_empty() && line.chars().count() + 1 + chunk.chars().count() <= width {
+                line.push(' ');
+                line.push_str(&chunk);
+            } else {
+                if !line.is_empty() {
+                    lines.push(std::mem::take(&mut line));
+                }
+                line = chunk;
+            }
+        }
+    }
+    lines.push(line);
+    lines.join("\n")
+}
+
+// A results table row for a metric measured for JSON, protobuf and the additional codecs
+fn metric_row(label: &str, metric: &BenchmarkMetric, precision: usize, columns: Columns) -> Vec<String> {
+    if metric.status == TestStatus::Timeout {
+        return timeout_row(label, columns);
+    }
+    let mut row = vec![
+        label.to_string(),
+        columns.value(metric.json, precision),
+        columns.value(metric.protobuf, precision),
+        margin(metric.percent_faster),
+        metric.winner.clone(),
+    ];
+    columns.extend(&mut row, metric.json, metric.protobuf, metric.peak_memory);
+    row
+}
+
+// A winner margin cell; "-" when fewer than two formats were measured
+fn margin(percent: f64) -> String {
+    if percent.is_nan() { "-".to_string() } else { format!("{:.2}%", percent) }
+}
+
+// Margin and winner cells for a higher-is-better JSON vs protobuf row
+fn winner_cells(json: f64, protobuf: f64) -> [String; 2] {
+    let (winner, percent) = winner_and_margin(&rank_formats(json, protobuf, &[], false));
+    [margin(percent), winner]
+}
+
+fn timeout_row(label: &str, columns: Columns) -> Vec<String> {
+    let mut row: Vec<String> = [label, "TIMEOUT", "TIMEOUT", "-", "-"].iter().map(|cell| cell.to_string()).collect();
+    columns.extend(&mut row, f64::NAN, f64::NAN, None);
+    row
+}
+
+// Two block characters whose heights show JSON and protobuf relative to the larger of the two
+fn sparkline(json: f64, protobuf: f64) -> String {
+    const BLOCKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    
+    let max = json.max(protobuf);
+    if json.is_nan() || protobuf.is_nan() || max <= 0.0 {
+        return String::new();
+    }
+    let block = |value: f64| BLOCKS[((value / max) * (BLOCKS.len() - 1) as f64).round() as usize];
+    [block(json), block(protobuf)].iter().collect()
+}
+
+// Await `future`, giving up after `timeout` if one is set
+async fn with_timeout<F: Future>(timeout: Option<Duration>, future: F) -> Option<F::Output> {
+    match timeout {
+        Some(timeout) => tokio::time::timeout(timeout, future).await.ok(),
+        None => Some(future.await),
+    }
+}
+
+// Adapts an io::Write into the BufMut prost encodes into, passing bytes on in
+// small chunks so the message is never fully buffered
+struct WriterBuf<W: Write> {
+    inner: W,
+    buf: Vec<u8>,
+}
+
+impl<W: Write> WriterBuf<W> {
+    const CHUNK_SIZE: usize = 4096;
+
+    fn new(inner: W) -> Self {
+        WriterBuf { inner, buf: Vec::with_capacity(Self::CHUNK_SIZE) }
+    }
+
+    fn flush_chunk(&mut self) -> std::io::Result<()> {
+        self.inner.write_all(&self.buf)?;
+        self.buf.clear();
+        Ok(())
+    }
+
+    fn into_inner(mut self) -> std::io::Result<W> {
+        self.flush_chunk()?;
+        Ok(self.inner)
+    }
+}
+
+unsafe impl<W: Write> BufMut for WriterBuf<W> {
+    fn remaining_mut(&self) -> usize {
+        usize::MAX
+    }
+
+    unsafe fn advance_mut(&mut self, cnt: usize) {
+        self.buf.advance_mut(cnt);
+        if self.buf.len() >= Self::CHUNK_SIZE {
+            self.flush_chunk().unwrap();
+        }
+    }
+
+    fn chunk_mut(&mut self) -> &mut UninitSlice {
+        self.buf.chunk_mut()
+    }
+}
+
+// Run `op` on `producers` threads for `duration`, each cycling through the pool
+// from its own offset. Returns the combined ops per second and Jain's fairness
+// index, (sum x)^2 / (n * sum x^2), over the per-thread op counts.
+fn parallel_ops_per_sec<F>(pool: &[(JsonPerson, test_data::Person)], producers: usize, duration: Duration, op: F) -> (f64, f64)
+where
+    F: Fn(&(JsonPerson, test_data::Person)) + Sync,
+{
+    let start = Instant::now();
+    let counts: Vec<f64> = std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..producers)
+            .map(|thread| {
+                let op = &op;
+                scope.spawn(move || {
+                    let mut inputs = pool.iter().cycle().skip(thread % pool.len());
+                    let mut count = 0u64;
+                    while start.elapsed() < duration {
+                        op(inputs.next().unwrap());
+                        count += 1;
+                    }
+                    count as f64
+                })
+            })
+            .collect();
+        handles.into_iter().map(|handle| handle.join().unwrap()).collect()
+    });
+    let elapsed = start.elapsed().as_secs_f64();
+    
+    let total: f64 = counts.iter().sum();
+    let sum_squares: f64 = counts.iter().map(|count| count * count).sum();
+    let fairness = if sum_squares > 0.0 { total * total / (producers as f64 * sum_squares) } else { 1.0 };
+    (total / elapsed, fairness)
+}
+
+// Field-by-field conversion between the serde and prost representations
+fn json_person_to_proto(person: &JsonPerson) -> Person {
+    Person {
+        name: person.name.clone(),
+        id: person.id,
+        email: person.email.clone(),
+        phones: person.phones.iter().map(|phone| person::PhoneNumber {
+            number: phone.number.clone(),
+            r#type: phone.type_,
+        }).collect(),
+        addresses: person.addresses.iter().map(|address| person::Address {
+            street: address.street.clone(),
+            city: address.city.clone(),
+            state: address.state.clone(),
+            zip: address.zip.clone(),
+            country: address.country.clone(),
+        }).collect(),
+        metadata: person.metadata.clone(),
+    }
+}
+
+fn shared_person_to_proto(person: &JsonPersonShared) -> Person {
+    Person {
+        name: person.name.clone(),
+        id: person.id,
+        email: person.email.clone(),
+        phones: person.phones.iter().map(|phone| person::PhoneNumber {
+            number: phone.number.clone(),
+            r#type: phone.type_,
+        }).collect(),
+        addresses: person.addresses.iter().map(|address| person::Address {
+            street: address.street.clone(),
+            city: address.city.clone(),
+            state: address.state.clone(),
+            zip: address.zip.clone(),
+            country: address.country.clone(),
+        }).collect(),
+        metadata: person.metadata.clone(),
+    }
+}
+
+fn proto_person_to_json(person: &Person) -> JsonPerson {
+    JsonPerson {
+        name: person.name.clone(),
+        id: person.id,
+        email: person.email.clone(),
+        phones: person.phones.iter().map(|phone| JsonPhoneNumber {
+            number: phone.number.clone(),
+            type_: phone.r#type,
+        }).collect(),
+        addresses: person.addresses.iter().map(|address| JsonAddress {
+            street: address.street.clone(),
+            city: address.city.clone(),
+            state: address.state.clone(),
+            zip: address.zip.clone(),
+            country: address.country.clone(),
+        }).collect(),
+        metadata: person.metadata.clone(),
+    }
+}
+
+// Apply a JSON Merge Patch (RFC 7396): objects merge member by member, `null`
+// removes a member and any other value replaces the target
+// Every string in a record, with metadata sorted by key, for round-trip comparisons
+fn json_text_fields(person: &JsonPerson) -> Vec<String> {
+    let mut fields = vec![person.name.clone(), person.email.clone()];
+    fields.extend(person.phones.iter().map(|phone| phone.number.clone()));
+    for address in &person.addresses {
+        fields.extend([&address.street, &address.city, &address.state, &address.zip, &address.country].map(String::clone));
+    }
+    let mut metadata: Vec<_> = person.metadata.iter().collect();
+    metadata.sort();
+    fields.extend(metadata.into_iter().flat_map(|(key, value)| [key.clone(), value.clone()]));
+    fields
+}
+
+// Write every non-ASCII character as `\uXXXX`, using a surrogate pair above U+FFFF
+fn escape_non_ascii(json: &str) -> String {
+    let mut out = String::with_capacity(json.len() * 2);
+    for c in json.chars() {
+        if c.is_ascii() {
+            out.push(c);
+        } else {
+            for unit in c.encode_utf16(&mut [0; 2]) {
+                out.push_str(&format!("\\u{:04x}", unit));
+            }
+        }
+    }
+    out
+}
+
+fn apply_merge_patch(target: &mut serde_json::Value, patch: &serde_json::Value) {
+    let serde_json::Value::Object(members) = patch else {
+        *target = patch.clone();
+        return;
+    };
+    if !target.is_object() {
+        *target = json!({});
+    }
+    let object = target.as_object_mut().unwrap();
+    for (key, value) in members {
+        if value.is_null() {
+            object.remove(key);
+        } else {
+            apply_merge_patch(object.entry(key.clone()).or_insert(serde_json::Value::Null), value);
+        }
+    }
+}
+
+// Copy the fields named in the patch's FieldMask onto `target`, replacing
+// map and repeated fields rather than merging them
+fn apply_field_mask(target: &mut Person, patch: PersonPatch) {
+    let (Some(mut source), Some(mask)) = (patch.person, patch.update_mask) else {
+        return;
+    };
+    for path in &mask.paths {
+        match path.as_str() {
+            "name" => target.name = std::mem::take(&mut source.name),
+            "id" => target.id = source.id,
+            "email" => target.email = std::mem::take(&mut source.email),
+            "phones" => target.phones = std::mem::take(&mut source.phones),
+            "addresses" => target.addresses = std::mem::take(&mut source.addresses),
+            "metadata" => target.metadata = std::mem::take(&mut source.metadata),
+            _ => {}
+        }
+    }
+}
+
+// Person plus the raw bytes of any fields it doesn't know, at the top level and
+// inside each phone and address, written back after the known fields on encode.
+// Field order within a message is not preserved, but every unknown field
+// survives a decode/encode round trip, as JSON catch-all maps do.
+#[derive(Default)]
+struct RetainingPerson {
+    // Scalars and metadata; phones and addresses are kept below with their unknown fields
+    person: Person,
+    phones: Vec<(person::PhoneNumber, Vec<u8>)>,
+    addresses: Vec<(person::Address, Vec<u8>)>,
+    unknown_fields: Vec<u8>,
+}
+
+impl RetainingPerson {
+    // Field numbers declared in person.proto
+    const PHONES_TAG: u32 = 4;
+    const ADDRESSES_TAG: u32 = 5;
+    const PERSON_TAGS: std::ops::RangeInclusive<u32> = 1..=6;
+    const PHONE_TAGS: std::ops::RangeInclusive<u32> = 1..=2;
+    const ADDRESS_TAGS: std::ops::RangeInclusive<u32> = 1..=5;
+
+    fn decode(mut buf: &[u8]) -> Result<Self, prost::DecodeError> {
+        let mut message = RetainingPerson::default();
+        while !buf.is_empty() {
+            let field_start = buf;
+            let (tag, wire_type) = encoding::decode_key(&mut buf)?;
+            match tag {
+                Self::PHONES_TAG | Self::ADDRESSES_TAG => {
+                    encoding::check_wire_type(encoding::WireType::LengthDelimited, wire_type)?;
+                    let len = encoding::decode_varint(&mut buf)? as usize;
+                    if len > buf.len() {
+                        return Err(prost::DecodeError::new("buffer underflow"));
+                    }
+                    let (nested, rest) = buf.split_at(len);
+                    buf = rest;
+                    if tag == Self::PHONES_TAG {
+                        let mut phone = person::PhoneNumber::default();
+                        let unknown = merge_known_fields(nested, Self::PHONE_TAGS, &mut phone)?;
+                        message.phones.push((phone, unknown));
+                    } else {
+                        let mut address = person::Address::default();
+                        let unknown = merge_known_fields(nested, Self::ADDRESS_TAGS, &mut address)?;
+                        message.addresses.push((address, unknown));
+                    }
+                },
+                _ if Self::PERSON_TAGS.contains(&tag) => {
+                    message.person.merge_field(tag, wire_type, &mut buf, encoding::DecodeContext::default())?;
+                },
+                _ => {
+                    encoding::skip_field(wire_type, tag, &mut buf, encoding::DecodeContext::default())?;
+                    let field_len = field_start.len() - buf.len();
+                    message.unknown_fields.extend_from_slice(&field_start[..field_len]);
+                },
+            }
+        }
+        Ok(message)
+    }
+
+    fn encode_to_vec(&self) -> Vec<u8> {
+        let mut buf = self.person.encode_to_vec();
+        for (phone, unknown) in &self.phones {
+            encode_with_unknown(Self::PHONES_TAG, phone, unknown, &mut buf);
+        }
+        for (address, unknown) in &self.addresses {
+            encode_with_unknown(Self::ADDRESSES_TAG, address, unknown, &mut buf);
+        }
+        buf.extend_from_slice(&self.unknown_fields);
+        buf
+    }
+}
+
+// Merge the fields of `buf` numbered in `known` into `message`, returning the
+// raw bytes of the rest
+fn merge_known_fields<M: Message>(mut buf: &[u8], known: std::ops::RangeInclusive<u32>, message: &mut M)
+    -> Result<Vec<u8>, prost::DecodeError>
+{
+    let mut unknown = Vec::new();
+    while !buf.is_empty() {
+        let field_start = buf;
+        let (tag, wire_type) = encoding::decode_key(&mut buf)?;
+        if known.contains(&tag) {
+            message.merge_field(tag, wire_type, &mut buf, encoding::DecodeContext::default())?;
+        } else {
+            encoding::skip_field(wire_type, tag, &mut buf, encoding::DecodeContext::default())?;
+            unknown.extend_from_slice(&field_start[..field_start.len() - buf.len()]);
+        }
+    }
+    Ok(unknown)
+}
+
+// Write `message` as field `tag` of its parent, with its unknown fields appended
+// Larger over smaller of two per-op times; infinite when one of them is zero
+fn stability_ratio(a: f64, b: f64) -> f64 {
+    a.max(b) / a.min(b)
+}
+
+// Encode a Person like prost does, but with metadata entries sorted by key, so
+// equal records always produce the same bytes. prost writes map entries in
+// HashMap iteration order, which changes with every map's hash seed.
+fn encode_deterministic(person: &Person, buf: &mut Vec<u8>) {
+    if !person.name.is_empty() {
+        encoding::string::encode(1, &person.name, buf);
+    }
+    if person.id != 0 {
+        encoding::int32::encode(2, &person.id, buf);
+    }
+    if !person.email.is_empty() {
+        encoding::string::encode(3, &person.email, buf);
+    }
+    encoding::message::encode_repeated(4, &person.phones, buf);
+    encoding::message::encode_repeated(5, &person.addresses, buf);
+    
+    let mut entries: Vec<_> = person.metadata.iter().collect();
+    entries.sort_unstable();
+    for (key, value) in entries {
+        // Each entry is a nested message; like prost, leave out empty keys and values
+        let key_len = if key.is_empty() { 0 } else { encoding::string::encoded_len(1, key) };
+        let value_len = if value.is_empty() { 0 } else { encoding::string::encoded_len(2, value) };
+        encoding::encode_key(6, encoding::WireType::LengthDelimited, buf);
+        encoding::encode_varint((key_len + value_len) as u64, buf);
+        if !key.is_empty() {
+            encoding::string::encode(1, key, buf);
+        }
+        if !value.is_empty() {
+            encoding::string::encode(2, value, buf);
+        }
+    }
+}
+
+fn encode_with_unknown<M: Message>(tag: u32, message: &M, unknown: &[u8], buf: &mut Vec<u8>) {
+    encoding::encode_key(tag, encoding::WireType::LengthDelimited, buf);
+    encoding::encode_varint((message.encoded_len() + unknown.len()) as u64, buf);
+    message.encode(buf).unwrap();
+    buf.extend_from_slice(unknown);
+}
+
+// Apply 1-4 random byte-level mutations: bit flips, deletions, insertions and truncation
+fn mutate(bytes: &[u8], rng: &mut StdRng) -> Vec<u8> {
+    let mut mutated = bytes.to_vec();
+    for _ in 0..rng.gen_range(1..=4) {
+        if mutated.is_empty() {
+            break;
+        }
+        let pos = rng.gen_range(0..mutated.len());
+        match rng.gen_range(0..4) {
+            0 => mutated[pos] ^= 1 << rng.gen_range(0..8),
+            1 => { mutated.remove(pos); },
+            2 => mutated.insert(pos, rng.gen()),
+            _ => mutated.truncate(pos),
+        }
+    }
+    mutated
+}
+
+// Read a protobuf varint length prefix from a stream; `None` at a clean end of input
+fn read_length_delimiter<R: Read>(reader: &mut R) -> std::io::Result<Option<usize>> {
+    let mut value = 0usize;
+    for shift in (0..64).step_by(7) {
+        let mut byte = [0u8];
+        if reader.read(&mut byte)? == 0 {
+            if shift == 0 {
+                return Ok(None);
+            }
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+        value |= ((byte[0] & 0x7f) as usize) << shift;
+        if byte[0] & 0x80 == 0 {
+            return Ok(Some(value));
+        }
+    }
+    Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "varint too long"))
+}
+
+// Length of a `"key":value` member as it appears in the serialized JSON object
+fn json_member_len<T: serde::Serialize + ?Sized>(key: &str, value: &T) -> usize {
+    serde_json::to_string(key).unwrap().len() + 1 + serde_json::to_string(value).unwrap().len()
+}
+
+// Attribute the bytes of one sample record to strings, integers, the metadata map,
+// repeated fields and (for JSON) structural overhead such as braces and commas.
+// The categories of each format sum to its full serialized size.
+pub fn field_byte_breakdown(json_data: &JsonPerson, proto_data: &test_data::Person) -> Vec<FieldBytes> {
+    // JSON: measure each member's substring, the remainder is object syntax
+    let json_total = serde_json::to_string(json_data).unwrap().len();
+    let json_strings = json_member_len("name", &json_data.name) + json_member_len("email", &json_data.email);
+    let json_integers = json_member_len("id", &json_data.id);
+    let json_metadata = json_member_len("metadata", &json_data.metadata);
+    let json_repeated = json_member_len("phones", &json_data.phones)
+        + json_member_len("addresses", &json_data.addresses);
+    let json_structure = json_total - json_strings - json_integers - json_metadata - json_repeated;
+    
+    // Protobuf: the per-field encoded length (tag + length prefix + payload)
+    let proto_strings = encoding::string::encoded_len(1, &proto_data.name)
+        + encoding::string::encoded_len(3, &proto_data.email);
+    let proto_integers = if proto_data.id != 0 { encoding::int32::encoded_len(2, &proto_data.id) } else { 0 };
+    let proto_metadata = encoding::hash_map::encoded_len(
+        encoding::string::encoded_len,
+        encoding::string::encoded_len,
+        6,
+        &proto_data.metadata,
+    );
+    let proto_repeated = encoding::message::encoded_len_repeated(4, &proto_data.phones)
+        + encoding::message::encoded_len_repeated(5, &proto_data.addresses);
+    let proto_structure = proto_data.encoded_len() - proto_strings - proto_integers - proto_metadata - proto_repeated;
+    
+    vec![
+        FieldBytes { category: "Strings", json: json_strings, protobuf: proto_strings },
+        FieldBytes { category: "Integers", json: json_integers, protobuf: proto_integers },
+        FieldBytes { category: "Metadata map", json: json_metadata, protobuf: proto_metadata },
+        FieldBytes { category: "Repeated fields", json: json_repeated, protobuf: proto_repeated },
+        FieldBytes { category: "Structure", json: json_structure, protobuf: proto_structure },
+    ]
+}
+
+// Print the gzip level sweep as a level x format matrix, with columns for the
+// selected formats only
+fn print_gzip_levels(levels: &[CompressionLevelMetric], json: bool, protobuf: bool) {
+    if !json && !protobuf {
+        return;
+    }
+    let mut table = Table::new();
+    let mut header = vec![Cell::new("Gzip Level").style_spec("bFg")];
+    for (selected, name) in [(json, "JSON"), (protobuf, "Protobuf")] {
+        if selected {
+            header.push(Cell::new(&format!("{} (bytes)", name)).style_spec("bFg"));
+            header.push(Cell::new(&format!("{} (ms)", name)).style_spec("bFg"));
+        }
+    }
+    table.add_row(Row::new(header));
+    for level in levels {
+        let mut cells = vec![Cell::new(&level.level.to_string())];
+        for (selected, bytes, ms) in [(json, level.json_bytes, level.json_ms), (protobuf, level.protobuf_bytes, level.protobuf_ms)] {
+            if selected {
+                cells.push(Cell::new(&bytes.to_string()));
+                cells.push(Cell::new(&format!("{:.4}", ms)));
+            }
+        }
+        table.add_row(Row::new(cells));
+    }
+    eprint!("{}", table);
+}
+
+// Warn when the protobuf encoding is larger than the JSON one, which surprises
+// users who expect protobuf to always be smaller
+fn warn_if_protobuf_larger(json_size: usize, proto_size: usize) {
+    if proto_size <= json_size {
+        return;
+    }
+    eprintln!("{}", format!("Note: protobuf is {:.1}% larger than JSON for this data.",
+                          (proto_size as f64 / json_size as f64 - 1.0) * 100.0).yellow());
+    eprintln!("{}", "Protobuf saves space on field names and large numbers, but negative int32/int64 values \
+                    always take 10 bytes as varints (sint32/sint64 avoid this), double and fixed64 fields \
+                    always take 8 bytes where JSON may print a short value like 0.5 in 3, and small \
+                    numbers cost JSON only a digit or two.".yellow());
+}
+
+// Print raw and gzip sizes per null fraction; gzip shrinks the repeated
+// `"field":null` members to back-references, so explicit nulls cost little once compressed
+fn print_sparsity(rows: &[SparsityRow]) {
+    let mut table = Table::new();
+    table.add_row(row![bFg->"Absent", bFg->"JSON nulls", bFg->"gzip", bFg->"JSON omitted", bFg->"gzip",
+                       bFg->"Protobuf", bFg->"gzip"]);
+    for row in rows {
+        table.add_row(row![
+            format!("{:.0}%", row.null_fraction * 100.0),
+            row.json_nulls,
+            row.json_nulls_gzip,
+            row.json_omitted,
+            row.json_omitted_gzip,
+            row.protobuf,
+            row.protobuf_gzip
+        ]);
+    }
+    eprint!("{}", table);
+}
+
+// Mean milliseconds to send `payload` to an echo server and read it back, after
+// `warmup` untimed round trips. Writing and reading run together so a payload larger
+// than the socket buffers can't stall both ends.
+async fn echo_round_trip_ms(stream: &mut TcpStream, payload: &[u8], warmup: usize, iterations: usize) -> std::io::Result<f64> {
+    let (mut reader, mut writer) = stream.split();
+    let mut echoed = vec![0u8; payload.len()];
+    for _ in 0..warmup {
+        tokio::try_join!(writer.write_all(payload), reader.read_exact(&mut echoed))?;
+    }
+    
+    let start = Instant::now();
+    for _ in 0..iterations {
+        tokio::try_join!(writer.write_all(payload), reader.read_exact(&mut echoed))?;
+    }
+    let elapsed = start.elapsed();
+    if echoed != payload {
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "echoed bytes differ from the payload"));
+    }
+    Ok(elapsed.as_secs_f64() * 1000.0 / iterations as f64)
+}
+
+// Drain a channel, spending CONSUMER_WORK per message; returns the message count
+async fn slow_consumer<T>(mut rx: tokio::sync::mpsc::Receiver<T>) -> usize {
+    let mut received = 0;
+    while let Some(message) = rx.recv().await {
+        std::hint::black_box(message);
+        // Busy-wait: tokio's timer can't sleep for less than a millisecond
+        let start = Instant::now();
+        while start.elapsed() < CONSUMER_WORK {
+            std::hint::spin_loop();
+        }
+        received += 1;
+    }
+    received
+}
+
+// Median time in milliseconds to drop a freshly built collection, and the heap
+// bytes the drop released
+fn median_drop<T, F: Fn() -> T>(build: F) -> (f64, usize) {
+    let mut times = Vec::with_capacity(DROP_ROUNDS);
+    let mut freed = 0;
+    for _ in 0..DROP_ROUNDS {
+        let collection = std::hint::black_box(build());
+        let before = alloc_tracker::current_bytes();
+        let start = Instant::now();
+        drop(collection);
+        times.push(start.elapsed().as_secs_f64() * 1000.0);
+        freed = before.saturating_sub(alloc_tracker::current_bytes());
+    }
+    times.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    (times[DROP_ROUNDS / 2], freed)
+}
+
+// Mean milliseconds per call of `pass` over INPUT_PASSES calls
+fn time_passes<F: Fn()>(pass: F) -> f64 {
+    let start = Instant::now();
+    for _ in 0..INPUT_PASSES {
+        pass();
+    }
+    start.elapsed().as_secs_f64() * 1000.0 / INPUT_PASSES as f64
+}
+
+// Size of `bytes` after gzip at the default level
+fn gzip_len(bytes: &[u8]) -> usize {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(bytes).unwrap();
+    encoder.finish().unwrap().len()
+}
+
+// Print the per-category byte breakdown as a table
+fn print_field_breakdown(breakdown: &[FieldBytes]) {
+    eprintln!("{}", "Bytes per field type (one record):".blue());
+    
+    let mut table = Table::new();
+    table.add_row(row![bFg->"Field Type", bFg->"JSON (bytes)", bFg->"Protobuf (bytes)", bFg->"Saved"]);
+    
+    let mut json_total = 0;
+    let mut proto_total = 0;
+    for entry in breakdown {
+        json_total += entry.json;
+        proto_total += entry.protobuf;
+        table.add_row(row![
+            entry.category,
+            entry.json,
+            entry.protobuf,
+            format!("{}", entry.json as i64 - entry.protobuf as i64)
+        ]);
+    }
+    table.add_row(row![b->"Total", b->json_total, b->proto_total, b->format!("{}", json_total as i64 - proto_total as i64)]);
+    
+    eprint!("{}", table);
+}
+
+// Insert `<test>.<format>.<unit>` entries for a metric and its additional codecs
+fn flatten_metric(flat: &mut BTreeMap<String, serde_json::Value>, test: &str, unit: &str, metric: &BenchmarkMetric) {
+    flat.insert(format!("{}.json.{}", test, unit), json!(metric.json));
+    flat.insert(format!("{}.protobuf.{}", test, unit), json!(metric.protobuf));
+    flat.insert(format!("{}.difference.ratio", test), json!(metric.ratio));
+    flat.insert(format!("{}.difference.percent_faster", test), json!(metric.percent_faster));
+    if let Some(p_value) = metric.p_value {
+        flat.insert(format!("{}.p_value", test), json!(p_value));
+    }
+    if let Some(peak) = metric.peak_memory {
+        for (format, bytes) in [("json", peak.json_bytes), ("protobuf", peak.protobuf_bytes)] {
+            if let Some(bytes) = bytes {
+                flat.insert(format!("{}.{}.peak_bytes", test, format), json!(bytes));
+            }
+        }
+    }
+    if let Some(network) = metric.network {
+        flat.insert(format!("{}.json.transmission_ms", test), json!(network.json_transmission_ms));
+        flat.insert(format!("{}.protobuf.transmission_ms", test), json!(network.protobuf_transmission_ms));
+        flat.insert(format!("{}.base_latency_ms", test), json!(network.base_latency_ms));
+    }
+    for (name, value) in &metric.others {
+        flat.insert(format!("{}.{}.{}", test, name, unit), json!(*value));
+    }
+}
+
+// Flatten results into dotted metric names (e.g. `serialization.json.ms`) for TSDB ingestion,
+// plus the string `run_id`
+pub fn flatten_results(results: &BenchmarkResults) -> BTreeMap<String, serde_json::Value> {
+    let mut flat = BTreeMap::new();
+    flat.insert("run_id".to_string(), json!(results.run_id));
+    flat.insert("worker_threads".to_string(), json!(results.worker_threads));
+    flat.insert("allocator".to_string(), json!(results.allocator));
+    
+    flatten_metric(&mut flat, "serialization", "ms", &results.serialization);
+    flatten_metric(&mut flat, "deserialization", "ms", &results.deserialization);
+    flatten_metric(&mut flat, "payload_size", "bytes", &results.payload_size.uncompressed);
+    flatten_metric(&mut flat, "compressed_size", "bytes", &results.payload_size.compressed);
+    flatten_metric(&mut flat, "zstd_size", "bytes", &results.payload_size.zstd_compressed);
+    flat.insert("gzip.json.ms".to_string(), json!(results.payload_size.json_compress_ms));
+    flat.insert("gzip.protobuf.ms".to_string(), json!(results.payload_size.proto_compress_ms));
+    flat.insert("gzip.json.ratio".to_string(), json!(results.payload_size.json_ratio));
+    flat.insert("gzip.protobuf.ratio".to_string(), json!(results.payload_size.proto_ratio));
+    flatten_metric(&mut flat, "cpu_usage", "ms", &results.cpu_usage);
+    flatten_metric(&mut flat, "memory_usage", "bytes", &results.memory_usage);
+    flatten_metric(&mut flat, "network_transfer", "ms", &results.network_transfer);
+    flatten_metric(&mut flat, "latency_under_load", "ms", &results.latency_under_load);
+    flatten_metric(&mut flat, "parser_init", "ms", &results.parser_init);
+    
+    flat.insert("throughput.json.ops_per_sec".to_string(), json!(results.throughput.json));
+    flat.insert("throughput.protobuf.ops_per_sec".to_string(), json!(results.throughput.protobuf));
+    flat.insert("throughput.difference.ratio".to_string(), json!(results.throughput.ratio));
+    flat.insert("throughput.difference.percent_faster".to_string(), json!(results.throughput.percent_faster));
+    if let Some(parallel) = &results.throughput.parallel {
+        flat.insert("throughput_parallel.json.ops_per_sec".to_string(), json!(parallel.json));
+        flat.insert("throughput_parallel.protobuf.ops_per_sec".to_string(), json!(parallel.protobuf));
+    }
+    if let Some(normalized) = results.throughput.normalized {
+        flat.insert("throughput.json.normalized".to_string(), json!(normalized.json));
+        flat.insert("throughput.protobuf.normalized".to_string(), json!(normalized.protobuf));
+        flat.insert("throughput.calibration.ns".to_string(), json!(normalized.calibration_ns));
+    }
+    
+    flat.insert("bytes_processed.json.bytes".to_string(), json!(results.bytes_processed.json_bytes));
+    flat.insert("bytes_processed.protobuf.bytes".to_string(), json!(results.bytes_processed.protobuf_bytes));
+    flat.insert("bytes_processed.json.mb_per_sec".to_string(), json!(results.bytes_processed.json_mb_per_sec));
+    flat.insert("bytes_processed.protobuf.mb_per_sec".to_string(), json!(results.bytes_processed.protobuf_mb_per_sec));
+    
+    flat.insert("schema_evolution.json.ms".to_string(), json!(results.schema_evolution.json));
+    flat.insert("schema_evolution.protobuf_backwards.ms".to_string(), json!(results.schema_evolution.protobuf_backwards));
+    flat.insert("schema_evolution.protobuf_forwards.ms".to_string(), json!(results.schema_evolution.protobuf_forwards));
+    flat.insert("schema_evolution.protobuf.ms".to_string(), json!(results.schema_evolution.protobuf_average));
+    flat.insert("schema_evolution.protobuf_basic_decode.ms".to_string(), json!(results.schema_evolution.decode_cost.basic_ms));
+    flat.insert("schema_evolution.protobuf_evolved_decode.ms".to_string(), json!(results.schema_evolution.decode_cost.evolved_ms));
+    flat.insert("schema_evolution.protobuf_reduced_decode.ms".to_string(), json!(results.schema_evolution.removed_field.ms));
+    
+    flat
+}
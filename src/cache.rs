@@ -0,0 +1,32 @@
+use crate::benchmark::BenchmarkResults;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+// Path of the cache entry for a run ID: `<dir>/<run_id>.json`
+fn entry_path(dir: &str, run_id: &str) -> PathBuf {
+    Path::new(dir).join(format!("{}.json", run_id))
+}
+
+// Load the cached results of a configuration, or None if it hasn't been run
+pub fn load(dir: &str, run_id: &str) -> Result<Option<BenchmarkResults>, String> {
+    let path = entry_path(dir, run_id);
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+    };
+    serde_json::from_str(&text)
+        .map(Some)
+        .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))
+}
+
+// Store a run's results as plain JSON, creating the directory if needed.
+// Returns the path written.
+pub fn store(dir: &str, results: &BenchmarkResults) -> Result<PathBuf, String> {
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir, e))?;
+    let path = entry_path(dir, &results.run_id);
+    fs::write(&path, serde_json::to_string_pretty(results).unwrap())
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(path)
+}
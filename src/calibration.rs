@@ -0,0 +1,56 @@
+use std::hint::black_box;
+use std::time::Instant;
+
+// Elements sorted and hashed by one calibration op
+const WORKLOAD_LEN: usize = 4096;
+// Calibration ops per timed round, and rounds whose median is reported
+const OPS_PER_ROUND: usize = 50;
+const ROUNDS: usize = 7;
+
+// Time a fixed reference workload, in nanoseconds per op.
+//
+// The workload is independent of the formats under test: fill a buffer from a
+// fixed xorshift sequence, sort it and FNV-1a hash the result. Like parsing, it
+// is a mix of branchy integer work and sequential memory access, so it scales
+// with the machine in roughly the same way. The median round is used so a
+// single preempted round doesn't skew the result.
+pub fn calibrate() -> f64 {
+    let mut rounds: Vec<f64> = (0..ROUNDS)
+        .map(|_| {
+            let start = Instant::now();
+            for _ in 0..OPS_PER_ROUND {
+                black_box(reference_op());
+            }
+            start.elapsed().as_nanos() as f64 / OPS_PER_ROUND as f64
+        })
+        .collect();
+    rounds.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    rounds[ROUNDS / 2]
+}
+
+// Normalized throughput: how many operations complete in the time of one
+// calibration op.
+//
+//     score = ops_per_sec * calibration_ns / 1e9
+//
+// A machine twice as fast runs both the benchmark and the calibration op twice
+// as fast, so the score stays the same and can be compared across machines.
+pub fn normalize(ops_per_sec: f64, calibration_ns: f64) -> f64 {
+    ops_per_sec * calibration_ns / 1e9
+}
+
+fn reference_op() -> u64 {
+    let mut state = 0x2545_f491_4f6c_dd1du64;
+    let mut values: Vec<u64> = (0..WORKLOAD_LEN)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        })
+        .collect();
+    values.sort_unstable();
+    values.iter().fold(0xcbf2_9ce4_8422_2325, |hash, value| {
+        (hash ^ value).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
@@ -0,0 +1,353 @@
+#[cfg(feature = "capnp")]
+use crate::person_capnp::person;
+#[cfg(feature = "capnp")]
+use crate::test_data::{JsonAddress, JsonPhoneNumber};
+use crate::test_data::JsonPerson;
+use serde::Serialize;
+use serde_json::ser::{CompactFormatter, Formatter};
+use serde_json::Value;
+use std::io::{self, Write};
+
+// A format benchmarked alongside JSON and Protocol Buffers. Codecs work on
+// `JsonPerson`: most need no schema beyond its serde derives, Cap'n Proto
+// converts to and from its own schema's structs.
+pub trait Codec: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn serialize(&self, person: &JsonPerson) -> Vec<u8>;
+    fn deserialize(&self, bytes: &[u8]) -> JsonPerson;
+
+    // The read the deserialization test times: a full `deserialize` unless the
+    // format can read its fields in place
+    fn access(&self, bytes: &[u8]) {
+        let _ = self.deserialize(bytes);
+    }
+}
+
+// postcard: compact, varint-based serde format popular in embedded Rust
+pub struct Postcard;
+
+impl Codec for Postcard {
+    fn name(&self) -> &'static str {
+        "postcard"
+    }
+
+    fn serialize(&self, person: &JsonPerson) -> Vec<u8> {
+        postcard::to_stdvec(person).unwrap()
+    }
+
+    fn deserialize(&self, bytes: &[u8]) -> JsonPerson {
+        postcard::from_bytes(bytes).unwrap()
+    }
+}
+
+// bincode, the usual choice for Rust-to-Rust services: fixed-width integers and
+// no field names or tags, so it needs the exact same struct on both ends
+pub struct Bincode;
+
+impl Codec for Bincode {
+    fn name(&self) -> &'static str {
+        "bincode"
+    }
+
+    fn serialize(&self, person: &JsonPerson) -> Vec<u8> {
+        bincode::serialize(person).unwrap()
+    }
+
+    fn deserialize(&self, bytes: &[u8]) -> JsonPerson {
+        bincode::deserialize(bytes).unwrap()
+    }
+}
+
+// MessagePack via rmp-serde, the common middle ground: binary, but self-describing
+// like JSON. Structs are written as maps with field names (`to_vec_named`), as most
+// MessagePack producers in other languages do, rather than as positional arrays.
+pub struct MessagePack;
+
+impl Codec for MessagePack {
+    fn name(&self) -> &'static str {
+        "msgpack"
+    }
+
+    fn serialize(&self, person: &JsonPerson) -> Vec<u8> {
+        rmp_serde::to_vec_named(person).unwrap()
+    }
+
+    fn deserialize(&self, bytes: &[u8]) -> JsonPerson {
+        rmp_serde::from_slice(bytes).unwrap()
+    }
+}
+
+// CBOR (RFC 8949) via ciborium, as used by IoT protocols and COSE: self-describing
+// like JSON (field names on the wire) but binary like protobuf
+pub struct Cbor;
+
+impl Codec for Cbor {
+    fn name(&self) -> &'static str {
+        "cbor"
+    }
+
+    fn serialize(&self, person: &JsonPerson) -> Vec<u8> {
+        let mut out = Vec::new();
+        ciborium::into_writer(person, &mut out).unwrap();
+        out
+    }
+
+    fn deserialize(&self, bytes: &[u8]) -> JsonPerson {
+        ciborium::from_reader(bytes).unwrap()
+    }
+}
+
+// XML via quick-xml's serde support, for the classic three-way comparison
+pub struct Xml;
+
+impl Codec for Xml {
+    fn name(&self) -> &'static str {
+        "xml"
+    }
+
+    fn serialize(&self, person: &JsonPerson) -> Vec<u8> {
+        quick_xml::se::to_string(person).unwrap().into_bytes()
+    }
+
+    fn deserialize(&self, bytes: &[u8]) -> JsonPerson {
+        quick_xml::de::from_str(std::str::from_utf8(bytes).unwrap()).unwrap()
+    }
+}
+
+// Canonical JSON (object keys sorted, no insignificant whitespace), as needed
+// for signing or hashing. Protobuf makes no such guarantee: map entry order and
+// unknown-field placement can differ between encoders.
+pub struct CanonicalJson;
+
+impl Codec for CanonicalJson {
+    fn name(&self) -> &'static str {
+        "canonical-json"
+    }
+
+    fn serialize(&self, person: &JsonPerson) -> Vec<u8> {
+        let mut out = String::new();
+        write_canonical(&serde_json::to_value(person).unwrap(), &mut out);
+        out.into_bytes()
+    }
+
+    fn deserialize(&self, bytes: &[u8]) -> JsonPerson {
+        serde_json::from_slice(bytes).unwrap()
+    }
+}
+
+fn write_canonical(value: &Value, out: &mut String) {
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_by_key(|(key, _)| *key);
+            out.push('{');
+            for (i, (key, value)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                out.push_str(&serde_json::to_string(key).unwrap());
+                out.push(':');
+                write_canonical(value, out);
+            }
+            out.push('}');
+        }
+        Value::Array(values) => {
+            out.push('[');
+            for (i, value) in values.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_canonical(value, out);
+            }
+            out.push(']');
+        }
+        // Scalars already have a single compact encoding
+        scalar => out.push_str(&serde_json::to_string(scalar).unwrap()),
+    }
+}
+
+// JSON5 (comments, trailing commas), as used by config files, parsed with the
+// `json5` crate. Documents are written as JSON with a leading comment and a
+// trailing comma in every non-empty object and array, so the permissive parts
+// of the grammar are actually exercised.
+pub struct Json5;
+
+impl Codec for Json5 {
+    fn name(&self) -> &'static str {
+        "json5"
+    }
+
+    fn serialize(&self, person: &JsonPerson) -> Vec<u8> {
+        let mut out = b"// Person record\n".to_vec();
+        let mut serializer = serde_json::Serializer::with_formatter(&mut out, TrailingCommas::default());
+        person.serialize(&mut serializer).unwrap();
+        out
+    }
+
+    fn deserialize(&self, bytes: &[u8]) -> JsonPerson {
+        json5::from_str(std::str::from_utf8(bytes).unwrap()).unwrap()
+    }
+}
+
+// Compact JSON with a comma after the last element of every non-empty container
+#[derive(Default)]
+struct TrailingCommas {
+    // Whether each open container has had an element yet
+    non_empty: Vec<bool>,
+}
+
+impl TrailingCommas {
+    fn end<W: ?Sized + Write>(&mut self, writer: &mut W, close: &[u8]) -> io::Result<()> {
+        if self.non_empty.pop().unwrap_or(false) {
+            writer.write_all(b",")?;
+        }
+        writer.write_all(close)
+    }
+}
+
+impl Formatter for TrailingCommas {
+    fn begin_array<W: ?Sized + Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.non_empty.push(false);
+        writer.write_all(b"[")
+    }
+
+    fn begin_array_value<W: ?Sized + Write>(&mut self, writer: &mut W, first: bool) -> io::Result<()> {
+        *self.non_empty.last_mut().unwrap() = true;
+        CompactFormatter.begin_array_value(writer, first)
+    }
+
+    fn end_array<W: ?Sized + Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.end(writer, b"]")
+    }
+
+    fn begin_object<W: ?Sized + Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.non_empty.push(false);
+        writer.write_all(b"{")
+    }
+
+    fn begin_object_key<W: ?Sized + Write>(&mut self, writer: &mut W, first: bool) -> io::Result<()> {
+        *self.non_empty.last_mut().unwrap() = true;
+        CompactFormatter.begin_object_key(writer, first)
+    }
+
+    fn end_object<W: ?Sized + Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.end(writer, b"}")
+    }
+}
+
+
+// Cap'n Proto (proto/person.capnp), the other schema-based binary format. Its wire
+// layout is its in-memory layout, so `access` reads every field straight out of
+// the buffer without decoding or allocating, which is what the deserialization
+// test times; `deserialize` copies the fields into a `JsonPerson`. Cap'n Proto
+// has no map type, so metadata is a list of key/value entries.
+#[cfg(feature = "capnp")]
+pub struct Capnp;
+
+#[cfg(feature = "capnp")]
+impl Capnp {
+    fn read(bytes: &[u8]) -> capnp::Result<capnp::message::Reader<capnp::serialize::NoAllocSliceSegments<'_>>> {
+        capnp::serialize::read_message_from_flat_slice_no_alloc(&mut &bytes[..], capnp::message::ReaderOptions::new())
+    }
+
+    fn decode(bytes: &[u8]) -> capnp::Result<JsonPerson> {
+        let message = Self::read(bytes)?;
+        let root = message.get_root::<person::Reader>()?;
+        let text = |text: capnp::Result<capnp::text::Reader>| -> capnp::Result<String> { Ok(text?.to_str()?.to_string()) };
+        
+        let mut phones = Vec::new();
+        for phone in root.get_phones()? {
+            phones.push(JsonPhoneNumber {
+                number: text(phone.get_number())?,
+                type_: phone.get_type()? as i32,
+            });
+        }
+        let mut addresses = Vec::new();
+        for address in root.get_addresses()? {
+            addresses.push(JsonAddress {
+                street: text(address.get_street())?,
+                city: text(address.get_city())?,
+                state: text(address.get_state())?,
+                zip: text(address.get_zip())?,
+                country: text(address.get_country())?,
+            });
+        }
+        let mut metadata = std::collections::HashMap::new();
+        for entry in root.get_metadata()? {
+            metadata.insert(text(entry.get_key())?, text(entry.get_value())?);
+        }
+        Ok(JsonPerson {
+            name: text(root.get_name())?,
+            id: root.get_id(),
+            email: text(root.get_email())?,
+            phones,
+            addresses,
+            metadata,
+        })
+    }
+
+    // Touch every field in place; returns the text bytes seen so the reads can't
+    // be optimized away
+    fn visit(bytes: &[u8]) -> capnp::Result<usize> {
+        let message = Self::read(bytes)?;
+        let root = message.get_root::<person::Reader>()?;
+        let mut seen = root.get_name()?.len() + root.get_email()?.len() + root.get_id() as usize;
+        for phone in root.get_phones()? {
+            seen += phone.get_number()?.len() + phone.get_type()? as usize;
+        }
+        for address in root.get_addresses()? {
+            seen += address.get_street()?.len() + address.get_city()?.len() + address.get_state()?.len()
+                + address.get_zip()?.len() + address.get_country()?.len();
+        }
+        for entry in root.get_metadata()? {
+            seen += entry.get_key()?.len() + entry.get_value()?.len();
+        }
+        Ok(seen)
+    }
+}
+
+#[cfg(feature = "capnp")]
+impl Codec for Capnp {
+    fn name(&self) -> &'static str {
+        "capnp"
+    }
+
+    fn serialize(&self, person: &JsonPerson) -> Vec<u8> {
+        let mut message = capnp::message::Builder::new_default();
+        let mut root = message.init_root::<person::Builder>();
+        root.set_name(person.name.as_str());
+        root.set_id(person.id);
+        root.set_email(person.email.as_str());
+        
+        let mut phones = root.reborrow().init_phones(person.phones.len() as u32);
+        for (i, phone) in person.phones.iter().enumerate() {
+            let mut entry = phones.reborrow().get(i as u32);
+            entry.set_number(phone.number.as_str());
+            entry.set_type(person::PhoneType::try_from(phone.type_ as u16).unwrap_or(person::PhoneType::Mobile));
+        }
+        let mut addresses = root.reborrow().init_addresses(person.addresses.len() as u32);
+        for (i, address) in person.addresses.iter().enumerate() {
+            let mut entry = addresses.reborrow().get(i as u32);
+            entry.set_street(address.street.as_str());
+            entry.set_city(address.city.as_str());
+            entry.set_state(address.state.as_str());
+            entry.set_zip(address.zip.as_str());
+            entry.set_country(address.country.as_str());
+        }
+        let mut metadata = root.init_metadata(person.metadata.len() as u32);
+        for (i, (key, value)) in person.metadata.iter().enumerate() {
+            let mut entry = metadata.reborrow().get(i as u32);
+            entry.set_key(key.as_str());
+            entry.set_value(value.as_str());
+        }
+        capnp::serialize::write_message_to_words(&message)
+    }
+
+    fn deserialize(&self, bytes: &[u8]) -> JsonPerson {
+        Self::decode(bytes).unwrap()
+    }
+
+    fn access(&self, bytes: &[u8]) {
+        std::hint::black_box(Self::visit(bytes).unwrap());
+    }
+}
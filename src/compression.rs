@@ -0,0 +1,63 @@
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::io::Write;
+
+// A general-purpose compressor applied to an already-serialized payload
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Compressor {
+    None,
+    Gzip,
+    Zstd,
+    Brotli,
+}
+
+impl Compressor {
+    pub const ALL: [Compressor; 4] = [Compressor::None, Compressor::Gzip, Compressor::Zstd, Compressor::Brotli];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Compressor::None => "none",
+            Compressor::Gzip => "gzip",
+            Compressor::Zstd => "zstd",
+            Compressor::Brotli => "brotli",
+        }
+    }
+
+    // Compress at each library's default level
+    pub fn compress(&self, bytes: &[u8]) -> Vec<u8> {
+        match self {
+            Compressor::None => bytes.to_vec(),
+            Compressor::Gzip => {
+                let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(bytes).unwrap();
+                encoder.finish().unwrap()
+            },
+            Compressor::Zstd => zstd::encode_all(bytes, zstd::DEFAULT_COMPRESSION_LEVEL).unwrap(),
+            Compressor::Brotli => {
+                let mut out = Vec::new();
+                {
+                    // Quality 11 and a 4 MiB window (lgwin 22) are brotli's defaults
+                    let mut writer = brotli::CompressorWriter::new(&mut out, 4096, 11, 22);
+                    writer.write_all(bytes).unwrap();
+                }
+                out
+            },
+        }
+    }
+}
+
+// Train a zstd dictionary of at most `max_size` bytes on sample payloads. Small
+// messages compress poorly on their own because each starts with an empty
+// window; a dictionary built from similar messages supplies that context.
+pub fn train_zstd_dictionary(samples: &[Vec<u8>], max_size: usize) -> Vec<u8> {
+    zstd::dict::from_samples(samples, max_size).unwrap()
+}
+
+// A reusable zstd compressor and decompressor primed with a trained dictionary,
+// at zstd's default level
+pub fn zstd_dictionary_codec(dictionary: &[u8]) -> (zstd::bulk::Compressor<'static>, zstd::bulk::Decompressor<'static>) {
+    (
+        zstd::bulk::Compressor::with_dictionary(zstd::DEFAULT_COMPRESSION_LEVEL, dictionary).unwrap(),
+        zstd::bulk::Decompressor::with_dictionary(dictionary).unwrap(),
+    )
+}
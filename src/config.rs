@@ -0,0 +1,60 @@
+use schemars::JsonSchema;
+use serde::Serialize;
+
+// The parameters that determine a run's results
+#[derive(Serialize, JsonSchema, Clone, Debug)]
+pub struct BenchConfig {
+    pub data_size: usize,
+    pub iterations: usize,
+    // Untimed calls before the serialization, deserialization, CPU and throughput loops
+    pub warmup_iterations: usize,
+    pub zstd_level: i32,
+    // Passes over the suite averaged into one result; left out for a single run so
+    // run ids from before --runs stay valid
+    #[serde(skip_serializing_if = "is_single_run")]
+    pub runs: usize,
+    pub auto_iterations: bool,
+    // --normalize, which adds calibrated throughput to the results
+    pub normalize: bool,
+    // --per-test-timeout-secs; tests that run longer are reported as timed out
+    pub per_test_timeout_secs: Option<u64>,
+    pub data_pool: usize,
+    pub null_fraction: f64,
+    pub map_size: usize,
+    pub array_size: usize,
+    // Shuffle seed for --order shuffled
+    pub order_seed: Option<u64>,
+    pub metadata_unique_keys: Option<usize>,
+    // Seed of the randomized synthetic data; None for the fixed record (--seed 0)
+    pub data_seed: Option<u64>,
+    // Seed of the `fake` data generator for --realistic; None for synthetic data
+    pub realistic_seed: Option<u64>,
+    // --charset when not plain ASCII
+    pub charset: Option<String>,
+    pub formats: Vec<String>,
+    // Tokio worker threads, which the latency-under-load test runs on
+    pub worker_threads: usize,
+    // Global allocator, which changes allocation-heavy timings
+    pub allocator: String,
+    // Threads in the parallel throughput variant (1 = single-threaded only)
+    pub producers: usize,
+    pub tag: Option<String>,
+}
+
+fn is_single_run(runs: &usize) -> bool {
+    *runs == 1
+}
+
+// Stable identifier for a configuration: 64-bit FNV-1a over its canonical JSON
+// encoding. Unlike `DefaultHasher` this doesn't change between Rust releases, so
+// identical parameters always produce the same id.
+pub fn run_id(config: &BenchConfig) -> String {
+    const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    let canonical = serde_json::to_vec(config).unwrap();
+    let hash = canonical.iter().fold(FNV_OFFSET_BASIS, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(FNV_PRIME)
+    });
+    format!("{:016x}", hash)
+}
@@ -0,0 +1,200 @@
+use prost_reflect::prost::bytes::Bytes;
+use prost_reflect::prost::Message;
+use prost_reflect::{DescriptorPool, DynamicMessage, EnumDescriptor, FieldDescriptor, Kind, MapKey, MessageDescriptor, Value};
+use std::collections::HashMap;
+use crate::test_data::TestMessage;
+use std::fs;
+
+// Nested messages deeper than this are left unset, which also stops recursive schemas
+const MAX_DEPTH: usize = 4;
+
+// Descriptor set of proto/person.proto, written by build.rs
+const BUILTIN_DESCRIPTOR: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/person_descriptor.bin"));
+
+// Load a message descriptor by its fully-qualified name (e.g. `test.Person`) from a
+// serialized FileDescriptorSet, as written by `protoc --descriptor_set_out --include_imports`
+pub fn load_message_descriptor(path: &str, message: &str) -> Result<MessageDescriptor, String> {
+    let bytes = fs::read(path).map_err(|e| format!("Failed to read descriptor {}: {}", path, e))?;
+    let pool = DescriptorPool::decode(bytes.as_slice())
+        .map_err(|e| format!("Failed to decode descriptor {}: {}", path, e))?;
+    pool.get_message_by_name(message)
+        .ok_or_else(|| format!("Message {} not found in {}", message, path))
+}
+
+// The compiled descriptor of the built-in `test.Person`
+pub fn builtin_person_descriptor() -> MessageDescriptor {
+    DescriptorPool::decode(BUILTIN_DESCRIPTOR)
+        .unwrap()
+        .get_message_by_name("test.Person")
+        .unwrap()
+}
+
+// Render a message and its nested types as .proto source
+pub fn describe_message(desc: &MessageDescriptor) -> String {
+    let mut out = format!("package {};\n\n", desc.package_name());
+    write_message(desc, 0, &mut out);
+    out
+}
+
+fn write_message(desc: &MessageDescriptor, depth: usize, out: &mut String) {
+    let indent = "  ".repeat(depth);
+    out.push_str(&format!("{}message {} {{\n", indent, desc.name()));
+    for field in desc.fields() {
+        let label = if field.is_list() {
+            "repeated "
+        } else if field.field_descriptor_proto().proto3_optional() {
+            "optional "
+        } else {
+            ""
+        };
+        let field_type = match field.kind() {
+            Kind::Message(entry) if field.is_map() => format!("map<{}, {}>",
+                type_name(&entry.map_entry_key_field()), type_name(&entry.map_entry_value_field())),
+            _ => type_name(&field),
+        };
+        out.push_str(&format!("{}  {}{} {} = {};\n", indent, label, field_type, field.name(), field.number()));
+    }
+    for child in desc.child_messages().filter(|child| !child.is_map_entry()) {
+        out.push('\n');
+        write_message(&child, depth + 1, out);
+    }
+    for child in desc.child_enums() {
+        out.push('\n');
+        write_enum(&child, depth + 1, out);
+    }
+    out.push_str(&format!("{}}}\n", indent));
+}
+
+fn write_enum(desc: &EnumDescriptor, depth: usize, out: &mut String) {
+    let indent = "  ".repeat(depth);
+    out.push_str(&format!("{}enum {} {{\n", indent, desc.name()));
+    for value in desc.values() {
+        out.push_str(&format!("{}  {} = {};\n", indent, value.name(), value.number()));
+    }
+    out.push_str(&format!("{}}}\n", indent));
+}
+
+// The .proto spelling of a field's type
+fn type_name(field: &FieldDescriptor) -> String {
+    match field.kind() {
+        Kind::Double => "double".to_string(),
+        Kind::Float => "float".to_string(),
+        Kind::Int32 => "int32".to_string(),
+        Kind::Int64 => "int64".to_string(),
+        Kind::Uint32 => "uint32".to_string(),
+        Kind::Uint64 => "uint64".to_string(),
+        Kind::Sint32 => "sint32".to_string(),
+        Kind::Sint64 => "sint64".to_string(),
+        Kind::Fixed32 => "fixed32".to_string(),
+        Kind::Fixed64 => "fixed64".to_string(),
+        Kind::Sfixed32 => "sfixed32".to_string(),
+        Kind::Sfixed64 => "sfixed64".to_string(),
+        Kind::Bool => "bool".to_string(),
+        Kind::String => "string".to_string(),
+        Kind::Bytes => "bytes".to_string(),
+        Kind::Message(desc) => desc.full_name().to_string(),
+        Kind::Enum(desc) => desc.full_name().to_string(),
+    }
+}
+
+// A message loaded from a descriptor set at runtime; both of its forms are the
+// same `DynamicMessage`, written as JSON with the canonical protobuf JSON mapping
+pub struct RuntimeMessage(pub MessageDescriptor);
+
+impl TestMessage for RuntimeMessage {
+    type Json = DynamicMessage;
+    type Proto = DynamicMessage;
+
+    fn name(&self) -> String {
+        self.0.full_name().to_string()
+    }
+
+    fn generate(&self, size: usize) -> (DynamicMessage, DynamicMessage) {
+        let message = generate_dynamic_message(&self.0, size);
+        (message.clone(), message)
+    }
+
+    fn serialize_json(&self, record: &DynamicMessage) -> String {
+        serde_json::to_string(record).unwrap()
+    }
+
+    fn deserialize_json(&self, json: &str) -> DynamicMessage {
+        let mut deserializer = serde_json::Deserializer::from_str(json);
+        DynamicMessage::deserialize(self.0.clone(), &mut deserializer).unwrap()
+    }
+
+    fn encode(&self, record: &DynamicMessage) -> Vec<u8> {
+        record.encode_to_vec()
+    }
+
+    fn decode(&self, bytes: &[u8]) -> DynamicMessage {
+        DynamicMessage::decode(self.0.clone(), bytes).unwrap()
+    }
+}
+
+// Function to generate a dynamic message with every field populated;
+// repeated and map fields get `size` entries, like `generate_test_data_with`
+pub fn generate_dynamic_message(desc: &MessageDescriptor, size: usize) -> DynamicMessage {
+    generate_message(desc, size, 0)
+}
+
+fn generate_message(desc: &MessageDescriptor, size: usize, depth: usize) -> DynamicMessage {
+    let mut message = DynamicMessage::new(desc.clone());
+
+    for field in desc.fields() {
+        if depth >= MAX_DEPTH && matches!(field.kind(), Kind::Message(_)) {
+            continue;
+        }
+
+        let value = if field.is_map() {
+            let Kind::Message(entry) = field.kind() else { continue };
+            let key_field = entry.map_entry_key_field();
+            let value_field = entry.map_entry_value_field();
+            let map: HashMap<MapKey, Value> = (0..size)
+                .map(|i| (sample_key(&key_field, i), sample_value(&value_field, i, size, depth)))
+                .collect();
+            Value::Map(map)
+        } else if field.is_list() {
+            Value::List((0..size).map(|i| sample_value(&field, i, size, depth)).collect())
+        } else {
+            sample_value(&field, 0, size, depth)
+        };
+
+        message.set_field(&field, value);
+    }
+
+    message
+}
+
+// A plausible value for the `index`-th element of a field
+fn sample_value(field: &FieldDescriptor, index: usize, size: usize, depth: usize) -> Value {
+    match field.kind() {
+        Kind::Double => Value::F64(index as f64 * 1.5),
+        Kind::Float => Value::F32(index as f32 * 1.5),
+        Kind::Int32 | Kind::Sint32 | Kind::Sfixed32 => Value::I32(1000 + index as i32),
+        Kind::Int64 | Kind::Sint64 | Kind::Sfixed64 => Value::I64(1000 + index as i64),
+        Kind::Uint32 | Kind::Fixed32 => Value::U32(1000 + index as u32),
+        Kind::Uint64 | Kind::Fixed64 => Value::U64(1000 + index as u64),
+        Kind::Bool => Value::Bool(index.is_multiple_of(2)),
+        Kind::String => Value::String(format!("{} {}", field.name(), index)),
+        Kind::Bytes => Value::Bytes(Bytes::from(format!("{}-{}", field.name(), index).into_bytes())),
+        Kind::Enum(desc) => {
+            // Cycle through the declared values
+            let values: Vec<i32> = desc.values().map(|v| v.number()).collect();
+            Value::EnumNumber(values[index % values.len()])
+        },
+        Kind::Message(desc) => Value::Message(generate_message(&desc, size, depth + 1)),
+    }
+}
+
+// A map key of the field's key type
+fn sample_key(field: &FieldDescriptor, index: usize) -> MapKey {
+    match field.kind() {
+        Kind::Bool => MapKey::Bool(index.is_multiple_of(2)),
+        Kind::Int32 | Kind::Sint32 | Kind::Sfixed32 => MapKey::I32(index as i32),
+        Kind::Int64 | Kind::Sint64 | Kind::Sfixed64 => MapKey::I64(index as i64),
+        Kind::Uint32 | Kind::Fixed32 => MapKey::U32(index as u32),
+        Kind::Uint64 | Kind::Fixed64 => MapKey::U64(index as u64),
+        _ => MapKey::String(format!("key{}", index)),
+    }
+}
@@ -0,0 +1,112 @@
+#[cfg(feature = "capnp")]
+use crate::codec::Capnp;
+use crate::codec::{Bincode, CanonicalJson, Cbor, Codec, Json5, MessagePack, Postcard, Xml};
+use crate::test_data::{JsonPerson, Person};
+use prost::Message;
+
+// A format that can take part in a run. Any subset can be selected; formats other
+// than JSON and protobuf are codecs added to every results-table test except schema evolution, which
+// needs both JSON and protobuf.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    Protobuf,
+    Postcard,
+    Bincode,
+    MessagePack,
+    Cbor,
+    Xml,
+    CanonicalJson,
+    Json5,
+    // Only in builds with the `capnp` feature
+    Capnp,
+}
+
+impl Format {
+    pub const ALL: [Format; 10] = [
+        Format::Json,
+        Format::Protobuf,
+        Format::Postcard,
+        Format::Bincode,
+        Format::MessagePack,
+        Format::Cbor,
+        Format::Xml,
+        Format::CanonicalJson,
+        Format::Json5,
+        Format::Capnp,
+    ];
+
+    // The name used by --formats and in results
+    pub fn name(&self) -> &'static str {
+        match self {
+            Format::Json => "json",
+            Format::Protobuf => "protobuf",
+            Format::Postcard => "postcard",
+            Format::Bincode => "bincode",
+            Format::MessagePack => "msgpack",
+            Format::Cbor => "cbor",
+            Format::Xml => "xml",
+            Format::CanonicalJson => "canonical-json",
+            Format::Json5 => "json5",
+            Format::Capnp => "capnp",
+        }
+    }
+
+    // The name used in results tables and messages
+    pub fn label(&self) -> &'static str {
+        match self {
+            Format::Json => "JSON",
+            Format::Protobuf => "Protobuf",
+            _ => self.name(),
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Format> {
+        Format::ALL.into_iter().find(|format| format.name() == name)
+    }
+
+    // Encode the record; protobuf writes the prost struct, every other format the
+    // serde one
+    pub fn serialize(&self, json: &JsonPerson, proto: &Person) -> Vec<u8> {
+        match self {
+            Format::Json => serde_json::to_vec(json).unwrap(),
+            Format::Protobuf => proto.encode_to_vec(),
+            _ => self.codec().unwrap().serialize(json),
+        }
+    }
+
+    // Decode bytes written by `serialize`
+    pub fn deserialize(&self, bytes: &[u8]) -> Result<Decoded, String> {
+        match self {
+            Format::Json => serde_json::from_slice(bytes).map(Decoded::Serde).map_err(|e| e.to_string()),
+            Format::Protobuf => Person::decode(bytes).map(Decoded::Protobuf).map_err(|e| e.to_string()),
+            _ => Ok(Decoded::Serde(self.codec().unwrap().deserialize(bytes))),
+        }
+    }
+
+    // The codec behind an additional format; None for JSON and protobuf, which the
+    // tests handle with their own typed paths
+    pub fn codec(&self) -> Option<Box<dyn Codec>> {
+        match self {
+            Format::Json | Format::Protobuf => None,
+            Format::Postcard => Some(Box::new(Postcard)),
+            Format::Bincode => Some(Box::new(Bincode)),
+            Format::MessagePack => Some(Box::new(MessagePack)),
+            Format::Cbor => Some(Box::new(Cbor)),
+            Format::Xml => Some(Box::new(Xml)),
+            Format::CanonicalJson => Some(Box::new(CanonicalJson)),
+            Format::Json5 => Some(Box::new(Json5)),
+            #[cfg(feature = "capnp")]
+            Format::Capnp => Some(Box::new(Capnp)),
+            // Rejected when parsing the command line
+            #[cfg(not(feature = "capnp"))]
+            Format::Capnp => None,
+        }
+    }
+}
+
+// A record decoded by `Format::deserialize`
+pub enum Decoded {
+    Serde(JsonPerson),
+    Protobuf(Person),
+}
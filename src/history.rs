@@ -0,0 +1,52 @@
+use rusqlite::{params, Connection};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const SCHEMA: &str = "CREATE TABLE IF NOT EXISTS results (
+    run_id TEXT NOT NULL,
+    timestamp INTEGER NOT NULL,
+    tag TEXT,
+    test TEXT NOT NULL,
+    format TEXT NOT NULL,
+    value REAL NOT NULL,
+    PRIMARY KEY (run_id, test, format)
+)";
+
+// Store a run's flattened results in an SQLite database, one row per test and
+// format. `test` is the metric name without its format segment, e.g.
+// `serialization.ms` for `serialization.json.ms`. Rows are replaced rather than
+// duplicated when the same run_id is recorded again. Returns the rows written.
+pub fn record_run(path: &str, flat: &BTreeMap<String, Value>, tag: Option<&str>) -> Result<usize, String> {
+    let error = |e: rusqlite::Error| format!("SQLite error in {}: {}", path, e);
+    let run_id = flat.get("run_id").and_then(Value::as_str).unwrap_or_default();
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64;
+
+    let mut conn = Connection::open(path).map_err(error)?;
+    conn.execute(SCHEMA, []).map_err(error)?;
+
+    let tx = conn.transaction().map_err(error)?;
+    let mut rows = 0;
+    {
+        let mut insert = tx.prepare(
+            "INSERT OR REPLACE INTO results (run_id, timestamp, tag, test, format, value)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        ).map_err(error)?;
+
+        for (metric, value) in flat {
+            // Only `<test>.<format>.<unit>` entries; relative differences aren't a format
+            let parts: Vec<&str> = metric.split('.').collect();
+            let (Some(value), [test, format, unit]) = (value.as_f64(), parts.as_slice()) else { continue };
+            if *format == "difference" {
+                continue;
+            }
+
+            let test = format!("{}.{}", test, unit);
+            insert.execute(params![run_id, timestamp, tag, test, format, value]).map_err(error)?;
+            rows += 1;
+        }
+    }
+    tx.commit().map_err(error)?;
+
+    Ok(rows)
+}
@@ -1,10 +1,115 @@
+mod alloc_tracker;
+mod baseline;
+mod cache;
+mod calibration;
 mod test_data;
 mod benchmark;
+mod codec;
+#[cfg(feature = "capnp")]
+capnp::generated_code!(mod person_capnp);
+mod compression;
+mod config;
+mod dynamic;
+mod formats;
+mod history;
+mod server;
+mod stats;
 
-use benchmark::PerformanceTester;
-use clap::{Parser, ArgAction};
+use benchmark::{flatten_results, BaseFormat, BenchmarkResults, PerformanceTester, TestOrder, SCORED_METRICS, WEIGHTED_TESTS};
+use formats::Format;
+use test_data::TextCharset;
+use clap::{Parser, ArgAction, Subcommand, ValueEnum};
 use colored::*;
-use tokio;
+use std::collections::BTreeMap;
+use std::io::{IsTerminal, Write};
+use std::num::NonZeroUsize;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// Track heap usage so tests can report peak memory
+#[cfg(feature = "alloc-tracker")]
+#[global_allocator]
+static GLOBAL: alloc_tracker::CountingAlloc = alloc_tracker::CountingAlloc;
+
+#[cfg(not(feature = "alloc-tracker"))]
+#[global_allocator]
+static GLOBAL: alloc_tracker::Backend = alloc_tracker::Backend;
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum OutputFormat {
+    /// Human-readable table
+    Table,
+    /// Full results as a JSON document
+    Json,
+    /// The results table in Markdown, for pasting into issues and docs
+    Markdown,
+    /// InfluxDB line protocol, one point per metric
+    Influx,
+    /// One JSON object per line, printed as each size/repetition finishes
+    Ndjson,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum RelativeTo {
+    /// Values as multiples of JSON's
+    Json,
+    /// Values as multiples of protobuf's
+    Protobuf,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum RecordsFileFormat {
+    /// One JSON Person object per line
+    Ndjson,
+    /// Length-delimited protobuf Person messages (a varint length before each)
+    Protobuf,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum Charset {
+    /// Plain ASCII text
+    Ascii,
+    /// Accented Latin, Greek, Cyrillic and CJK text
+    Unicode,
+    /// Flags, ZWJ sequences, skin tones and keycaps (multi-codepoint grapheme clusters)
+    Emoji,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum Order {
+    /// The fixed default order
+    Sequential,
+    /// A seeded random order, reported with the results
+    Shuffled,
+}
+
+// Names accepted by --test
+const TESTS: [&str; 40] = [
+    "serialization", "deserialization", "payload", "cpu", "memory", "network", "latency", "init",
+    "throughput", "schema", "optional", "unknown", "stream-compress", "stream-decode", "map", "binary",
+    "fuzz", "patch", "int-map", "sparsity", "sinks", "small-numbers", "transcode", "drop",
+    "double-array", "crossover", "short-keys", "shared", "stream-backpressure", "decode-reuse",
+    "construction", "number-precision", "varint", "dictionary", "money",
+    "checksum", "unicode", "string-ids", "determinism", "real-network",
+];
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Serve results over HTTP: GET /benchmark?size=&iterations= runs the suite and
+    /// returns JSON, GET /metrics exposes the latest run in the Prometheus text format
+    Serve {
+        /// Address to listen on
+        #[arg(long, default_value = "127.0.0.1:8080")]
+        addr: String,
+    },
+    /// Print the change in every metric between two flattened result files
+    /// (--flatten output or baselines), green for improvements and red for regressions
+    Compare {
+        /// Earlier run
+        old: String,
+        /// Later run
+        new: String,
+    },
+}
 
 #[derive(Parser, Debug)]
 #[command(
@@ -14,15 +119,268 @@ use tokio;
     long_about = "A comprehensive benchmark tool that compares JSON and Protocol Buffers across multiple performance dimensions."
 )]
 struct Args {
-    /// Size of the test data (number of elements)
-    #[arg(short, long, default_value_t = 20)]
+    #[command(subcommand)]
+    command: Option<Command>,
+    
+    /// Size of the test data (number of elements; accepts k/M/G suffixes, e.g. 10k)
+    #[arg(short, long, default_value_t = 20, value_parser = parse_count)]
     size: usize,
     
-    /// Number of iterations for each test
-    #[arg(short, long, default_value_t = 1000)]
+    /// Run the full suite at each of these sizes (comma-separated, e.g. 10,100,1k) instead of --size
+    #[arg(long, value_delimiter = ',', value_parser = parse_count, conflicts_with_all = ["baseline", "raw_timings"])]
+    sizes: Vec<usize>,
+    
+    /// Run the full suite this many times and report each metric's mean and its coefficient of variation across runs
+    #[arg(long, default_value = "1")]
+    runs: NonZeroUsize,
+    
+    /// Run the full suite this many times at each size
+    #[arg(long, default_value = "1", conflicts_with_all = ["baseline", "raw_timings"])]
+    repeat: NonZeroUsize,
+    
+    /// Number of iterations for each test (accepts k/M/G suffixes, e.g. 500k, 1M)
+    #[arg(short, long, default_value_t = 1000, value_parser = parse_count)]
     iterations: usize,
     
-    /// Run a specific test only
+    /// zstd compression level for the payload test's zstd sizes
+    #[arg(long, default_value_t = 3, value_parser = clap::value_parser!(i32).range(1..=22))]
+    zstd_level: i32,
+    
+    /// Untimed warmup calls before each serialization, deserialization, CPU and throughput loop [default: 10% of --iterations]
+    #[arg(long, value_parser = parse_count)]
+    warmup: Option<usize>,
+    
+    /// Keep increasing iterations until per-op timings stabilize (overrides --iterations for timing tests)
+    #[arg(long, action = ArgAction::SetTrue)]
+    iterations_auto: bool,
+    
+    /// Formats to compare, any subset of json, protobuf, postcard, bincode, msgpack, cbor, xml, canonical-json, json5 and capnp
+    #[arg(long, value_delimiter = ',', value_parser = parse_format, default_value = "json,protobuf")]
+    formats: Vec<Format>,
+    
+    /// Include postcard in every results-table test except schema evolution (same as adding it to --formats)
+    #[arg(long, action = ArgAction::SetTrue)]
+    with_postcard: bool,
+    
+    /// Include bincode in every results-table test except schema evolution (same as adding it to --formats)
+    #[arg(long, action = ArgAction::SetTrue)]
+    with_bincode: bool,
+    
+    /// Include MessagePack (rmp-serde, named fields) in every results-table test except schema evolution (same as adding msgpack to --formats)
+    #[arg(long, action = ArgAction::SetTrue)]
+    with_msgpack: bool,
+    
+    /// Include CBOR (ciborium) in every results-table test except schema evolution (same as adding cbor to --formats)
+    #[arg(long, action = ArgAction::SetTrue)]
+    with_cbor: bool,
+    
+    /// Include XML (quick-xml) in every results-table test except schema evolution (same as adding xml to --formats)
+    #[arg(long, action = ArgAction::SetTrue)]
+    with_xml: bool,
+    
+    /// Include canonical JSON (sorted keys, no whitespace) in every results-table test except schema evolution (same as adding canonical-json to --formats)
+    #[arg(long, action = ArgAction::SetTrue)]
+    with_canonical_json: bool,
+    
+    /// Include JSON5 (comments, trailing commas; parsed with the json5 crate) in every results-table test except schema evolution (same as adding json5 to --formats)
+    #[arg(long, action = ArgAction::SetTrue)]
+    with_json5: bool,
+    
+    /// Include Cap'n Proto (proto/person.capnp, read in place by the deserialization test) in every results-table test except schema evolution (same as adding capnp to --formats; needs a build with the `capnp` feature)
+    #[arg(long, action = ArgAction::SetTrue)]
+    with_capnp: bool,
+    
+    /// Rotate through distinct pre-generated records in timing loops instead of reusing one
+    #[arg(long, action = ArgAction::SetTrue)]
+    fresh_data_per_iter: bool,
+    
+    /// Number of records pre-generated for --fresh-data-per-iter
+    #[arg(long, default_value_t = 64)]
+    data_pool: usize,
+    
+    /// Fraction of optional fields left absent in the optional-fields test (0.0 - 1.0)
+    #[arg(long, default_value_t = 0.5)]
+    null_fraction: f64,
+    
+    /// Number of entries in the map-only test
+    #[arg(long, default_value_t = 1000)]
+    map_size: usize,
+    
+    /// Number of values in the numeric-array test
+    #[arg(long, default_value_t = 10000)]
+    array_size: usize,
+    
+    /// Output format for the full benchmark results
+    #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+    format: OutputFormat,
+    
+    /// Show table and Markdown values as multiples of this format (JSON/flattened output keeps raw values)
+    #[arg(long, value_enum)]
+    relative_to: Option<RelativeTo>,
+    
+    /// Print the JSON Schema of the --format json output and exit
+    #[arg(long, action = ArgAction::SetTrue)]
+    print_output_schema: bool,
+    
+    /// Print the protobuf definition and the JSON structure being benchmarked and exit
+    #[arg(long, action = ArgAction::SetTrue)]
+    show_schema: bool,
+    
+    /// Print the size of one record for every format under every compressor (none, gzip, zstd, brotli) and exit
+    #[arg(long, action = ArgAction::SetTrue)]
+    compare_compression: bool,
+    
+    /// Run the per-op tests at a tenth of --iterations and at the full count, print how much per-op time moved, and exit
+    #[arg(long, action = ArgAction::SetTrue, conflicts_with = "iterations_auto")]
+    compare_iterations: bool,
+    
+    /// Percentage per-op time may move between the two --compare-iterations counts before a test is flagged
+    #[arg(long, default_value_t = 25.0)]
+    max_drift: f64,
+    
+    /// Print one line per test with the winner and speedup factor instead of the full table
+    #[arg(long, action = ArgAction::SetTrue)]
+    only_winners: bool,
+    
+    /// Print the crate, dependency and compiler versions this binary was built with and exit
+    #[arg(long, action = ArgAction::SetTrue)]
+    version_info: bool,
+    
+    /// Print results as a flat JSON object with dotted metric names instead of the table
+    #[arg(long, action = ArgAction::SetTrue)]
+    flatten: bool,
+    
+    /// Check round-trip correctness of each test's data after it runs
+    #[arg(long, action = ArgAction::SetTrue)]
+    verify: bool,
+    
+    /// With --verify, stop at the first failing test instead of reporting all failures at the end
+    #[arg(long, action = ArgAction::SetTrue, requires = "verify")]
+    fail_fast: bool,
+    
+    /// Benchmark a message from a FileDescriptorSet (protoc --descriptor_set_out) instead of Person
+    #[arg(long, requires = "message")]
+    descriptor: Option<String>,
+    
+    /// Benchmark records from a newline-delimited JSON file of Person objects instead of generated data
+    #[arg(long, value_name = "PATH", conflicts_with = "descriptor")]
+    input: Option<String>,
+    
+    /// Format of the --input file
+    #[arg(long, value_enum, default_value_t = RecordsFileFormat::Ndjson, requires = "input")]
+    records_file_format: RecordsFileFormat,
+    
+    /// Fully-qualified message name to benchmark from --descriptor (e.g. test.Person)
+    #[arg(long, requires = "descriptor")]
+    message: Option<String>,
+    
+    /// Draw metadata keys from this many distinct key stems (controls key redundancy)
+    #[arg(long)]
+    metadata_unique_keys: Option<usize>,
+    
+    /// Use realistic names, emails, phone numbers and addresses (generated with `fake`) instead of synthetic text
+    #[arg(long, action = ArgAction::SetTrue)]
+    realistic: bool,
+    
+    /// Characters mixed into generated names, streets, cities and metadata values
+    #[arg(long, value_enum, default_value_t = Charset::Ascii)]
+    charset: Charset,
+    
+    /// Seed for randomized test data (ids, names, emails, phones, metadata values, and --realistic records); 0 keeps the fixed synthetic record
+    #[arg(long, default_value_t = 42)]
+    seed: u64,
+    
+    /// Write every iteration's duration from the per-iteration tests (serialization, deserialization) to this CSV file
+    #[arg(long, value_name = "PATH")]
+    raw_timings: Option<String>,
+    
+    /// Reuse results stored in this directory for an identical configuration (same run ID), and store new ones there
+    #[arg(long, value_name = "DIR")]
+    cache: Option<String>,
+    
+    /// With --cache, always run the suite (the fresh results still replace the cached entry)
+    #[arg(long, action = ArgAction::SetTrue, requires = "cache")]
+    no_cache: bool,
+    
+    /// Free-form label included in the run configuration and its run ID
+    #[arg(long)]
+    tag: Option<String>,
+    
+    /// Abandon any test in the full run that takes longer than this and report it as TIMEOUT
+    #[arg(long)]
+    per_test_timeout_secs: Option<u64>,
+    
+    /// Disable colored output and the sparkline column (both are also off when stdout isn't a terminal)
+    #[arg(long, action = ArgAction::SetTrue)]
+    no_color: bool,
+    
+    /// Print the results table with one row per format and one column per metric (easier to read with many formats)
+    #[arg(long, action = ArgAction::SetTrue)]
+    transpose: bool,
+    
+    /// Wrap results table cells wider than this many characters
+    #[arg(long, value_name = "CHARS")]
+    column_width: Option<NonZeroUsize>,
+    
+    /// Write the sample record's JSON to <PREFIX>.json and protobuf to <PREFIX>.pb before running
+    #[arg(long, value_name = "PREFIX")]
+    dump_bytes: Option<String>,
+    
+    /// Order of the tests in the full run
+    #[arg(long, value_enum, default_value_t = Order::Sequential)]
+    order: Order,
+    
+    /// Seed for --order shuffled (random if omitted; the seed used is printed so the order can be reproduced)
+    #[arg(long)]
+    order_seed: Option<u64>,
+    
+    /// Compare the full run against a baseline file and exit non-zero on regressions
+    #[arg(long, value_name = "PATH")]
+    baseline: Option<String>,
+    
+    /// Percentage a metric may get worse than the baseline before it counts as a regression
+    #[arg(long, default_value_t = 10.0)]
+    max_regression: f64,
+    
+    /// Overwrite the --baseline file with this run's results instead of comparing
+    #[arg(long, action = ArgAction::SetTrue, requires = "baseline")]
+    update_baseline: bool,
+    
+    /// Don't ask before overwriting an existing baseline
+    #[arg(long, action = ArgAction::SetTrue)]
+    force: bool,
+    
+    /// Add JSON and protobuf peak heap columns for serialization and deserialization to the table (implied by --verbose)
+    #[arg(long, action = ArgAction::SetTrue)]
+    show_memory: bool,
+    
+    /// Record the full run's results in this SQLite database (created if missing)
+    #[arg(long, value_name = "PATH")]
+    sqlite: Option<String>,
+    
+    /// Weight a test in the overall winner, e.g. serialization=3,init=0 (unlisted tests weigh 1).
+    /// Tests: serialization, deserialization, payload, compressed, cpu, memory, network, latency, init, throughput, schema
+    #[arg(long, value_name = "TEST=WEIGHT", value_delimiter = ',', value_parser = parse_weight)]
+    weight: Vec<(String, f64)>,
+    
+    /// Weight a metric in the 0-100 efficiency score, e.g. payload=3 for bandwidth-bound
+    /// use (unlisted metrics weigh 1). Metrics: serialization, deserialization, payload
+    #[arg(long, value_name = "METRIC=WEIGHT", value_delimiter = ',', value_parser = parse_score_weight)]
+    score_weight: Vec<(String, f64)>,
+    
+    /// Worker threads for the tokio runtime (default: one per CPU core)
+    #[arg(long)]
+    threads: Option<NonZeroUsize>,
+    
+    /// Also report throughput normalized by a calibration workload, comparable across machines
+    #[arg(long, action = ArgAction::SetTrue)]
+    normalize: bool,
+    
+    /// Also run the throughput test on this many threads at once and report aggregate ops/s
+    #[arg(long, default_value = "1")]
+    producers: NonZeroUsize,
+    
+    /// Run a specific test only, or every test matching a glob pattern (e.g. 'ser*', '*load*')
     #[arg(short, long)]
     test: Option<String>,
     
@@ -31,105 +389,1037 @@ struct Args {
     verbose: bool,
 }
 
+// Parse a `test=weight` pair for --weight
+fn parse_weight(value: &str) -> Result<(String, f64), String> {
+    parse_named_weight(value, &WEIGHTED_TESTS)
+}
+
+// Parse a `metric=weight` pair for --score-weight
+fn parse_score_weight(value: &str) -> Result<(String, f64), String> {
+    parse_named_weight(value, &SCORED_METRICS)
+}
+
+fn parse_named_weight(value: &str, names: &[&str]) -> Result<(String, f64), String> {
+    let (test, weight) = value.split_once('=')
+        .ok_or_else(|| format!("invalid weight '{}' (expected e.g. serialization=3)", value))?;
+    if !names.contains(&test) {
+        return Err(format!("unknown test '{}' (expected one of {})", test, names.join(", ")));
+    }
+    match weight.parse::<f64>() {
+        Ok(weight) if weight.is_finite() && weight >= 0.0 => Ok((test.to_string(), weight)),
+        _ => Err(format!("invalid weight '{}' for {} (expected a non-negative number)", weight, test)),
+    }
+}
+
+// Parse one entry of --formats by its name
+fn parse_format(value: &str) -> Result<Format, String> {
+    Format::from_name(value.trim()).ok_or_else(|| {
+        let names: Vec<&str> = Format::ALL.iter().map(|format| format.name()).collect();
+        format!("unknown format '{}' (expected one of {})", value, names.join(", "))
+    })
+}
+
+// Parse a count with an optional decimal suffix: 500k, 1M, 1.5M, 2G
+fn parse_count(value: &str) -> Result<usize, String> {
+    let invalid = || format!("invalid count '{}' (expected e.g. 1000, 500k, 1M)", value);
+    
+    let digits = value.trim().replace('_', "");
+    let (number, multiplier) = match digits.char_indices().last() {
+        Some((i, 'k' | 'K')) => (&digits[..i], 1_000),
+        Some((i, 'm' | 'M')) => (&digits[..i], 1_000_000),
+        Some((i, 'g' | 'G')) => (&digits[..i], 1_000_000_000),
+        _ => (digits.as_str(), 1),
+    };
+    
+    // Whole and fractional parts are scaled separately so 1.1k is exactly 1100
+    let (whole, fraction) = number.split_once('.').unwrap_or((number, ""));
+    let all_digits = |part: &str| part.chars().all(|c| c.is_ascii_digit());
+    if whole.is_empty() || !all_digits(whole) || !all_digits(fraction) || fraction.len() > 9 {
+        return Err(invalid());
+    }
+    
+    let scale = 10usize.pow(fraction.len() as u32);
+    let fraction_value: usize = if fraction.is_empty() { 0 } else { fraction.parse().map_err(|_| invalid())? };
+    if !(fraction_value * multiplier).is_multiple_of(scale) {
+        return Err(format!("'{}' is not a whole number", value));
+    }
+    
+    whole.parse::<usize>().ok()
+        .and_then(|whole| whole.checked_mul(multiplier))
+        .and_then(|count| count.checked_add(fraction_value * multiplier / scale))
+        .ok_or_else(invalid)
+}
+
 // Entry point of the application - regular main function
 fn main() {
-    // Use tokio runtime without the macro
-    let rt = tokio::runtime::Runtime::new().unwrap();
-    rt.block_on(async_main());
+    // Parse command line arguments
+    let args = Args::parse();
+    
+    // Use tokio runtime without the macro; tokio defaults to one worker per core
+    let worker_threads = args.threads.map_or_else(num_cpus::get, NonZeroUsize::get);
+    let rt = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(worker_threads)
+        .enable_all()
+        .build()
+        .unwrap();
+    rt.block_on(async_main(args, worker_threads));
+    // Don't wait for timed-out tests still running on blocking threads
+    rt.shutdown_background();
 }
 
 // Async main function that will be run inside the tokio runtime
-async fn async_main() {
-    // Parse command line arguments
-    let args = Args::parse();
+async fn async_main(args: Args, worker_threads: usize) {
+    // Keep piped output free of escape codes and block characters
+    let styled = !args.no_color && std::io::stdout().is_terminal();
+    if !styled {
+        colored::control::set_override(false);
+    }
+    
+    if args.version_info {
+        print_version_info();
+        return;
+    }
     
-    println!("{}", "JSON vs Protocol Buffers Benchmark".green().bold());
-    println!("=====================================");
-    println!("Data size: {}", args.size);
-    println!("Iterations: {}", args.iterations);
-    println!();
+    if args.show_schema {
+        show_schema(&args);
+        return;
+    }
+    
+    if let Some(Command::Compare { old, new }) = &args.command {
+        compare_runs(old, new);
+        return;
+    }
+    
+    if args.print_output_schema {
+        let schema = schemars::schema_for!(BenchmarkResults);
+        println!("{}", serde_json::to_string_pretty(&schema).unwrap());
+        return;
+    }
+    
+    eprintln!("{}", "JSON vs Protocol Buffers Benchmark".green().bold());
+    eprintln!("=====================================");
+    if args.sizes.is_empty() {
+        eprintln!("Data size: {}", args.size);
+    } else {
+        eprintln!("Data sizes: {}", args.sizes.iter().map(|size| size.to_string()).collect::<Vec<_>>().join(", "));
+    }
+    if args.repeat.get() > 1 {
+        eprintln!("Repetitions: {}", args.repeat);
+    }
+    if args.iterations_auto {
+        eprintln!("Iterations: auto (adaptive)");
+    } else {
+        eprintln!("Iterations: {}", args.iterations);
+    }
+    eprintln!("Worker threads: {}", worker_threads);
+    eprintln!("Allocator: {}", alloc_tracker::ALLOCATOR);
+    if args.show_memory && !alloc_tracker::TRACKING {
+        eprintln!("{}", "--show-memory needs the `alloc-tracker` feature (cargo run --features alloc-tracker); the peak columns stay blank".yellow());
+    }
+    eprintln!();
     
     // Create a tester instance
-    let mut tester = PerformanceTester::new(args.size, args.iterations);
-    
-    // If a specific test is requested, run only that test
-    if let Some(test_name) = args.test {
-        match test_name.as_str() {
-            "serialization" => {
-                let result = tester.test_serialization_speed();
-                println!("JSON: {:.4} ms", result.json);
-                println!("Protobuf: {:.4} ms", result.protobuf);
-                println!("Winner: {}", result.winner);
-            },
-            "deserialization" => {
-                let result = tester.test_deserialization_speed();
-                println!("JSON: {:.4} ms", result.json);
-                println!("Protobuf: {:.4} ms", result.protobuf);
-                println!("Winner: {}", result.winner);
-            },
-            "payload" => {
-                let result = tester.test_payload_size();
-                println!("JSON uncompressed: {} bytes", result.uncompressed.json);
-                println!("Protobuf uncompressed: {} bytes", result.uncompressed.protobuf);
-                println!("JSON compressed: {} bytes", result.compressed.json);
-                println!("Protobuf compressed: {} bytes", result.compressed.protobuf);
-                println!("Uncompressed winner: {}", result.uncompressed.winner);
-                println!("Compressed winner: {}", result.compressed.winner);
-            },
-            "cpu" => {
-                let result = tester.test_cpu_usage();
-                println!("JSON: {:.2} ms", result.json);
-                println!("Protobuf: {:.2} ms", result.protobuf);
-                println!("Winner: {}", result.winner);
-            },
-            "memory" => {
-                let result = tester.test_memory_usage();
-                println!("JSON: {:.2} ms", result.json);
-                println!("Protobuf: {:.2} ms", result.protobuf);
-                println!("Winner: {}", result.winner);
-            },
-            "network" => {
-                let result = tester.test_network_transfer().await;
-                println!("JSON: {:.2} ms", result.json);
-                println!("Protobuf: {:.2} ms", result.protobuf);
-                println!("Winner: {}", result.winner);
-            },
-            "latency" => {
-                let result = tester.test_latency_under_load().await;
-                println!("JSON: {:.2} ms", result.json);
-                println!("Protobuf: {:.2} ms", result.protobuf);
-                println!("Winner: {}", result.winner);
-            },
-            "init" => {
-                let result = tester.test_parser_initialization();
-                println!("JSON: {:.2} ms", result.json);
-                println!("Protobuf: {:.2} ms", result.protobuf);
-                println!("Winner: {}", result.winner);
-            },
-            "throughput" => {
-                let result = tester.test_throughput();
-                println!("JSON: {:.2} ops/s", result.json);
-                println!("Protobuf: {:.2} ops/s", result.protobuf);
-                println!("Winner: {}", result.winner);
-            },
-            "schema" => {
-                let result = tester.test_schema_evolution();
-                println!("JSON: {:.4} ms", result.json);
-                println!("Protobuf backwards: {:.4} ms", result.protobuf_backwards);
-                println!("Protobuf forwards: {:.4} ms", result.protobuf_forwards);
-                println!("Protobuf average: {:.4} ms", result.protobuf_average);
-                println!("Winner: {}", result.winner);
-            },
-            _ => {
-                println!("Unknown test: {}", test_name);
-                println!("Available tests: serialization, deserialization, payload, cpu, memory, network, latency, init, throughput, schema");
+    let mut tester = PerformanceTester::new(args.size, args.iterations).with_verbose(args.verbose)
+        .with_auto_iterations(args.iterations_auto);
+    if let Some(warmup) = args.warmup {
+        tester = tester.with_warmup(warmup);
+    }
+    tester = tester.with_zstd_level(args.zstd_level)
+        .with_runs(args.runs.get())
+        .with_null_fraction(args.null_fraction)
+        .with_map_size(args.map_size)
+        .with_array_size(args.array_size)
+        .with_verify(args.verify, args.fail_fast)
+        .with_metadata_unique_keys(args.metadata_unique_keys)
+        .with_seed((args.seed != 0).then_some(args.seed))
+        .with_realistic(args.realistic.then_some(args.seed))
+        .with_charset(match args.charset {
+            Charset::Ascii => TextCharset::Ascii,
+            Charset::Unicode => TextCharset::Unicode,
+            Charset::Emoji => TextCharset::Emoji,
+        })
+        .with_tag(args.tag.clone())
+        .with_per_test_timeout(args.per_test_timeout_secs.map(Duration::from_secs))
+        .with_sparklines(styled)
+        .with_transpose(args.transpose)
+        .with_column_width(args.column_width.map(NonZeroUsize::get))
+        .with_show_memory(args.show_memory)
+        .with_worker_threads(worker_threads)
+        .with_normalize(args.normalize)
+        .with_producers(args.producers.get())
+        .with_weights(args.weight.iter().cloned().collect())
+        .with_score_weights(args.score_weight.iter().cloned().collect())
+        .with_relative_to(args.relative_to.map(|base| match base {
+            RelativeTo::Json => BaseFormat::Json,
+            RelativeTo::Protobuf => BaseFormat::Protobuf,
+        }));
+    if args.order == Order::Shuffled {
+        tester = tester.with_order(TestOrder::Shuffled(args.order_seed.unwrap_or_else(rand::random)));
+    }
+    if args.fresh_data_per_iter {
+        tester = tester.with_fresh_data(args.data_pool);
+    }
+    let mut formats = args.formats.clone();
+    for (selected, format) in [(args.with_postcard, Format::Postcard), (args.with_bincode, Format::Bincode),
+                               (args.with_msgpack, Format::MessagePack), (args.with_cbor, Format::Cbor),
+                               (args.with_xml, Format::Xml), (args.with_canonical_json, Format::CanonicalJson),
+                               (args.with_json5, Format::Json5), (args.with_capnp, Format::Capnp)] {
+        if selected {
+            formats.push(format);
+        }
+    }
+    if formats.contains(&Format::Capnp) && !cfg!(feature = "capnp") {
+        eprintln!("{}", "capnp needs a build with the `capnp` feature (cargo run --features capnp), which runs the capnp schema compiler".red());
+        std::process::exit(1);
+    }
+    tester = tester.with_formats(&formats);
+    
+    // The results-table tests run any subset of formats; these modes compare JSON
+    // with protobuf directly, so they need both
+    let both = formats.contains(&Format::Json) && formats.contains(&Format::Protobuf);
+    let direct = [(args.dump_bytes.is_some(), "--dump-bytes"), (args.descriptor.is_some(), "--descriptor"),
+                  (args.input.is_some(), "--input"), (args.compare_iterations, "--compare-iterations")];
+    if let Some((_, option)) = direct.iter().find(|(used, _)| *used && !both) {
+        eprintln!("{}", format!("{} compares JSON with protobuf, so --formats must include both", option).red());
+        std::process::exit(1);
+    }
+    if let Some(base) = args.relative_to {
+        let format = match base {
+            RelativeTo::Json => Format::Json,
+            RelativeTo::Protobuf => Format::Protobuf,
+        };
+        if !formats.contains(&format) {
+            eprintln!("{}", format!("--relative-to {} needs {} in --formats", format.name(), format.name()).red());
+            std::process::exit(1);
+        }
+    }
+    
+    if let Some(Command::Serve { addr }) = &args.command {
+        if let Err(e) = server::serve(addr, tester).await {
+            eprintln!("{}", e.red());
+            std::process::exit(1);
+        }
+        return;
+    }
+    
+    if let Some(prefix) = &args.dump_bytes {
+        match tester.dump_bytes(prefix) {
+            Ok((json_path, proto_path)) => eprintln!("Wrote {} and {}\n", json_path, proto_path),
+            Err(e) => {
+                eprintln!("{}", format!("Failed to write {}.json/.pb: {}", prefix, e).red());
+                std::process::exit(1);
+            }
+        }
+    }
+    
+    // A runtime-loaded schema replaces the built-in Person benchmarks
+    if let (Some(descriptor), Some(message)) = (&args.descriptor, &args.message) {
+        let desc = match dynamic::load_message_descriptor(descriptor, message) {
+            Ok(desc) => desc,
+            Err(e) => {
+                eprintln!("{}", e.red());
+                std::process::exit(1);
+            }
+        };
+        let result = tester.test_message(&dynamic::RuntimeMessage(desc));
+        println!("Message: {}", result.message);
+        println!("JSON serialization: {:.4} ms", result.serialization.json);
+        println!("Protobuf serialization: {:.4} ms", result.serialization.protobuf);
+        println!("JSON deserialization: {:.4} ms", result.deserialization.json);
+        println!("Protobuf deserialization: {:.4} ms", result.deserialization.protobuf);
+        println!("JSON size: {} bytes", result.payload_size.json);
+        println!("Protobuf size: {} bytes", result.payload_size.protobuf);
+        return;
+    }
+    
+    // Real protobuf files are decoded as they are on disk
+    if let (Some(path), RecordsFileFormat::Protobuf) = (&args.input, args.records_file_format) {
+        let bytes = match std::fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                eprintln!("{}", format!("Failed to open {}: {}", path, e).red());
+                std::process::exit(1);
+            }
+        };
+        let records = match test_data::decode_length_delimited(path, &bytes) {
+            Ok(records) if !records.is_empty() => records,
+            Ok(_) => {
+                eprintln!("{}", format!("No records in {}", path).red());
+                std::process::exit(1);
+            }
+            Err(e) => {
+                eprintln!("{}", e.red());
+                std::process::exit(1);
+            }
+        };
+        let result = tester.test_records_file(&bytes, &records);
+        let per_record = |total: f64| total / result.records as f64;
+        println!("Records: {}", result.records);
+        println!("NDJSON encode: {:.4} ms total, {:.6} ms/record", result.encode.json, per_record(result.encode.json));
+        println!("Protobuf encode: {:.4} ms total, {:.6} ms/record", result.encode.protobuf, per_record(result.encode.protobuf));
+        println!("NDJSON decode: {:.4} ms total, {:.6} ms/record", result.decode.json, per_record(result.decode.json));
+        println!("Protobuf decode: {:.4} ms total, {:.6} ms/record", result.decode.protobuf, per_record(result.decode.protobuf));
+        println!("NDJSON file: {} bytes, {:.1} bytes/record", result.file_size.json, per_record(result.file_size.json));
+        println!("Protobuf file: {} bytes, {:.1} bytes/record", result.file_size.protobuf, per_record(result.file_size.protobuf));
+        println!("Decode winner: {}", result.decode.winner);
+        return;
+    }
+    
+    // Recorded production data also replaces the generated records
+    if let Some(path) = &args.input {
+        let records = match test_data::load_ndjson(path) {
+            Ok(records) if !records.is_empty() => records,
+            Ok(_) => {
+                eprintln!("{}", format!("No records in {}", path).red());
+                std::process::exit(1);
+            }
+            Err(e) => {
+                eprintln!("{}", e.red());
+                std::process::exit(1);
+            }
+        };
+        let result = tester.test_input_batch(&records);
+        let per_record = |total: f64| total / result.records as f64;
+        println!("Records: {}", result.records);
+        println!("JSON serialization: {:.4} ms total, {:.6} ms/record", result.serialization.json, per_record(result.serialization.json));
+        println!("Protobuf serialization: {:.4} ms total, {:.6} ms/record", result.serialization.protobuf, per_record(result.serialization.protobuf));
+        println!("JSON deserialization: {:.4} ms total, {:.6} ms/record", result.deserialization.json, per_record(result.deserialization.json));
+        println!("Protobuf deserialization: {:.4} ms total, {:.6} ms/record", result.deserialization.protobuf, per_record(result.deserialization.protobuf));
+        println!("JSON size: {} bytes total, {:.1} bytes/record", result.payload_size.json, per_record(result.payload_size.json));
+        println!("Protobuf size: {} bytes total, {:.1} bytes/record", result.payload_size.protobuf, per_record(result.payload_size.protobuf));
+        return;
+    }
+    
+    if args.compare_compression {
+        let matrix = tester.test_compression_matrix();
+        let best = matrix.rows.iter()
+            .flat_map(|(format, sizes)| sizes.iter().zip(&matrix.compressors).map(move |(size, compressor)| (size, format, compressor)))
+            .min();
+        if let Some((size, format, compressor)) = best {
+            println!("Smallest: {} + {} ({} bytes)", format, compressor, size);
+        }
+        return;
+    }
+    
+    if args.compare_iterations {
+        let rows = tester.compare_iterations(args.max_drift);
+        println!();
+        for row in &rows {
+            let flag = if (row.ratio - 1.0) * 100.0 > args.max_drift { "UNSTABLE" } else { "ok" };
+            println!("{:<16} JSON {:.6} -> {:.6} ms/op, Protobuf {:.6} -> {:.6} ms/op, ratio {:.2} {}",
+                    row.test, row.json.0, row.json.1, row.protobuf.0, row.protobuf.1, row.ratio, flag);
+        }
+        return;
+    }
+    
+    // If a specific test (or a glob pattern of tests) is requested, run only those
+    if let Some(pattern) = args.test {
+        let tests = match resolve_tests(&pattern) {
+            Ok(tests) => tests,
+            Err(e) => {
+                eprintln!("{}", e.red());
+                std::process::exit(1);
             }
+        };
+        for test_name in tests {
+            // Past the first nine (the results-table tests), every test compares JSON with protobuf
+            if !both && TESTS[9..].contains(&test_name) {
+                eprintln!("{}", format!("Skipping {}: it compares JSON with protobuf, and --formats leaves one out", test_name).yellow());
+                continue;
+            }
+            run_test(&tester, test_name).await;
+        }
+        
+        if let Some(path) = &args.raw_timings {
+            write_raw_timings(&tester, path);
         }
     } else {
-        // Run all tests and print results
-        let _results = tester.run_all_tests().await;
+        // Run the full suite once per size and repetition
+        let sizes = if args.sizes.is_empty() { vec![args.size] } else { args.sizes.clone() };
+        let configurations = sizes.len() * args.repeat.get();
+        let mut failures = Vec::new();
+        for &size in &sizes {
+            for repetition in 1..=args.repeat.get() {
+                let mut tester = tester.clone().with_size(size);
+                // Headers only separate human-readable tables; JSON-based output stays parseable
+                if configurations > 1 && matches!(args.format, OutputFormat::Table | OutputFormat::Markdown) && !args.flatten {
+                    println!("\n{}", format!("=== Size {}, run {} of {} ===", size, repetition, args.repeat).bold());
+                }
+                run_configuration(&args, &mut tester, size, repetition).await;
+                failures.extend(tester.verification_failures().iter().cloned());
+            }
+        }
         
+        // Without --fail-fast, report every failure once the suite has finished
+        if !failures.is_empty() {
+            eprintln!("\n{}", format!("{} verification failure(s):", failures.len()).red().bold());
+            for failure in failures {
+                eprintln!("  {}", failure);
+            }
+            std::process::exit(1);
+        }
+    }
+}
+
+// Run all tests at one size and print or record the results
+async fn run_configuration(args: &Args, tester: &mut PerformanceTester, size: usize, repetition: usize) {
+    // A cache hit has no per-iteration samples, so --raw-timings always runs the suite
+    let run_id = config::run_id(&tester.config());
+    let cached = match &args.cache {
+        Some(dir) if !args.no_cache && args.raw_timings.is_none() => match cache::load(dir, &run_id) {
+            Ok(cached) => cached,
+            Err(e) => {
+                eprintln!("{}", format!("Ignoring cache entry: {}", e).yellow());
+                None
+            }
+        },
+        _ => None,
+    };
+    
+    let results = if let Some(cached) = cached {
+        eprintln!("{}", format!("Using cached results for run {} (--no-cache to rerun)", run_id).yellow());
+        tester.restore_results(cached)
+    } else {
+        if let Err(e) = tester.run_all_tests().await {
+            eprintln!("{}", format!("Verification failed: {}", e).red().bold());
+            std::process::exit(1);
+        }
+        let results = tester.results().unwrap();
+        // Runs with verification failures aren't worth reusing
+        if let Some(dir) = args.cache.as_ref().filter(|_| tester.verification_failures().is_empty()) {
+            match cache::store(dir, results) {
+                Ok(path) => eprintln!("Cached results in {}", path.display()),
+                Err(e) => eprintln!("{}", e.yellow()),
+            }
+        }
+        results
+    };
+    
+    let flat = flatten_results(results);
+    if args.flatten {
+        // One line of flat JSON, e.g. {"serialization.json.ms": 0.12, ...}
+        println!("{}", serde_json::to_string(&flat).unwrap());
+    } else if args.format == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&tester.results_as_json()).unwrap());
+    } else if args.format == OutputFormat::Ndjson {
+        // One line per configuration; stdout is line-buffered, so each line
+        // reaches the consumer as soon as its configuration finishes
+        let mut line = tester.results_as_json();
+        let object = line.as_object_mut().unwrap();
+        object.insert("size".to_string(), size.into());
+        object.insert("repetition".to_string(), repetition.into());
+        println!("{}", line);
+    } else if args.format == OutputFormat::Markdown {
+        print!("{}", tester.results_to_markdown());
+    } else if args.format == OutputFormat::Influx {
+        let timestamp_ns = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos() as u64;
+        print!("{}", tester.results_to_influx(timestamp_ns));
+    } else if args.only_winners {
+        tester.print_winners();
+    } else {
         // Print table of results
         tester.print_results();
     }
-}
\ No newline at end of file
+    
+    if let Some(path) = &args.raw_timings {
+        write_raw_timings(tester, path);
+    }
+    
+    if let Some(path) = &args.sqlite {
+        match history::record_run(path, &flat, args.tag.as_deref()) {
+            Ok(rows) => eprintln!("Recorded {} results in {}", rows, path),
+            Err(e) => {
+                eprintln!("{}", e.red());
+                std::process::exit(1);
+            }
+        }
+    }
+    
+    if let Some(path) = &args.baseline {
+        if args.update_baseline {
+            update_baseline(path, &flat, args.force);
+        } else {
+            check_baseline(path, &flat, args.max_regression);
+        }
+    }
+}
+
+// Versions captured by build.rs, for bug reports
+fn print_version_info() {
+    println!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
+    println!("prost {}", env!("BUILD_PROST_VERSION"));
+    println!("serde_json {}", env!("BUILD_SERDE_JSON_VERSION"));
+    println!("flate2 {}", env!("BUILD_FLATE2_VERSION"));
+    println!("{}", env!("BUILD_RUSTC_VERSION"));
+}
+
+// Print the message definition from its compiled descriptor and the JSON it is
+// compared against: the serde structs' JSON Schema for the built-in Person, or an
+// example document in the canonical protobuf JSON mapping for --descriptor
+fn show_schema(args: &Args) {
+    if let (Some(descriptor), Some(message)) = (&args.descriptor, &args.message) {
+        let desc = match dynamic::load_message_descriptor(descriptor, message) {
+            Ok(desc) => desc,
+            Err(e) => {
+                eprintln!("{}", e.red());
+                std::process::exit(1);
+            }
+        };
+        println!("{}", "Protobuf".blue().bold());
+        print!("{}", dynamic::describe_message(&desc));
+        println!("\n{}", "JSON (example, one entry per repeated or map field)".blue().bold());
+        println!("{}", serde_json::to_string_pretty(&dynamic::generate_dynamic_message(&desc, 1)).unwrap());
+    } else {
+        println!("{}", "Protobuf".blue().bold());
+        print!("{}", dynamic::describe_message(&dynamic::builtin_person_descriptor()));
+        println!("\n{}", "JSON (JSON Schema of the serde structs)".blue().bold());
+        println!("{}", serde_json::to_string_pretty(&schemars::schema_for!(test_data::JsonPerson)).unwrap());
+    }
+}
+
+// Overwrite the baseline with this run, asking first if it already exists
+fn update_baseline(path: &str, flat: &BTreeMap<String, serde_json::Value>, force: bool) {
+    if !force && std::path::Path::new(path).exists() {
+        if !std::io::stdin().is_terminal() {
+            eprintln!("{}", format!("{} exists; pass --force to overwrite it non-interactively", path).red());
+            std::process::exit(1);
+        }
+        eprint!("Overwrite baseline {}? [y/N] ", path);
+        std::io::stderr().flush().unwrap();
+        let mut answer = String::new();
+        std::io::stdin().read_line(&mut answer).unwrap();
+        if !matches!(answer.trim(), "y" | "Y" | "yes") {
+            eprintln!("Baseline left unchanged");
+            return;
+        }
+    }
+    
+    if let Err(e) = baseline::save_baseline(path, flat) {
+        eprintln!("{}", e.red());
+        std::process::exit(1);
+    }
+    eprintln!("{}", format!("Baseline {} updated", path).green());
+}
+
+// Print a line of a standalone test for `format` unless --formats left it out
+fn print_selected(tester: &PerformanceTester, format: Format, line: String) {
+    if tester.measures(format) {
+        println!("{}", line);
+    }
+}
+
+// Print `label` followed by the JSON and protobuf parts that --formats selected,
+// if any
+fn print_selected_parts(tester: &PerformanceTester, label: String, [json, protobuf]: [String; 2]) {
+    let parts: Vec<String> = [(Format::Json, json), (Format::Protobuf, protobuf)].into_iter()
+        .filter(|(format, _)| tester.measures(*format))
+        .map(|(_, part)| part)
+        .collect();
+    if !parts.is_empty() {
+        println!("{}{}", label, parts.join(", "));
+    }
+}
+
+// Run one test by name and print its results
+async fn run_test(tester: &PerformanceTester, test_name: &str) {
+    match test_name {
+        "serialization" => {
+            let result = tester.test_serialization_speed();
+            print_selected(tester, Format::Json, format!("JSON: {:.4} ms", result.json));
+            print_selected(tester, Format::Protobuf, format!("Protobuf: {:.4} ms", result.protobuf));
+            for (name, value) in &result.others {
+                println!("{}: {:.4} ms", name, value);
+            }
+            if let Some(p_value) = result.p_value {
+                println!("p-value: {:.4}", p_value);
+            }
+            println!("Winner: {}", result.winner);
+        },
+        "deserialization" => {
+            let result = tester.test_deserialization_speed();
+            print_selected(tester, Format::Json, format!("JSON: {:.4} ms", result.json));
+            print_selected(tester, Format::Protobuf, format!("Protobuf: {:.4} ms", result.protobuf));
+            for (name, value) in &result.others {
+                println!("{}: {:.4} ms", name, value);
+            }
+            if let Some(p_value) = result.p_value {
+                println!("p-value: {:.4}", p_value);
+            }
+            println!("Winner: {}", result.winner);
+        },
+        "payload" => {
+            let result = tester.test_payload_size();
+            print_selected(tester, Format::Json, format!("JSON uncompressed: {} bytes", result.uncompressed.json));
+            print_selected(tester, Format::Protobuf, format!("Protobuf uncompressed: {} bytes", result.uncompressed.protobuf));
+            print_selected(tester, Format::Json, format!("JSON compressed: {} bytes", result.compressed.json));
+            print_selected(tester, Format::Protobuf, format!("Protobuf compressed: {} bytes", result.compressed.protobuf));
+            print_selected(tester, Format::Json, format!("JSON gzip: {:.2}x in {:.4} ms", result.json_ratio, result.json_compress_ms));
+            print_selected(tester, Format::Protobuf, format!("Protobuf gzip: {:.2}x in {:.4} ms", result.proto_ratio, result.proto_compress_ms));
+            print_selected(tester, Format::Json, format!("JSON zstd: {} bytes", result.zstd_compressed.json));
+            print_selected(tester, Format::Protobuf, format!("Protobuf zstd: {} bytes", result.zstd_compressed.protobuf));
+            for (((name, size), (_, compressed)), (_, zstd)) in result.uncompressed.others.iter()
+                .zip(&result.compressed.others)
+                .zip(&result.zstd_compressed.others) {
+                println!("{} uncompressed: {} bytes", name, size);
+                println!("{} compressed: {} bytes", name, compressed);
+                println!("{} zstd: {} bytes", name, zstd);
+            }
+            for level in &result.gzip_levels {
+                print_selected_parts(tester, format!("Gzip level {}: ", level.level),
+                                     [format!("JSON {} bytes ({:.4} ms)", level.json_bytes, level.json_ms),
+                                      format!("Protobuf {} bytes ({:.4} ms)", level.protobuf_bytes, level.protobuf_ms)]);
+            }
+            println!("Uncompressed winner: {}", result.uncompressed.winner);
+            println!("Compressed winner: {}", result.compressed.winner);
+            println!("Zstd winner: {}", result.zstd_compressed.winner);
+        },
+        "cpu" => {
+            let result = tester.test_cpu_usage();
+            print_selected(tester, Format::Json, format!("JSON: {:.2} ms", result.json));
+            print_selected(tester, Format::Protobuf, format!("Protobuf: {:.2} ms", result.protobuf));
+            for (name, value) in &result.others {
+                println!("{}: {:.2} ms", name, value);
+            }
+            println!("Winner: {}", result.winner);
+        },
+        "memory" => {
+            let result = tester.test_memory_usage();
+            print_selected(tester, Format::Json, format!("JSON: {:.0} bytes", result.json));
+            print_selected(tester, Format::Protobuf, format!("Protobuf: {:.0} bytes", result.protobuf));
+            for (name, value) in &result.others {
+                println!("{}: {:.0} bytes", name, value);
+            }
+            println!("Winner: {}", result.winner);
+        },
+        "network" => {
+            let result = tester.test_network_transfer().await;
+            print_selected(tester, Format::Json, format!("JSON: {:.2} ms", result.json));
+            print_selected(tester, Format::Protobuf, format!("Protobuf: {:.2} ms", result.protobuf));
+            for (name, value) in &result.others {
+                println!("{}: {:.2} ms", name, value);
+            }
+            if let Some(network) = result.network {
+                print_selected_parts(tester, "Transmission: ".to_string(),
+                                     [format!("JSON {:.4} ms", network.json_transmission_ms),
+                                      format!("Protobuf {:.4} ms", network.protobuf_transmission_ms)]);
+                println!("Base latency: {:.2} ms", network.base_latency_ms);
+            }
+            println!("Winner: {}", result.winner);
+        },
+        "latency" => {
+            let result = tester.test_latency_under_load().await;
+            print_selected(tester, Format::Json, format!("JSON: {:.2} ms", result.json));
+            print_selected(tester, Format::Protobuf, format!("Protobuf: {:.2} ms", result.protobuf));
+            for (name, value) in &result.others {
+                println!("{}: {:.2} ms", name, value);
+            }
+            println!("Winner: {}", result.winner);
+        },
+        "init" => {
+            let result = tester.test_parser_initialization();
+            print_selected(tester, Format::Json, format!("JSON: {:.4} ms", result.json));
+            print_selected(tester, Format::Protobuf, format!("Protobuf: {:.4} ms", result.protobuf));
+            for (name, value) in &result.others {
+                println!("{}: {:.4} ms", name, value);
+            }
+            println!("Winner: {}", result.winner);
+        },
+        "throughput" => {
+            let result = tester.test_throughput();
+            print_selected(tester, Format::Json, format!("JSON: {:.2} ops/s", result.json));
+            print_selected(tester, Format::Protobuf, format!("Protobuf: {:.2} ops/s", result.protobuf));
+            for (name, value) in &result.others {
+                println!("{}: {:.2} ops/s", name, value);
+            }
+            if let Some(parallel) = &result.parallel {
+                print_selected(tester, Format::Json, format!("JSON ({} producers): {:.2} ops/s, fairness {:.3}", parallel.producers, parallel.json, parallel.json_fairness));
+                print_selected(tester, Format::Protobuf, format!("Protobuf ({} producers): {:.2} ops/s, fairness {:.3}", parallel.producers, parallel.protobuf, parallel.protobuf_fairness));
+            }
+            if let Some(normalized) = result.normalized {
+                println!("Calibration op: {:.0} ns", normalized.calibration_ns);
+                print_selected(tester, Format::Json, format!("JSON (normalized): {:.4}", normalized.json));
+                print_selected(tester, Format::Protobuf, format!("Protobuf (normalized): {:.4}", normalized.protobuf));
+            }
+            println!("Winner: {}", result.winner);
+        },
+        "schema" => {
+            let result = tester.test_schema_evolution();
+            println!("JSON: {:.4} ms", result.json);
+            println!("Protobuf backwards: {:.4} ms", result.protobuf_backwards);
+            println!("Protobuf forwards: {:.4} ms", result.protobuf_forwards);
+            println!("Protobuf average: {:.4} ms", result.protobuf_average);
+            let cost = &result.decode_cost;
+            println!("Protobuf decode, basic schema: {:.4} ms", cost.basic_ms);
+            println!("Protobuf decode, evolved schema: {:.4} ms ({} added values, {} extra bytes)",
+                    cost.evolved_ms, cost.added_values, cost.added_bytes);
+            println!("Added field decode cost: {:+.4} ms ({:+.1} ns per value)", cost.delta_ms, cost.per_value_ns);
+            let removed = &result.removed_field;
+            println!("Protobuf decode, removed field: {} ({:.4} ms, {} bytes dropped)",
+                    if removed.decoded { "succeeded" } else { "failed" }, removed.ms, removed.dropped_bytes);
+            println!("Winner: {}", result.winner);
+        },
+        "optional" => {
+            let result = tester.test_optional_fields();
+            println!("Absent fraction: {:.2}", result.null_fraction);
+            println!("JSON size: {} bytes", result.payload_size.json);
+            println!("Protobuf size: {} bytes", result.payload_size.protobuf);
+            println!("JSON serialization: {:.4} ms", result.serialization.json);
+            println!("Protobuf serialization: {:.4} ms", result.serialization.protobuf);
+            println!("JSON deserialization: {:.4} ms", result.deserialization.json);
+            println!("Protobuf deserialization: {:.4} ms", result.deserialization.protobuf);
+            for (label, metric, unit, precision) in [("size", &result.payload_size, "bytes", 0),
+                                                     ("serialization", &result.serialization, "ms", 4),
+                                                     ("deserialization", &result.deserialization, "ms", 4)] {
+                for (name, value) in &metric.others {
+                    println!("{} {}: {:.*} {} ({:.2}% of optional)", name, label, precision, value, unit, value / metric.protobuf * 100.0);
+                }
+            }
+            println!("Size winner: {}", result.payload_size.winner);
+            println!("Serialization winner: {}", result.serialization.winner);
+            println!("Deserialization winner: {}", result.deserialization.winner);
+        },
+        "unknown" => {
+            let result = tester.test_unknown_fields();
+            println!("JSON (catch-all): {:.4} ms, {:.2}% kept, lossless: {}",
+                    result.json_retain, result.json_retain_fidelity, result.json_retain_lossless);
+            println!("JSON (plain): {:.4} ms, {:.2}% kept, lossless: {}",
+                    result.json_drop, result.json_drop_fidelity, result.json_drop_lossless);
+            println!("Protobuf: {:.4} ms, {:.2}% kept, lossless: {}",
+                    result.protobuf_drop, result.protobuf_fidelity, result.protobuf_lossless);
+            println!("Protobuf (retaining): {:.4} ms, {:.2}% kept, lossless: {}",
+                    result.protobuf_retain, result.protobuf_retain_fidelity, result.protobuf_retain_lossless);
+        },
+        "stream-compress" => {
+            let result = tester.test_stream_compression();
+            println!("JSON two-step: {:.4} ms", result.two_step.json);
+            println!("JSON one-pass: {:.4} ms", result.one_pass.json);
+            println!("Protobuf two-step: {:.4} ms", result.two_step.protobuf);
+            println!("Protobuf one-pass: {:.4} ms", result.one_pass.protobuf);
+            println!("One-pass winner: {}", result.one_pass.winner);
+        },
+        "stream-decode" => {
+            let result = tester.test_streaming_decode();
+            println!("Messages: {}", result.messages);
+            println!("JSON: {} bytes in {:.2} ms", result.json_input_bytes, result.time.json);
+            println!("Protobuf: {} bytes in {:.2} ms", result.protobuf_input_bytes, result.time.protobuf);
+            println!("Time winner: {}", result.time.winner);
+            match result.peak_memory {
+                Some(peak) => {
+                    println!("JSON peak memory: {} bytes", peak.json);
+                    println!("Protobuf peak memory: {} bytes", peak.protobuf);
+                    println!("Peak memory winner: {}", peak.winner);
+                },
+                None => println!("Peak memory: unavailable (needs the `alloc-tracker` feature)"),
+            }
+        },
+        "map" => {
+            let result = tester.test_map_serialization();
+            println!("Entries: {}", result.entries);
+            println!("JSON size: {} bytes", result.payload_size.json);
+            println!("Protobuf size: {} bytes", result.payload_size.protobuf);
+            println!("JSON serialization: {:.4} ms", result.serialization.json);
+            println!("Protobuf serialization: {:.4} ms", result.serialization.protobuf);
+            println!("JSON deserialization: {:.4} ms", result.deserialization.json);
+            println!("Protobuf deserialization: {:.4} ms", result.deserialization.protobuf);
+            println!("Size winner: {}", result.payload_size.winner);
+        },
+        "int-map" => {
+            let result = tester.test_int_key_map();
+            println!("Entries: {}", result.entries);
+            println!("JSON size (string keys): {} bytes", result.payload_size.json);
+            println!("Protobuf size (int32 keys): {} bytes", result.payload_size.protobuf);
+            println!("JSON serialization: {:.4} ms", result.serialization.json);
+            println!("Protobuf serialization: {:.4} ms", result.serialization.protobuf);
+            println!("JSON deserialization: {:.4} ms", result.deserialization.json);
+            println!("Protobuf deserialization: {:.4} ms", result.deserialization.protobuf);
+            println!("Size winner: {}", result.payload_size.winner);
+        },
+        "sparsity" => {
+            let result = tester.test_sparsity();
+            println!("Records: {}", result.records);
+            for row in &result.rows {
+                println!("{:.0}% absent: JSON nulls {} bytes ({} gzip), JSON omitted {} bytes ({} gzip), Protobuf {} bytes ({} gzip)",
+                        row.null_fraction * 100.0, row.json_nulls, row.json_nulls_gzip,
+                        row.json_omitted, row.json_omitted_gzip, row.protobuf, row.protobuf_gzip);
+            }
+        },
+        "sinks" => {
+            match tester.test_sinks() {
+                Ok(rows) => {
+                    for row in rows {
+                        let protobuf = row.protobuf.map_or("n/a".to_string(), |ms| format!("{:.4} ms", ms));
+                        println!("{}: JSON {:.4} ms, Protobuf {}", row.sink, row.json, protobuf);
+                    }
+                },
+                Err(e) => {
+                    eprintln!("{}", format!("Failed to write temporary file: {}", e).red());
+                    std::process::exit(1);
+                }
+            }
+        },
+        "small-numbers" => {
+            let result = tester.test_small_numbers();
+            println!("JSON size: {} bytes", result.json);
+            println!("Protobuf size: {} bytes", result.protobuf);
+            println!("Size winner: {}", result.winner);
+        },
+        "transcode" => {
+            let result = tester.test_transcoding();
+            println!("JSON -> Protobuf: {:.4} ms ({:.0} ops/s)", result.json_to_protobuf, 1000.0 / result.json_to_protobuf);
+            println!("Protobuf -> JSON: {:.4} ms ({:.0} ops/s)", result.protobuf_to_json, 1000.0 / result.protobuf_to_json);
+        },
+        "drop" => {
+            let result = tester.test_drop_cost();
+            println!("Objects: {}", result.objects);
+            match (result.json_freed_bytes, result.protobuf_freed_bytes) {
+                (Some(json_freed), Some(protobuf_freed)) => println!("Freed per object: JSON {} bytes, Protobuf {} bytes",
+                                                                     json_freed / result.objects.max(1), protobuf_freed / result.objects.max(1)),
+                _ => println!("Freed per object: unavailable (needs the `alloc-tracker` feature)"),
+            }
+            println!("Drop time winner: {}", result.time.winner);
+        },
+        "double-array" => {
+            let result = tester.test_double_array();
+            println!("Values: {}", result.values);
+            println!("Size winner: {}", result.payload_size.winner);
+            println!("Serialization winner: {}", result.serialization.winner);
+            println!("Deserialization winner: {}", result.deserialization.winner);
+        },
+        "crossover" => {
+            let crossovers = tester.test_crossover();
+            let never: Vec<&str> = crossovers.iter()
+                .filter(|crossover| crossover.size.is_none())
+                .map(|crossover| crossover.metric)
+                .collect();
+            println!("Protobuf overtakes JSON on {} of {} metrics", crossovers.len() - never.len(), crossovers.len());
+            if !never.is_empty() {
+                println!("JSON ahead throughout: {}", never.join(", "));
+            }
+        },
+        "short-keys" => {
+            let result = tester.test_short_keys();
+            println!("JSON: {} bytes, JSON (short keys): {} bytes, Protobuf: {} bytes",
+                    result.json, result.json_short_keys, result.protobuf);
+            println!("Gap closed: {:.1}% ({:.1}% compressed)", result.gap_closed_percent, result.compressed_gap_closed_percent);
+        },
+        "shared" => {
+            let result = tester.test_shared_data();
+            println!("Records: {} sharing {} addresses", result.records, result.distinct_addresses);
+            match (result.materialized_heap, result.shared_heap) {
+                (Some(materialized), Some(shared)) => println!("Heap saved by sharing: {} bytes", materialized.saturating_sub(shared)),
+                _ => println!("Heap saved by sharing: unavailable (needs the `alloc-tracker` feature)"),
+            }
+            println!("Wire bytes saved by sharing: 0 (JSON {} bytes, Protobuf {} bytes)", result.json_size, result.protobuf_size);
+            println!("Protobuf expansion cost: {:.4} ms", result.protobuf_shared - result.protobuf_materialized);
+            println!("JSON shared vs materialized: {:.4} ms vs {:.4} ms", result.json_shared, result.json_materialized);
+        },
+        "stream-backpressure" => {
+            let result = tester.test_backpressure_decode().await;
+            println!("Messages: {} through a channel of {}", result.messages, result.buffer);
+            println!("JSON: {:.0} msgs/s, producer blocked {:.2} ms", result.json_msgs_per_sec, result.json_send_wait_ms);
+            println!("Protobuf: {:.0} msgs/s, producer blocked {:.2} ms", result.protobuf_msgs_per_sec, result.protobuf_send_wait_ms);
+        },
+        "decode-reuse" => {
+            let result = tester.test_decode_reuse();
+            println!("JSON: {:.4} ms", result.json);
+            println!("Protobuf fresh: {:.4} ms", result.protobuf_fresh);
+            println!("Protobuf reused: {:.4} ms", result.protobuf_reused);
+            println!("Improvement from reuse: {:.1}%", result.improvement_percent);
+        },
+        "construction" => {
+            let result = tester.test_data_construction();
+            println!("JsonPerson: {:.4} ms", result.json);
+            println!("Person: {:.4} ms", result.protobuf);
+            println!("Ratio (Protobuf/JSON): {:.2}x", result.ratio);
+        },
+        "number-precision" => {
+            let result = tester.test_number_precision();
+            println!("arbitrary_precision: {}", result.arbitrary_precision);
+            println!("JSON Value parse: {:.4} ms", result.json_value_parse);
+            println!("JSON Value serialize: {:.4} ms", result.json_value_serialize);
+            println!("JSON typed parse: {:.4} ms", result.json_typed_parse);
+            println!("Protobuf decode: {:.4} ms", result.protobuf_decode);
+            for (field, percent) in &result.exact_percent {
+                println!("Exact {}: {:.1}%", field, percent);
+            }
+        },
+        "varint" => {
+            let points = tester.test_varint_magnitude();
+            for point in points.iter().filter(|point| point.varint_bytes > point.decimal_bytes) {
+                println!("Varint longer than decimal text for {}: {} vs {} bytes", point.value, point.varint_bytes, point.decimal_bytes);
+            }
+        },
+        "dictionary" => {
+            let result = tester.test_dictionary_compression();
+            println!("Held-out records: {}", result.records);
+            for row in &result.rows {
+                println!("{}: gzip {:.1} bytes, zstd with dictionary {:.1} bytes ({:.2}x smaller)",
+                        row.format, row.gzip, row.zstd_dictionary, row.gzip / row.zstd_dictionary);
+            }
+        },
+        "money" => {
+            for row in tester.test_money() {
+                println!("{}: {} bytes, {:.4} ms serialize, {:.4} ms deserialize, {:.1}% exact",
+                        row.representation, row.bytes, row.serialize_ms, row.deserialize_ms, row.exact_percent);
+            }
+        },
+        "string-ids" => {
+            for row in tester.test_string_ids() {
+                println!("{}: {} bytes, {:.4} ms serialize, {:.4} ms deserialize, {:.1}% exact",
+                        row.representation, row.bytes, row.serialize_ms, row.deserialize_ms, row.exact_percent);
+            }
+        },
+        "determinism" => {
+            let result = tester.test_determinism();
+            let overhead = |deterministic: f64, default: f64| (deterministic / default - 1.0) * 100.0;
+            println!("JSON: {:.4} ms default, {:.4} ms canonical ({:+.1}%)", result.default.json, result.deterministic.json,
+                    overhead(result.deterministic.json, result.default.json));
+            println!("Protobuf: {:.4} ms default, {:.4} ms sorted maps ({:+.1}%)", result.default.protobuf, result.deterministic.protobuf,
+                    overhead(result.deterministic.protobuf, result.default.protobuf));
+            println!("Default output stable across map orders: JSON {}, Protobuf {}",
+                    result.json_default_stable, result.protobuf_default_stable);
+            println!("Deterministic winner: {}", result.deterministic.winner);
+        },
+        "real-network" => {
+            match tester.test_real_network().await {
+                Ok(result) => {
+                    println!("JSON: {:.4} ms per round trip", result.json);
+                    println!("Protobuf: {:.4} ms per round trip", result.protobuf);
+                    println!("Winner: {}", result.winner);
+                },
+                Err(e) => {
+                    eprintln!("{}", format!("Loopback network test failed: {}", e).red());
+                    std::process::exit(1);
+                }
+            }
+        },
+        "checksum" => {
+            let result = tester.test_checksum();
+            println!("JSON checksum: {:.6} ms ({} bytes)", result.json_checksum_ms, result.json_bytes);
+            println!("Protobuf checksum: {:.6} ms ({} bytes)", result.protobuf_checksum_ms, result.protobuf_bytes);
+            println!("JSON serialize + checksum: {:.4} ms", result.serialize_and_checksum.json);
+            println!("Protobuf serialize + checksum: {:.4} ms", result.serialize_and_checksum.protobuf);
+            println!("Winner: {}", result.serialize_and_checksum.winner);
+        },
+        "unicode" => {
+            let result = tester.test_charset();
+            println!("Charset: {:?}", result.charset);
+            println!("JSON size: {} bytes ({} bytes with \\u escapes)", result.json_bytes, result.escaped_json_bytes);
+            println!("Protobuf size: {} bytes", result.protobuf_bytes);
+            println!("JSON serialization: {:.4} ms, Protobuf: {:.4} ms", result.serialization.json, result.serialization.protobuf);
+            println!("JSON deserialization: {:.4} ms, Protobuf: {:.4} ms", result.deserialization.json, result.deserialization.protobuf);
+            if !(result.json_round_trip && result.escaped_json_round_trip && result.protobuf_round_trip) {
+                eprintln!("{}", format!("Text did not survive the round trip (JSON {}, escaped JSON {}, Protobuf {})",
+                    result.json_round_trip, result.escaped_json_round_trip, result.protobuf_round_trip).red());
+                std::process::exit(1);
+            }
+        },
+        "binary" => {
+            let result = tester.test_binary_fields();
+            println!("IDs: {}", result.ids);
+            println!("JSON (base64): {} bytes, {:.4} ms", result.json_size, result.json_decode);
+            println!("Protobuf string: {} bytes, {:.4} ms", result.protobuf_string_size, result.protobuf_string_decode);
+            println!("Protobuf bytes: {} bytes, {:.4} ms", result.protobuf_bytes_size, result.protobuf_bytes_decode);
+        },
+        "patch" => {
+            let result = tester.test_partial_update();
+            println!("Full record: JSON {} bytes, Protobuf {} bytes", result.full_json_size, result.full_protobuf_size);
+            println!("JSON Merge Patch: {} bytes", result.payload_size.json);
+            println!("Protobuf with FieldMask: {} bytes", result.payload_size.protobuf);
+            println!("JSON patch serialization: {:.4} ms", result.serialization.json);
+            println!("Protobuf patch serialization: {:.4} ms", result.serialization.protobuf);
+            println!("JSON patch apply: {:.4} ms", result.apply.json);
+            println!("Protobuf patch apply: {:.4} ms", result.apply.protobuf);
+            println!("Size winner: {}", result.payload_size.winner);
+        },
+        "fuzz" => {
+            let result = tester.test_fuzzed_input();
+            println!("Mutated inputs: {}", result.mutations);
+            for (name, outcomes) in [("JSON", &result.json), ("Protobuf", &result.protobuf)] {
+                let percent = |count: usize| count as f64 / result.mutations as f64 * 100.0;
+                println!("{}: {:.1}% rejected, {:.1}% accepted, {:.1}% panicked", name,
+                        percent(outcomes.rejected), percent(outcomes.accepted), percent(outcomes.panicked));
+            }
+        },
+        _ => {
+            println!("Unknown test: {}", test_name);
+            println!("Available tests: {}", TESTS.join(", "));
+        }
+    }
+}
+
+// Names matching a glob pattern such as `ser*` or `*load*`, in TESTS order. A
+// name without wildcards is passed through as-is, so unknown names are reported
+// by `run_test` along with the list of tests.
+fn resolve_tests(pattern: &str) -> Result<Vec<&str>, String> {
+    if !pattern.contains(['*', '?', '[']) {
+        return Ok(vec![pattern]);
+    }
+    let glob = glob::Pattern::new(pattern).map_err(|e| format!("Invalid test pattern {}: {}", pattern, e))?;
+    let tests: Vec<&str> = TESTS.iter().copied().filter(|test| glob.matches(test)).collect();
+    if tests.is_empty() {
+        return Err(format!("No tests match {}. Available tests: {}", pattern, TESTS.join(", ")));
+    }
+    Ok(tests)
+}
+
+fn write_raw_timings(tester: &PerformanceTester, path: &str) {
+    match tester.write_raw_timings(path) {
+        Ok(rows) => eprintln!("Wrote {} iteration timings to {}", rows, path),
+        Err(e) => {
+            eprintln!("{}", format!("Failed to write {}: {}", path, e).red());
+            std::process::exit(1);
+        }
+    }
+}
+
+// Print per-metric deltas between two flattened result files
+fn compare_runs(old_path: &str, new_path: &str) {
+    let (old, new) = match (baseline::load_baseline(old_path), baseline::load_baseline(new_path)) {
+        (Ok(old), Ok(new)) => (old, new),
+        (Err(e), _) | (_, Err(e)) => {
+            eprintln!("{}", e.red());
+            std::process::exit(1);
+        }
+    };
+    
+    let deltas = baseline::diff(&old, &new);
+    let width = deltas.iter().map(|delta| delta.metric.len()).max().unwrap_or(0);
+    for delta in &deltas {
+        let change = format!("{:+.1}%", delta.change_percent);
+        let change = match delta.improved {
+            Some(true) => format!("{} better", change).green(),
+            Some(false) => format!("{} worse", change).red(),
+            None => change.normal(),
+        };
+        println!("{:width$}  {:>12.4} -> {:<12.4} {}", delta.metric, delta.old, delta.new, change, width = width);
+    }
+    
+    let improved = deltas.iter().filter(|delta| delta.improved == Some(true)).count();
+    let regressed = deltas.iter().filter(|delta| delta.improved == Some(false)).count();
+    println!("\n{} improved, {} regressed, {} compared", improved, regressed, deltas.len());
+}
+
+// Compare this run against the baseline and exit non-zero on regressions
+fn check_baseline(path: &str, flat: &BTreeMap<String, serde_json::Value>, max_regression: f64) {
+    let stored = match baseline::load_baseline(path) {
+        Ok(stored) => stored,
+        Err(e) => {
+            eprintln!("{}", e.red());
+            std::process::exit(1);
+        }
+    };
+    
+    let regressions = baseline::find_regressions(&stored, flat, max_regression);
+    if regressions.is_empty() {
+        eprintln!("{}", format!("No regressions beyond {}% against {}", max_regression, path).green());
+        return;
+    }
+    
+    eprintln!("\n{}", format!("{} regression(s) against {}:", regressions.len(), path).red().bold());
+    for regression in &regressions {
+        eprintln!("  {}: {:.4} -> {:.4} ({:.1}% worse)",
+                 regression.metric, regression.baseline, regression.current, regression.change_percent);
+    }
+    std::process::exit(1);
+}
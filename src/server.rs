@@ -0,0 +1,108 @@
+use crate::benchmark::{flatten_results, BenchmarkResults, PerformanceTester};
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use axum::routing::get;
+use axum::{Json, Router};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+struct ServerState {
+    // Configured from the command line; each request runs a copy of it
+    template: PerformanceTester,
+    // Held while a benchmark runs, so concurrent requests don't skew each other's timings
+    running: tokio::sync::Mutex<()>,
+    latest: Mutex<Option<BenchmarkResults>>,
+}
+
+#[derive(Deserialize)]
+struct BenchmarkParams {
+    size: Option<usize>,
+    iterations: Option<usize>,
+}
+
+// Serve `/benchmark?size=&iterations=` (runs the suite and returns the results as
+// JSON) and `/metrics` (the latest results in the Prometheus text format) until SIGINT
+pub async fn serve(addr: &str, template: PerformanceTester) -> Result<(), String> {
+    let state = Arc::new(ServerState {
+        template,
+        running: tokio::sync::Mutex::new(()),
+        latest: Mutex::new(None),
+    });
+    let app = Router::new()
+        .route("/benchmark", get(benchmark))
+        .route("/metrics", get(metrics))
+        .with_state(state);
+
+    let listener = tokio::net::TcpListener::bind(addr).await
+        .map_err(|e| format!("Failed to bind {}: {}", addr, e))?;
+    println!("Listening on http://{}", addr);
+    axum::serve(listener, app)
+        .with_graceful_shutdown(async {
+            let _ = tokio::signal::ctrl_c().await;
+            println!("Shutting down");
+        })
+        .await
+        .map_err(|e| format!("Server error: {}", e))
+}
+
+async fn benchmark(State(state): State<Arc<ServerState>>, Query(params): Query<BenchmarkParams>)
+    -> Result<Json<BenchmarkResults>, (StatusCode, String)> {
+    let mut tester = state.template.clone();
+    if let Some(size) = params.size {
+        tester = tester.with_size(size);
+    }
+    match params.iterations {
+        Some(0) => return Err((StatusCode::BAD_REQUEST, "iterations must be at least 1".to_string())),
+        Some(iterations) => tester = tester.with_iterations(iterations),
+        None => {}
+    }
+
+    let _running = state.running.lock().await;
+    let results = tester.run_all_tests().await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Verification failed: {}", e)))?
+        .clone();
+    *state.latest.lock().unwrap() = Some(results.clone());
+    Ok(Json(results))
+}
+
+async fn metrics(State(state): State<Arc<ServerState>>) -> String {
+    match &*state.latest.lock().unwrap() {
+        Some(results) => to_prometheus(&flatten_results(results)),
+        None => "# No benchmark has run yet\n".to_string(),
+    }
+}
+
+// Render flattened results as Prometheus gauges: `serialization.json.ms` becomes
+// `benchmark_serialization_ms{format="json"}`, other keys are joined with `_`
+// (e.g. `benchmark_serialization_difference_ratio`). The run id is exported as
+// a label on `benchmark_info`.
+fn to_prometheus(flat: &BTreeMap<String, Value>) -> String {
+    let mut series: BTreeMap<String, Vec<(Option<&str>, f64)>> = BTreeMap::new();
+    for (key, value) in flat {
+        let Some(value) = value.as_f64() else { continue };
+        let parts: Vec<&str> = key.split('.').collect();
+        let (name, format) = match parts.as_slice() {
+            [test, format, unit] if *format != "difference" => (format!("benchmark_{}_{}", test, unit), Some(*format)),
+            _ => (format!("benchmark_{}", parts.join("_")), None),
+        };
+        series.entry(name).or_default().push((format, value));
+    }
+
+    let mut out = String::new();
+    if let Some(run_id) = flat.get("run_id").and_then(Value::as_str) {
+        out.push_str("# TYPE benchmark_info gauge\n");
+        out.push_str(&format!("benchmark_info{{run_id=\"{}\"}} 1\n", run_id));
+    }
+    for (name, samples) in series {
+        out.push_str(&format!("# TYPE {} gauge\n", name));
+        for (format, value) in samples {
+            match format {
+                Some(format) => out.push_str(&format!("{}{{format=\"{}\"}} {}\n", name, format, value)),
+                None => out.push_str(&format!("{} {}\n", name, value)),
+            }
+        }
+    }
+    out
+}
@@ -1,6 +1,18 @@
+use fake::faker::address::en::{BuildingNumber, CityName, CountryName, StateName, StreetName, StreetSuffix, ZipCode};
+use fake::faker::internet::en::SafeEmail;
+use fake::faker::name::en::Name;
+use fake::faker::phone_number::en::PhoneNumber;
+use fake::Fake;
+use prost::Message;
+use rand::rngs::StdRng;
+use rand::distributions::Alphanumeric;
+use rand::{Rng, SeedableRng};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use prost::Message;
+use std::fs::File;
+use std::io::BufReader;
+use std::sync::Arc;
 
 // Include the generated code from the Protocol Buffers
 include!(concat!(env!("OUT_DIR"), "/test.rs"));
@@ -11,8 +23,13 @@ pub mod evolved {
     include!("generated/test_evolved.rs");
 }
 
+// Create a module for the reduced schema (`email` removed and reserved)
+pub mod reduced {
+    include!("generated/test_reduced.rs");
+}
+
 // Serde-compatible data structures for JSON (mirroring the Protocol Buffers structs)
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug)]
 pub struct JsonPerson {
     pub name: String,
     pub id: i32,
@@ -22,13 +39,13 @@ pub struct JsonPerson {
     pub metadata: HashMap<String, String>,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug)]
 pub struct JsonPhoneNumber {
     pub number: String,
     pub type_: i32, // 0=MOBILE, 1=HOME, 2=WORK
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug)]
 pub struct JsonAddress {
     pub street: String,
     pub city: String,
@@ -37,6 +54,283 @@ pub struct JsonAddress {
     pub country: String,
 }
 
+// Original-schema JSON structures that keep fields they don't know about in a
+// catch-all map, so a round trip through an old reader preserves newer data
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct JsonPersonOpen {
+    pub name: String,
+    pub id: i32,
+    pub email: String,
+    pub phones: Vec<JsonPhoneNumberOpen>,
+    pub addresses: Vec<JsonAddressOpen>,
+    pub metadata: HashMap<String, String>,
+    #[serde(flatten)]
+    pub unknown: HashMap<String, serde_json::Value>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct JsonPhoneNumberOpen {
+    pub number: String,
+    pub type_: i32,
+    #[serde(flatten)]
+    pub unknown: HashMap<String, serde_json::Value>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct JsonAddressOpen {
+    pub street: String,
+    pub city: String,
+    pub state: String,
+    pub zip: String,
+    pub country: String,
+    #[serde(flatten)]
+    pub unknown: HashMap<String, serde_json::Value>,
+}
+
+// Profile with nullable fields; absent values serialize as JSON `null`
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct JsonProfile {
+    pub name: String,
+    pub nickname: Option<String>,
+    pub website: Option<String>,
+    pub company: Option<String>,
+    pub age: Option<i32>,
+    pub last_login: Option<i64>,
+    pub verified: Option<bool>,
+    pub score: Option<f64>,
+}
+
+// JsonProfile with absent fields left out of the object instead of written as `null`
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct JsonProfileOmitted {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nickname: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub website: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub company: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub age: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_login: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub verified: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub score: Option<f64>,
+}
+
+impl From<JsonProfile> for JsonProfileOmitted {
+    fn from(profile: JsonProfile) -> Self {
+        JsonProfileOmitted {
+            name: profile.name,
+            nickname: profile.nickname,
+            website: profile.website,
+            company: profile.company,
+            age: profile.age,
+            last_login: profile.last_login,
+            verified: profile.verified,
+            score: profile.score,
+        }
+    }
+}
+
+// JsonPerson whose addresses are shared between records (e.g. a household) instead
+// of copied into each one. Serde writes each `Arc` out in full, so the JSON is
+// identical to the materialized records'.
+#[derive(Serialize, Clone, Debug)]
+pub struct JsonPersonShared {
+    pub name: String,
+    pub id: i32,
+    pub email: String,
+    pub phones: Vec<JsonPhoneNumber>,
+    pub addresses: Vec<Arc<JsonAddress>>,
+    pub metadata: HashMap<String, String>,
+}
+
+impl From<&JsonPersonShared> for JsonPerson {
+    fn from(person: &JsonPersonShared) -> Self {
+        JsonPerson {
+            name: person.name.clone(),
+            id: person.id,
+            email: person.email.clone(),
+            phones: person.phones.clone(),
+            addresses: person.addresses.iter().map(|address| (**address).clone()).collect(),
+            metadata: person.metadata.clone(),
+        }
+    }
+}
+
+// JsonPerson with single-letter keys, as some teams do to shrink JSON payloads.
+// Metadata keys are data, not field names, so they are unchanged.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct JsonPersonShortKeys {
+    #[serde(rename = "n")]
+    pub name: String,
+    #[serde(rename = "i")]
+    pub id: i32,
+    #[serde(rename = "e")]
+    pub email: String,
+    #[serde(rename = "p")]
+    pub phones: Vec<JsonPhoneNumberShortKeys>,
+    #[serde(rename = "a")]
+    pub addresses: Vec<JsonAddressShortKeys>,
+    #[serde(rename = "m")]
+    pub metadata: HashMap<String, String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct JsonPhoneNumberShortKeys {
+    #[serde(rename = "n")]
+    pub number: String,
+    #[serde(rename = "t")]
+    pub type_: i32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct JsonAddressShortKeys {
+    #[serde(rename = "s")]
+    pub street: String,
+    #[serde(rename = "c")]
+    pub city: String,
+    #[serde(rename = "r")]
+    pub state: String,
+    #[serde(rename = "z")]
+    pub zip: String,
+    #[serde(rename = "y")]
+    pub country: String,
+}
+
+impl From<JsonPerson> for JsonPersonShortKeys {
+    fn from(person: JsonPerson) -> Self {
+        JsonPersonShortKeys {
+            name: person.name,
+            id: person.id,
+            email: person.email,
+            phones: person.phones.into_iter()
+                .map(|phone| JsonPhoneNumberShortKeys { number: phone.number, type_: phone.type_ })
+                .collect(),
+            addresses: person.addresses.into_iter()
+                .map(|address| JsonAddressShortKeys {
+                    street: address.street,
+                    city: address.city,
+                    state: address.state,
+                    zip: address.zip,
+                    country: address.country,
+                })
+                .collect(),
+            metadata: person.metadata,
+        }
+    }
+}
+
+// Binary IDs; JSON has no bytes type, so each one is a base64 string
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct JsonIdList {
+    #[serde(with = "base64_ids")]
+    pub ids: Vec<Vec<u8>>,
+}
+
+mod base64_ids {
+    use base64::engine::general_purpose::STANDARD;
+    use base64::Engine;
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(ids: &[Vec<u8>], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(ids.iter().map(|id| STANDARD.encode(id)))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Vec<u8>>, D::Error> {
+        Vec::<String>::deserialize(deserializer)?
+            .iter()
+            .map(|id| STANDARD.decode(id).map_err(D::Error::custom))
+            .collect()
+    }
+}
+
+// Person with a 64-bit id written as a JSON number
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct JsonPersonInt64Id {
+    pub name: String,
+    pub id: i64,
+    pub email: String,
+    pub phones: Vec<JsonPhoneNumber>,
+    pub addresses: Vec<JsonAddress>,
+    pub metadata: HashMap<String, String>,
+}
+
+// The same with the id as a JSON string, the workaround APIs use for readers that
+// parse every number as a double (e.g. JavaScript)
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct JsonPersonStringId {
+    pub name: String,
+    #[serde(with = "string_id")]
+    pub id: i64,
+    pub email: String,
+    pub phones: Vec<JsonPhoneNumber>,
+    pub addresses: Vec<JsonAddress>,
+    pub metadata: HashMap<String, String>,
+}
+
+// The id of a numeric-id record as such a reader sees it
+#[derive(Deserialize, Clone, Debug)]
+pub struct JsonFloatId {
+    pub id: f64,
+}
+
+mod string_id {
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(id: &i64, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(id)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<i64, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(D::Error::custom)
+    }
+}
+
+// Large-valued integers; JSON writes them as decimal text either way
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct JsonLargeInts {
+    pub timestamp_ns: i64,
+    pub hash: u64,
+    pub offset: i64,
+    pub sequence: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct JsonDoubleArray {
+    pub values: Vec<f64>,
+}
+
+// A monetary amount as a JSON number, which serde reads into an f64
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct JsonMoney {
+    pub currency: String,
+    pub amount: f64,
+}
+
+// A monetary amount as decimal text
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct JsonMoneyString {
+    pub currency: String,
+    pub amount: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct JsonIntValue {
+    pub value: i64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct JsonSmallNumbers {
+    pub deltas: Vec<i32>,
+    pub ratios: Vec<f64>,
+}
+
 // Evolved JSON structure (with new fields)
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct JsonPersonEvolved {
@@ -67,9 +361,173 @@ pub struct JsonAddressEvolved {
     pub additional_info: Option<String>,
 }
 
+// Knobs for the shape of generated test data
+#[derive(Clone, Debug, Default)]
+pub struct DataOptions {
+    // Draw metadata keys from this many distinct key stems instead of `key0..keyN`
+    pub metadata_unique_keys: Option<usize>,
+    // Randomize ids, names, emails, phone numbers and metadata values from this seed;
+    // None keeps the fixed `Test Person` record
+    pub seed: Option<u64>,
+    // Fill names, emails, phones and addresses with `fake` data from this seed
+    pub realistic_seed: Option<u64>,
+    // Characters mixed into names, streets, cities and metadata values
+    pub charset: TextCharset,
+}
+
+// Which characters the generated text uses beyond plain ASCII
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum TextCharset {
+    #[default]
+    Ascii,
+    // Accented Latin, Greek, Cyrillic and CJK (2 and 3-byte UTF-8)
+    Unicode,
+    // Multi-codepoint grapheme clusters: flags, ZWJ sequences, skin tones, keycaps
+    Emoji,
+}
+
+const UNICODE_SAMPLES: [&str; 5] = [
+    "J\u{fc}rgen M\u{fc}ller",
+    "S\u{e3}o Jos\u{e9}",
+    "\u{3b1}\u{3b8}\u{3ae}\u{3bd}\u{3b1}",
+    "\u{41c}\u{43e}\u{441}\u{43a}\u{432}\u{430}",
+    "\u{6771}\u{4eac}\u{90fd}",
+];
+
+const EMOJI_SAMPLES: [&str; 6] = [
+    // Regional indicator pairs: Japan, Brazil
+    "\u{1f1ef}\u{1f1f5}\u{1f1e7}\u{1f1f7}",
+    // Family: man, woman, girl, boy joined by ZWJ
+    "\u{1f468}\u{200d}\u{1f469}\u{200d}\u{1f467}\u{200d}\u{1f466}",
+    // Rainbow flag: white flag, VS16, ZWJ, rainbow
+    "\u{1f3f3}\u{fe0f}\u{200d}\u{1f308}",
+    // Thumbs up with a skin tone modifier
+    "\u{1f44d}\u{1f3fd}",
+    // Keycap one: digit, VS16, combining keycap
+    "1\u{fe0f}\u{20e3}",
+    // Flag of Scotland: black flag plus tag characters
+    "\u{1f3f4}\u{e0067}\u{e0062}\u{e0073}\u{e0063}\u{e0074}\u{e007f}",
+];
+
+impl TextCharset {
+    fn samples(self) -> &'static [&'static str] {
+        match self {
+            TextCharset::Ascii => &[],
+            TextCharset::Unicode => &UNICODE_SAMPLES,
+            TextCharset::Emoji => &EMOJI_SAMPLES,
+        }
+    }
+
+    // Append the `index`-th sample (cycling) to `text`
+    fn decorate(self, text: &mut String, index: usize) {
+        let samples = self.samples();
+        if !samples.is_empty() {
+            text.push(' ');
+            text.push_str(samples[index % samples.len()]);
+        }
+    }
+}
+
+// Metadata keys drawn from `unique_keys` distinct random stems. Maps can't repeat a key,
+// so entries beyond the first `unique_keys` reuse a stem with a short numeric suffix;
+// fewer stems means more redundant key text.
+fn pooled_metadata_keys(size: usize, unique_keys: usize) -> Vec<String> {
+    let unique_keys = unique_keys.max(1);
+    let mut rng = StdRng::seed_from_u64(7);
+    let stems: Vec<String> = (0..unique_keys)
+        .map(|_| {
+            let suffix: String = (&mut rng).sample_iter(&Alphanumeric).take(12).map(char::from).collect();
+            format!("attribute_{}", suffix)
+        })
+        .collect();
+
+    (0..size)
+        .map(|i| {
+            let stem = &stems[i % unique_keys];
+            if i < unique_keys { stem.clone() } else { format!("{}_{}", stem, i / unique_keys) }
+        })
+        .collect()
+}
+
 // Function to generate test data
-pub fn generate_test_data(size: usize) -> (JsonPerson, Person) {
-    // For JSON
+pub fn generate_test_data_with(size: usize, options: &DataOptions) -> (JsonPerson, Person) {
+    PersonMessage(options).generate(size)
+}
+
+// A message type the benchmarks can generate and round-trip, as a serde_json form
+// and a protobuf form of each record. The built-in `Person` is one; a message
+// loaded from a descriptor set at runtime (`dynamic::RuntimeMessage`) is another,
+// and other message types plug in the same way.
+pub trait TestMessage {
+    type Json;
+    type Proto;
+
+    // Fully-qualified protobuf name, e.g. `test.Person`
+    fn name(&self) -> String;
+    // One record whose repeated and map fields have `size` entries
+    fn generate(&self, size: usize) -> (Self::Json, Self::Proto);
+    fn serialize_json(&self, record: &Self::Json) -> String;
+    fn deserialize_json(&self, json: &str) -> Self::Json;
+    fn encode(&self, record: &Self::Proto) -> Vec<u8>;
+    fn decode(&self, bytes: &[u8]) -> Self::Proto;
+}
+
+// The built-in `Person`, generated with these options
+pub struct PersonMessage<'a>(pub &'a DataOptions);
+
+impl TestMessage for PersonMessage<'_> {
+    type Json = JsonPerson;
+    type Proto = Person;
+
+    fn name(&self) -> String {
+        "test.Person".to_string()
+    }
+
+    fn generate(&self, size: usize) -> (JsonPerson, Person) {
+        let options = self.0;
+        let mut json_person = build_json_person(size, options);
+        let mut proto_person = build_proto_person(size, options);
+
+        if let Some(seed) = options.seed {
+            randomize(&mut json_person, &mut proto_person, seed);
+        }
+        if let Some(seed) = options.realistic_seed {
+            fill_realistic(&mut json_person, &mut proto_person, seed);
+        }
+        if options.charset != TextCharset::Ascii {
+            apply_charset(&mut json_person, &mut proto_person, options.charset);
+        }
+
+        (json_person, proto_person)
+    }
+
+    fn serialize_json(&self, record: &JsonPerson) -> String {
+        serde_json::to_string(record).unwrap()
+    }
+
+    fn deserialize_json(&self, json: &str) -> JsonPerson {
+        serde_json::from_str(json).unwrap()
+    }
+
+    fn encode(&self, record: &Person) -> Vec<u8> {
+        record.encode_to_vec()
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Person {
+        Person::decode(bytes).unwrap()
+    }
+}
+
+// Metadata keys for a record of `size` entries
+fn metadata_keys(size: usize, options: &DataOptions) -> Vec<String> {
+    match options.metadata_unique_keys {
+        Some(unique_keys) => pooled_metadata_keys(size, unique_keys),
+        None => (0..size).map(|i| format!("key{}", i)).collect(),
+    }
+}
+
+// Build the serde side of a test record (before any realistic fill)
+pub fn build_json_person(size: usize, options: &DataOptions) -> JsonPerson {
     let mut json_person = JsonPerson {
         name: "Test Person".to_string(),
         id: 12345,
@@ -79,7 +537,35 @@ pub fn generate_test_data(size: usize) -> (JsonPerson, Person) {
         metadata: HashMap::new(),
     };
 
-    // For Protocol Buffers
+    // Add phone numbers based on size
+    for i in 0..size {
+        json_person.phones.push(JsonPhoneNumber {
+            number: format!("555-{}", 1000 + i),
+            type_: (i % 3) as i32, // Cycle through MOBILE, HOME, WORK
+        });
+    }
+
+    // Add addresses based on size
+    for i in 0..std::cmp::max(1, size / 2) {
+        json_person.addresses.push(JsonAddress {
+            street: format!("{} Main St", 100 + i),
+            city: format!("City {}", i),
+            state: format!("State {}", i),
+            zip: format!("{}", 10000 + i),
+            country: "Country".to_string(),
+        });
+    }
+
+    // Add metadata based on size
+    for (i, key) in metadata_keys(size, options).into_iter().enumerate() {
+        json_person.metadata.insert(key, format!("value{}", i));
+    }
+
+    json_person
+}
+
+// Build the Protocol Buffers side of a test record (before any realistic fill)
+pub fn build_proto_person(size: usize, options: &DataOptions) -> Person {
     let mut proto_person = Person {
         name: "Test Person".to_string(),
         id: 12345,
@@ -91,53 +577,549 @@ pub fn generate_test_data(size: usize) -> (JsonPerson, Person) {
 
     // Add phone numbers based on size
     for i in 0..size {
-        let phone_type = (i % 3) as i32; // Cycle through MOBILE, HOME, WORK
-        
-        json_person.phones.push(JsonPhoneNumber {
-            number: format!("555-{}", 1000 + i),
-            type_: phone_type,
-        });
-
         proto_person.phones.push(person::PhoneNumber {
             number: format!("555-{}", 1000 + i),
-            r#type: phone_type,
+            r#type: (i % 3) as i32, // Cycle through MOBILE, HOME, WORK
         });
     }
 
     // Add addresses based on size
     for i in 0..std::cmp::max(1, size / 2) {
-        json_person.addresses.push(JsonAddress {
+        proto_person.addresses.push(person::Address {
             street: format!("{} Main St", 100 + i),
             city: format!("City {}", i),
             state: format!("State {}", i),
             zip: format!("{}", 10000 + i),
             country: "Country".to_string(),
         });
+    }
 
-        proto_person.addresses.push(person::Address {
+    // Add metadata based on size
+    for (i, key) in metadata_keys(size, options).into_iter().enumerate() {
+        proto_person.metadata.insert(key, format!("value{}", i));
+    }
+
+    proto_person
+}
+
+// Replace the synthetic `City 0`-style text with plausible English names, emails,
+// phone numbers and addresses, whose entropy is closer to production data. The
+// same seed always produces the same record.
+fn fill_realistic(json_person: &mut JsonPerson, proto_person: &mut Person, seed: u64) {
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let name: String = Name().fake_with_rng(&mut rng);
+    let email: String = SafeEmail().fake_with_rng(&mut rng);
+    json_person.name = name.clone();
+    json_person.email = email.clone();
+    proto_person.name = name;
+    proto_person.email = email;
+
+    for (json_phone, proto_phone) in json_person.phones.iter_mut().zip(proto_person.phones.iter_mut()) {
+        let number: String = PhoneNumber().fake_with_rng(&mut rng);
+        json_phone.number = number.clone();
+        proto_phone.number = number;
+    }
+
+    for (json_address, proto_address) in json_person.addresses.iter_mut().zip(proto_person.addresses.iter_mut()) {
+        let building: String = BuildingNumber().fake_with_rng(&mut rng);
+        let street: String = StreetName().fake_with_rng(&mut rng);
+        let street_suffix: String = StreetSuffix().fake_with_rng(&mut rng);
+        let address = JsonAddress {
+            street: format!("{} {} {}", building, street, street_suffix),
+            city: CityName().fake_with_rng(&mut rng),
+            state: StateName().fake_with_rng(&mut rng),
+            zip: ZipCode().fake_with_rng(&mut rng),
+            country: CountryName().fake_with_rng(&mut rng),
+        };
+        proto_address.street = address.street.clone();
+        proto_address.city = address.city.clone();
+        proto_address.state = address.state.clone();
+        proto_address.zip = address.zip.clone();
+        proto_address.country = address.country.clone();
+        *json_address = address;
+    }
+}
+
+// Replace the fixed id, name, email, phone numbers and metadata values with values
+// drawn from `seed`, so records have some string entropy; the same seed always
+// produces the same record
+fn randomize(json_person: &mut JsonPerson, proto_person: &mut Person, seed: u64) {
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let id: i32 = rng.gen_range(1..i32::MAX);
+    let name = format!("Test Person {}", rng.gen::<u32>());
+    let email = format!("person{}@example.com", rng.gen::<u32>());
+    json_person.id = id;
+    json_person.name = name.clone();
+    json_person.email = email.clone();
+    proto_person.id = id;
+    proto_person.name = name;
+    proto_person.email = email;
+
+    for (json_phone, proto_phone) in json_person.phones.iter_mut().zip(proto_person.phones.iter_mut()) {
+        let number = format!("555-{:04}", rng.gen_range(0..10000));
+        json_phone.number = number.clone();
+        proto_phone.number = number;
+    }
+
+    // Sorted, since map iteration order differs between runs
+    let mut keys: Vec<String> = json_person.metadata.keys().cloned().collect();
+    keys.sort();
+    for key in keys {
+        let value = format!("value{}", rng.gen::<u32>());
+        proto_person.metadata.insert(key.clone(), value.clone());
+        json_person.metadata.insert(key, value);
+    }
+}
+
+// Mix the charset's samples into the free-text fields of both sides of a record,
+// cycling through them so every sample appears once the record is large enough
+fn apply_charset(json_person: &mut JsonPerson, proto_person: &mut Person, charset: TextCharset) {
+    charset.decorate(&mut json_person.name, 0);
+    charset.decorate(&mut proto_person.name, 0);
+
+    for (i, (json_address, proto_address)) in json_person.addresses.iter_mut().zip(proto_person.addresses.iter_mut()).enumerate() {
+        charset.decorate(&mut json_address.street, 2 * i + 1);
+        charset.decorate(&mut proto_address.street, 2 * i + 1);
+        charset.decorate(&mut json_address.city, 2 * i + 2);
+        charset.decorate(&mut proto_address.city, 2 * i + 2);
+    }
+
+    // Map order differs between the two sides, so pair values up by sorted key
+    let mut keys: Vec<String> = json_person.metadata.keys().cloned().collect();
+    keys.sort();
+    for (i, key) in keys.iter().enumerate() {
+        if let Some(value) = json_person.metadata.get_mut(key) {
+            charset.decorate(value, i);
+        }
+        if let Some(value) = proto_person.metadata.get_mut(key) {
+            charset.decorate(value, i);
+        }
+    }
+}
+
+// Function to generate a pool of distinct records with the same shape, so timing
+// loops can rotate through varied inputs instead of reusing one record
+pub fn generate_test_data_pool(size: usize, count: usize, options: &DataOptions) -> Vec<(JsonPerson, Person)> {
+    (0..count)
+        .map(|n| {
+            let n = n as u64;
+            // Every record gets its own seeds. Realistic records are only randomized
+            // further with an explicit seed; synthetic ones always are, or the pool
+            // would hold `count` copies of one record.
+            let seed = match (options.seed, options.realistic_seed) {
+                (Some(seed), _) => Some(seed.wrapping_add(n)),
+                (None, None) => Some(n),
+                (None, Some(_)) => None,
+            };
+            let options = DataOptions {
+                seed,
+                realistic_seed: options.realistic_seed.map(|seed| seed.wrapping_add(n)),
+                ..options.clone()
+            };
+            generate_test_data_with(size, &options)
+        })
+        .collect()
+}
+
+// Read newline-delimited JSON records (one `JsonPerson` per line). Records are
+// deserialized one at a time from a buffered reader, so the file is never held
+// as a single string or `Value`.
+// Decode a file of length-delimited `Person` messages (a varint length before each
+// message, as written by `encode_length_delimited` or Java's `writeDelimitedTo`)
+pub fn decode_length_delimited(path: &str, mut bytes: &[u8]) -> Result<Vec<JsonPerson>, String> {
+    let mut records = Vec::new();
+    while !bytes.is_empty() {
+        let person = Person::decode_length_delimited(&mut bytes)
+            .map_err(|e| format!("Invalid record {} in {}: {}", records.len() + 1, path, e))?;
+        records.push(JsonPerson {
+            name: person.name,
+            id: person.id,
+            email: person.email,
+            phones: person.phones.into_iter()
+                .map(|phone| JsonPhoneNumber { number: phone.number, type_: phone.r#type })
+                .collect(),
+            addresses: person.addresses.into_iter()
+                .map(|address| JsonAddress {
+                    street: address.street,
+                    city: address.city,
+                    state: address.state,
+                    zip: address.zip,
+                    country: address.country,
+                })
+                .collect(),
+            metadata: person.metadata,
+        });
+    }
+    Ok(records)
+}
+
+pub fn load_ndjson(path: &str) -> Result<Vec<JsonPerson>, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open {}: {}", path, e))?;
+    serde_json::Deserializer::from_reader(BufReader::new(file))
+        .into_iter::<JsonPerson>()
+        .map(|record| record.map_err(|e| format!("Invalid record in {}: {}", path, e)))
+        .collect()
+}
+
+// Function to generate `count` profiles where each optional field is absent
+// with probability `null_fraction`
+pub fn generate_optional_test_data(count: usize, null_fraction: f64) -> (Vec<JsonProfile>, ProfileList) {
+    let mut rng = StdRng::seed_from_u64(42);
+    let mut json_profiles = Vec::with_capacity(count);
+    let mut proto_profiles = Vec::with_capacity(count);
+
+    for i in 0..count {
+        let mut present = || !rng.gen_bool(null_fraction);
+        let nickname = present().then(|| format!("nick{}", i));
+        let website = present().then(|| format!("https://example.com/user/{}", i));
+        let company = present().then(|| format!("Company {}", i % 10));
+        let age = present().then(|| 20 + (i % 50) as i32);
+        let last_login = present().then(|| 1_700_000_000_000 + i as i64 * 1000);
+        let verified = present().then_some(i % 2 == 0);
+        let score = present().then_some(i as f64 * 1.5);
+
+        json_profiles.push(JsonProfile {
+            name: format!("User {}", i),
+            nickname: nickname.clone(),
+            website: website.clone(),
+            company: company.clone(),
+            age,
+            last_login,
+            verified,
+            score,
+        });
+
+        proto_profiles.push(Profile {
+            name: format!("User {}", i),
+            nickname,
+            website,
+            company,
+            age,
+            last_login,
+            verified,
+            score,
+        });
+    }
+
+    (json_profiles, ProfileList { profiles: proto_profiles })
+}
+
+// Function to generate `count` residents of one building who all share the same
+// `addresses` addresses through `Arc`
+// The same profiles with wrapper types (`google.protobuf.StringValue` etc.) in
+// place of proto3 `optional`
+pub fn wrap_profiles(profiles: &ProfileList) -> ProfileWrappedList {
+    ProfileWrappedList {
+        profiles: profiles.profiles.iter()
+            .map(|profile| ProfileWrapped {
+                name: profile.name.clone(),
+                nickname: profile.nickname.clone(),
+                website: profile.website.clone(),
+                company: profile.company.clone(),
+                age: profile.age,
+                last_login: profile.last_login,
+                verified: profile.verified,
+                score: profile.score,
+            })
+            .collect(),
+    }
+}
+
+pub fn generate_shared_address_test_data(count: usize, addresses: usize) -> Vec<JsonPersonShared> {
+    let shared: Vec<Arc<JsonAddress>> = (0..addresses)
+        .map(|i| Arc::new(JsonAddress {
             street: format!("{} Main St", 100 + i),
             city: format!("City {}", i),
             state: format!("State {}", i),
             zip: format!("{}", 10000 + i),
             country: "Country".to_string(),
+        }))
+        .collect();
+
+    (0..count)
+        .map(|i| JsonPersonShared {
+            name: format!("Resident {}", i),
+            id: i as i32,
+            email: format!("resident{}@example.com", i),
+            phones: vec![JsonPhoneNumber { number: format!("555-{}", 1000 + i), type_: 0 }],
+            addresses: shared.clone(),
+            metadata: HashMap::new(),
+        })
+        .collect()
+}
+
+// Function to generate a string map with `size` entries, shaped like Person metadata
+pub fn generate_map_test_data(size: usize) -> (HashMap<String, String>, MetadataMap) {
+    let entries: HashMap<String, String> = (0..size)
+        .map(|i| (format!("key{}", i), format!("value{}", i)))
+        .collect();
+
+    (entries.clone(), MetadataMap { entries })
+}
+
+// Function to generate an integer-keyed map with `size` entries, keyed by random
+// positive IDs; serde_json writes the keys as strings and parses them back
+pub fn generate_int_map_test_data(size: usize) -> (HashMap<i32, String>, IntKeyMap) {
+    let mut rng = StdRng::seed_from_u64(42);
+    let entries: HashMap<i32, String> = (0..size)
+        .map(|i| (rng.gen_range(1..i32::MAX), format!("value{}", i)))
+        .collect();
+
+    (entries.clone(), IntKeyMap { entries })
+}
+
+// Function to generate `count` random 16-byte IDs: base64 in JSON, hex in the
+// protobuf string variant, raw in the bytes variant
+pub fn generate_id_test_data(count: usize) -> (JsonIdList, IdListString, IdListBytes) {
+    let mut rng = StdRng::seed_from_u64(42);
+    let ids: Vec<Vec<u8>> = (0..count).map(|_| rng.gen::<[u8; 16]>().to_vec()).collect();
+    let hex_ids = ids.iter()
+        .map(|id| id.iter().map(|b| format!("{:02x}", b)).collect())
+        .collect();
+
+    (JsonIdList { ids: ids.clone() }, IdListString { ids: hex_ids }, IdListBytes { ids })
+}
+
+// Function to generate `count` records of large integers: nanosecond timestamps,
+// random 64-bit hashes, negative offsets and a small sequence number
+pub fn generate_large_int_test_data(count: usize) -> (Vec<JsonLargeInts>, LargeIntsVarintList, LargeIntsFixedList) {
+    let mut rng = StdRng::seed_from_u64(42);
+    let mut json_records = Vec::with_capacity(count);
+    let mut varint_records = Vec::with_capacity(count);
+    let mut fixed_records = Vec::with_capacity(count);
+
+    for i in 0..count {
+        let timestamp_ns = 1_700_000_000_000_000_000 + rng.gen_range(0..1_000_000_000_000i64);
+        let hash: u64 = rng.gen();
+        let offset = -rng.gen_range(1..1_000_000i64);
+        let sequence = i as u64;
+
+        json_records.push(JsonLargeInts { timestamp_ns, hash, offset, sequence });
+        varint_records.push(LargeIntsVarint { timestamp_ns, hash, offset, sequence });
+        fixed_records.push(LargeIntsFixed { timestamp_ns, hash, offset, sequence });
+    }
+
+    (
+        json_records,
+        LargeIntsVarintList { records: varint_records },
+        LargeIntsFixedList { records: fixed_records },
+    )
+}
+
+// Function to generate a JSON array of `count` records whose numbers don't all fit
+// in an f64: a u64 hash, a 128-bit id and a decimal amount with 27 significant
+// digits. Keys are in sorted order, matching how `serde_json::Value` writes them.
+// Also returns each record's number literals, keyed by field.
+pub fn generate_wide_number_json(count: usize) -> (String, Vec<[(&'static str, String); 3]>) {
+    let mut rng = StdRng::seed_from_u64(42);
+    let mut records = Vec::with_capacity(count);
+    let mut literals = Vec::with_capacity(count);
+
+    for _ in 0..count {
+        let amount = format!("{}.{:09}", rng.gen_range(10u64.pow(17)..10u64.pow(18)), rng.gen_range(1..1_000_000_000u32));
+        let hash = rng.gen::<u64>().to_string();
+        let wide = rng.gen_range(u64::MAX as u128 + 1..u128::MAX).to_string();
+        records.push(format!(r#"{{"amount":{},"hash":{},"wide":{}}}"#, amount, hash, wide));
+        literals.push([("amount", amount), ("hash", hash), ("wide", wide)]);
+    }
+
+    (format!("[{}]", records.join(",")), literals)
+}
+
+// Function to generate a partial update to the given record: a new email, one
+// changed metadata value and an extra phone number. JSON expresses it as a Merge
+// Patch (RFC 7396), which recurses into objects but replaces arrays; protobuf
+// sends whole top-level fields named by a FieldMask.
+pub fn generate_partial_update(json_person: &JsonPerson, proto_person: &Person) -> (serde_json::Value, PersonPatch) {
+    let email = "updated@example.com".to_string();
+    let number = format!("555-{}", 1000 + proto_person.phones.len());
+
+    let mut json_phones = json_person.phones.clone();
+    json_phones.push(JsonPhoneNumber { number: number.clone(), type_: 0 });
+    let merge_patch = serde_json::json!({
+        "email": email,
+        "phones": json_phones,
+        "metadata": { "key0": "updated" },
+    });
+
+    let mut phones = proto_person.phones.clone();
+    phones.push(person::PhoneNumber { number, r#type: 0 });
+    let mut metadata = proto_person.metadata.clone();
+    metadata.insert("key0".to_string(), "updated".to_string());
+    let patch = PersonPatch {
+        person: Some(Person { email, phones, metadata, ..Default::default() }),
+        update_mask: Some(prost_types::FieldMask {
+            paths: vec!["email".to_string(), "phones".to_string(), "metadata".to_string()],
+        }),
+    };
+
+    (merge_patch, patch)
+}
+
+// Function to generate `count` small negative deltas and short decimal ratios,
+// a shape where protobuf is larger than JSON
+// Function to generate `count` sensor-style readings: a noisy sine wave, so most
+// values need the full 15-17 significant digits in JSON
+pub fn generate_double_array_test_data(count: usize) -> (JsonDoubleArray, DoubleArray) {
+    let mut rng = StdRng::seed_from_u64(3);
+    let values: Vec<f64> = (0..count)
+        .map(|i| (i as f64 * 0.01).sin() * 100.0 + rng.gen_range(-0.5..0.5))
+        .collect();
+
+    (JsonDoubleArray { values: values.clone() }, DoubleArray { values })
+}
+
+pub fn generate_small_number_test_data(count: usize) -> (JsonSmallNumbers, SmallNumbers) {
+    let deltas: Vec<i32> = (0..count).map(|i| -1 - (i % 9) as i32).collect();
+    let ratios: Vec<f64> = (0..count).map(|i| (i % 4) as f64 * 0.25).collect();
+
+    (
+        JsonSmallNumbers { deltas: deltas.clone(), ratios: ratios.clone() },
+        SmallNumbers { deltas, ratios },
+    )
+}
+
+// Monetary amounts have this many decimal places
+// The same 64-bit IDs as JSON numbers, JSON strings and protobuf int64
+pub struct StringIdTestData {
+    pub ids: Vec<i64>,
+    pub json_number: Vec<JsonPersonInt64Id>,
+    pub json_string: Vec<JsonPersonStringId>,
+    pub proto: PersonInt64IdList,
+}
+
+// Function to generate `count` small records with snowflake-style IDs: a millisecond
+// timestamp above 41 bits shifted past 22 bits of worker and sequence number, which
+// puts every ID far beyond 2^53
+pub fn generate_string_id_test_data(count: usize) -> StringIdTestData {
+    let mut rng = StdRng::seed_from_u64(42);
+    let base = build_json_person(1, &DataOptions::default());
+    let mut data = StringIdTestData {
+        ids: Vec::with_capacity(count),
+        json_number: Vec::with_capacity(count),
+        json_string: Vec::with_capacity(count),
+        proto: PersonInt64IdList::default(),
+    };
+
+    for i in 0..count {
+        let timestamp_ms = rng.gen_range(1_700_000_000_000i64..1_800_000_000_000);
+        let id = (timestamp_ms << 22) | (rng.gen_range(0..1024i64) << 12) | (i as i64 & 0xfff);
+        data.ids.push(id);
+        data.json_number.push(JsonPersonInt64Id {
+            name: base.name.clone(),
+            id,
+            email: base.email.clone(),
+            phones: base.phones.clone(),
+            addresses: base.addresses.clone(),
+            metadata: base.metadata.clone(),
+        });
+        data.json_string.push(JsonPersonStringId {
+            name: base.name.clone(),
+            id,
+            email: base.email.clone(),
+            phones: base.phones.clone(),
+            addresses: base.addresses.clone(),
+            metadata: base.metadata.clone(),
+        });
+        data.proto.people.push(PersonInt64Id {
+            name: base.name.clone(),
+            id,
+            email: base.email.clone(),
+            phones: base.phones.iter()
+                .map(|phone| person::PhoneNumber { number: phone.number.clone(), r#type: phone.type_ })
+                .collect(),
+            addresses: base.addresses.iter()
+                .map(|address| person::Address {
+                    street: address.street.clone(),
+                    city: address.city.clone(),
+                    state: address.state.clone(),
+                    zip: address.zip.clone(),
+                    country: address.country.clone(),
+                })
+                .collect(),
+            metadata: base.metadata.clone(),
         });
     }
 
-    // Add metadata based on size
-    for i in 0..size {
-        let key = format!("key{}", i);
-        let value = format!("value{}", i);
-        json_person.metadata.insert(key.clone(), value.clone());
-        proto_person.metadata.insert(key, value);
+    data
+}
+
+pub const MONEY_DECIMALS: u32 = 4;
+
+// The same amounts in every money representation
+pub struct MoneyTestData {
+    // Exact amounts in 1/10^MONEY_DECIMALS units
+    pub scaled: Vec<i64>,
+    pub json_number: Vec<JsonMoney>,
+    pub json_string: Vec<JsonMoneyString>,
+    pub proto_double: MoneyList,
+    pub proto_decimal: MoneyList,
+    pub proto_scaled: MoneyList,
+}
+
+// Render an amount in 1/10^MONEY_DECIMALS units as decimal text, e.g. `12.3400`
+pub fn format_money(scaled: i64) -> String {
+    let unit = 10i64.pow(MONEY_DECIMALS);
+    format!("{}.{:0width$}", scaled / unit, scaled % unit, width = MONEY_DECIMALS as usize)
+}
+
+// Parse non-negative decimal text back into 1/10^MONEY_DECIMALS units; None when
+// it has more decimal places than that, i.e. it isn't an exact amount
+pub fn parse_money(text: &str) -> Option<i64> {
+    let (whole, fraction) = text.split_once('.').unwrap_or((text, ""));
+    if fraction.len() > MONEY_DECIMALS as usize {
+        return None;
     }
+    let padded = format!("{:0<width$}", fraction, width = MONEY_DECIMALS as usize);
+    whole.parse::<i64>().ok()?
+        .checked_mul(10i64.pow(MONEY_DECIMALS))?
+        .checked_add(padded.parse().ok()?)
+}
+
+// Function to generate `count` amounts with 1 to 18 significant digits, so both
+// amounts an f64 holds exactly and amounts it rounds are represented
+pub fn generate_money_test_data(count: usize) -> MoneyTestData {
+    let mut rng = StdRng::seed_from_u64(42);
+    let scaled: Vec<i64> = (0..count)
+        .map(|i| rng.gen_range(0..10i64.pow(1 + (i % 18) as u32)))
+        .collect();
+    let entries = |amount: fn(i64) -> Money| MoneyList { entries: scaled.iter().map(|&value| amount(value)).collect() };
+
+    MoneyTestData {
+        json_number: scaled.iter()
+            .map(|&value| JsonMoney { currency: "USD".to_string(), amount: format_money(value).parse().unwrap() })
+            .collect(),
+        json_string: scaled.iter()
+            .map(|&value| JsonMoneyString { currency: "USD".to_string(), amount: format_money(value) })
+            .collect(),
+        proto_double: entries(|value| Money {
+            currency: "USD".to_string(),
+            amount_double: format_money(value).parse().unwrap(),
+            ..Default::default()
+        }),
+        proto_decimal: entries(|value| Money {
+            currency: "USD".to_string(),
+            amount_decimal: format_money(value),
+            ..Default::default()
+        }),
+        proto_scaled: entries(|value| Money {
+            currency: "USD".to_string(),
+            amount_scaled: value,
+            ..Default::default()
+        }),
+        scaled,
+    }
+}
 
-    (json_person, proto_person)
+// Function to generate a one-integer message for each value
+pub fn generate_int_value_test_data(values: &[i64]) -> Vec<(JsonIntValue, IntValue)> {
+    values.iter().map(|&value| (JsonIntValue { value }, IntValue { value })).collect()
 }
 
-// Function to generate evolved test data
-pub fn generate_evolved_test_data(size: usize) -> (JsonPersonEvolved, evolved::Person) {
-    let (json_basic, _) = generate_test_data(size);
-    
+// Function to generate the evolved version of a record, as written by a newer service
+pub fn generate_evolved_test_data(json_basic: &JsonPerson) -> (JsonPersonEvolved, evolved::Person) {
     // Convert to evolved JSON with additional fields
     let json_evolved = JsonPersonEvolved {
         name: json_basic.name.clone(),
@@ -162,45 +1144,29 @@ pub fn generate_evolved_test_data(size: usize) -> (JsonPersonEvolved, evolved::P
     };
 
     // For Protocol Buffers evolved schema
-    let mut proto_evolved = evolved::Person {
-        name: json_basic.name,
+    let proto_evolved = evolved::Person {
+        name: json_basic.name.clone(),
         id: json_basic.id,
-        email: json_basic.email,
-        phones: Vec::new(),
-        addresses: Vec::new(),
-        metadata: HashMap::new(),
+        email: json_basic.email.clone(),
+        // Phone numbers with the new is_primary field
+        phones: json_basic.phones.iter().map(|p| evolved::person::PhoneNumber {
+            number: p.number.clone(),
+            r#type: p.type_,
+            is_primary: p.type_ == 0, // Make MOBILE phones primary
+        }).collect(),
+        // Addresses with the new additional_info field
+        addresses: json_basic.addresses.iter().map(|a| evolved::person::Address {
+            street: a.street.clone(),
+            city: a.city.clone(),
+            state: a.state.clone(),
+            zip: a.zip.clone(),
+            country: a.country.clone(),
+            additional_info: "Extra address details".to_string(),
+        }).collect(),
+        metadata: json_basic.metadata.clone(),
         additional_field: "New information".to_string(),
         priority: 5,
     };
-    
-    // Add phone numbers with the new is_primary field
-    for i in 0..size {
-        let phone_type = (i % 3) as i32;
-        proto_evolved.phones.push(evolved::person::PhoneNumber {
-            number: format!("555-{}", 1000 + i),
-            r#type: phone_type,
-            is_primary: phone_type == 0, // Make MOBILE phones primary
-        });
-    }
-    
-    // Add addresses with the new additional_info field
-    for i in 0..std::cmp::max(1, size / 2) {
-        proto_evolved.addresses.push(evolved::person::Address {
-            street: format!("{} Main St", 100 + i),
-            city: format!("City {}", i),
-            state: format!("State {}", i),
-            zip: format!("{}", 10000 + i),
-            country: "Country".to_string(),
-            additional_info: "Extra address details".to_string(),
-        });
-    }
-    
-    // Add metadata
-    for i in 0..size {
-        let key = format!("key{}", i);
-        let value = format!("value{}", i);
-        proto_evolved.metadata.insert(key, value);
-    }
 
     (json_evolved, proto_evolved)
 }
\ No newline at end of file